@@ -74,3 +74,18 @@ pub use ricq::structs::MusicVersion;
 /// # }
 /// ```
 pub use chocho_client::structs::AudioCodeC;
+
+/// 戳一戳的类型。
+pub use chocho_client::structs::PokeType;
+
+/// 消息发送的目标会话。
+pub use chocho_client::structs::Target;
+
+/// 已发送的消息。
+pub use chocho_client::structs::SentMessage;
+
+/// 当前登录账号的信息。
+pub use chocho_client::structs::AccountInfo;
+
+/// 性别。
+pub use chocho_client::structs::Gender;
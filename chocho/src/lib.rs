@@ -20,8 +20,8 @@
 //!
 //! #[chocho::main(handler = Handler)]
 //! async fn main(client: RQClient) {
-//!     let account_info = client.account_info.read().await;
-//!     tracing::info!("{:?}", account_info);
+//!     let account = client.account().await;
+//!     tracing::info!("{:?}", account);
 //! }
 //! ```
 #![deny(missing_docs)]
@@ -29,9 +29,17 @@
 pub mod common;
 pub mod lifespan;
 pub mod prelude;
+pub mod utils;
 
+pub use chocho_client as client;
 pub use chocho_client::{ClientExt, RQClient};
-pub use chocho_login::{login, LoginMethod, QSignClient, RQProtocol};
+pub use chocho_event as event;
+pub use chocho_event::Event;
+pub use chocho_login::layout;
+pub use chocho_login::{
+    login, login_with_credential, login_with_device, AccountManager, Credential, LoginMethod,
+    LoginOptions, MultiQSignClient, ProtocolName, QSignClient, RQProtocol,
+};
 pub use chocho_macros::main;
 pub use chocho_msg::{Message, RQElem};
 pub use lifespan::finalizer;
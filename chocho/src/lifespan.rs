@@ -1,13 +1,21 @@
 //! 生命周期管理。
 
-use std::{future::Future, pin::Pin};
+use std::{
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 
 type Finalizer = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
 
-static FINALIZERS: Lazy<Mutex<Vec<Finalizer>>> = Lazy::new(|| Mutex::new(vec![]));
+static FINALIZERS: Lazy<Mutex<Vec<(Option<String>, Finalizer)>>> = Lazy::new(|| Mutex::new(vec![]));
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
 
 /// 注册一个生命周期结束时执行的函数。
 pub fn finalizer<Fut>(f: impl FnOnce() -> Fut + Send + 'static)
@@ -15,7 +23,70 @@ where
     Fut: Future<Output = ()> + Send + 'static,
 {
     let mut finalizers = FINALIZERS.lock().expect("Failed locking FINALIZERS");
-    finalizers.push(Box::new(move || Box::pin(f())));
+    finalizers.push((None, Box::new(move || Box::pin(f()))));
+}
+
+/// 注册一个带名字的、生命周期结束时执行的函数。
+///
+/// 执行时会用 `tracing` 记录名字和耗时，便于排查卡住的清理函数。
+/// 同名的函数可以注册多次，每个都会执行；如需只保留最后注册的一个，使用 [`finalizer_replace`]。
+///
+/// # Examples
+///
+/// ```no_run
+/// # use chocho::prelude::*;
+/// #[chocho::main]
+/// async fn main(client: RQClient) {
+///     chocho::lifespan::finalizer_named("保存状态", || async {
+///         let _ = chocho::tokio::fs::write("./data/state.json", "{}").await;
+///     });
+/// }
+/// ```
+pub fn finalizer_named<Fut>(name: &str, f: impl FnOnce() -> Fut + Send + 'static)
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut finalizers = FINALIZERS.lock().expect("Failed locking FINALIZERS");
+    finalizers.push((Some(name.to_string()), Box::new(move || Box::pin(f()))));
+}
+
+/// 注册一个带名字的、生命周期结束时执行的函数，并移除之前注册的同名函数。
+///
+/// 适用于可能被重复执行的注册代码（例如热重载），保证同名的清理只执行一次。
+pub fn finalizer_replace<Fut>(name: &str, f: impl FnOnce() -> Fut + Send + 'static)
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut finalizers = FINALIZERS.lock().expect("Failed locking FINALIZERS");
+    finalizers.retain(|(existing, _)| existing.as_deref() != Some(name));
+    finalizers.push((Some(name.to_string()), Box::new(move || Box::pin(f()))));
+}
+
+/// 注册一个生命周期结束时执行的、可能失败的函数。
+///
+/// 函数返回错误时，会用 `tracing` 记录错误，并继续执行其它函数。
+///
+/// # Examples
+///
+/// ```no_run
+/// # use chocho::prelude::*;
+/// #[chocho::main]
+/// async fn main(client: RQClient) {
+///     chocho::lifespan::finalizer_fallible(|| async {
+///         chocho::tokio::fs::write("./data/state.json", "{}").await
+///     });
+/// }
+/// ```
+pub fn finalizer_fallible<Fut, E>(f: impl FnOnce() -> Fut + Send + 'static)
+where
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Display,
+{
+    finalizer(move || async move {
+        if let Err(e) = f().await {
+            tracing::error!("退出清理失败: {}", e);
+        }
+    });
 }
 
 /// 执行所有注册的生命周期结束时执行的函数。
@@ -26,7 +97,40 @@ pub async fn do_finalize() {
         let mut finalizers = FINALIZERS.lock().expect("Failed locking FINALIZERS");
         finalizers.drain(..).rev().collect::<Vec<_>>()
     };
-    for f in finalizers {
-        f().await;
+    for (name, f) in finalizers {
+        match name {
+            Some(name) => {
+                tracing::debug!("执行退出清理: {}", name);
+                let start = Instant::now();
+                f().await;
+                tracing::info!("退出清理 {} 完成，耗时 {:?}", name, start.elapsed());
+            }
+            None => f().await,
+        }
+    }
+}
+
+/// 收到退出信号时调用，执行所有退出清理后退出进程。
+///
+/// - 第一次调用时开始优雅关闭：执行 [`do_finalize`]，完成后以状态码 0 退出；
+/// - 退出清理超过 `timeout` 仍未完成时，以状态码 1 强制退出；`timeout` 为 `None` 时不限制时间；
+/// - 优雅关闭期间再次调用（例如再次按下 Ctrl-C）时，立即以状态码 1 强制退出。
+///
+/// `#[chocho::main]` 会在收到 `shutdown_signals` 中的信号时调用此函数，超时时间由 `shutdown_timeout` 指定。
+pub async fn shutdown(timeout: Option<Duration>) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        tracing::warn!("再次收到退出信号，强制退出");
+        std::process::exit(1);
+    }
+    tracing::info!("收到退出信号，正在执行退出清理，再次发送退出信号可强制退出");
+    match timeout {
+        Some(timeout) => {
+            if tokio::time::timeout(timeout, do_finalize()).await.is_err() {
+                tracing::error!("退出清理超过 {:?} 仍未完成，强制退出", timeout);
+                std::process::exit(1);
+            }
+        }
+        None => do_finalize().await,
     }
+    std::process::exit(0);
 }
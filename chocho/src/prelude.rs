@@ -6,5 +6,6 @@
 //! use chocho::prelude::*;
 //! ```
 
+pub use crate::event::WaitExt;
 pub use crate::{ClientExt, Message, RQClient, RQElem};
 pub use ricq::RQResult;
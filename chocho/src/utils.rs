@@ -0,0 +1,3 @@
+//! 工具函数。
+
+pub use chocho_client::utils::*;
@@ -0,0 +1,40 @@
+//! 当前登录账号的信息。
+//!
+//! ricq 的 [`Client::uin`] 是异步方法，不便在同步代码中使用。登录成功后调用 [`cache_self_uin`] 缓存 QQ 号，
+//! 之后即可通过 [`ClientExt::self_uin`](crate::ClientExt::self_uin) 同步获取。
+//!
+//! `#[chocho::main]` 会在登录成功后自动缓存；`chocho::event::Dispatcher` 也会在把消息事件交给事件处理器之前缓存，
+//! 因此在 `PartlyHandler` 中可以直接使用。
+
+use std::sync::Arc;
+
+use ricq::Client;
+
+use crate::state;
+
+/// 缓存的 QQ 号。
+struct SelfUin(i64);
+
+/// 缓存客户端当前登录的 QQ 号，并返回该 QQ 号。
+///
+/// 已经缓存过时不会再次读取。缓存随客户端释放而清除。
+pub async fn cache_self_uin(client: &Arc<Client>) -> i64 {
+    if let Some(uin) = cached_self_uin(client) {
+        return uin;
+    }
+    let uin = client.uin().await;
+    state::insert(client, SelfUin(uin));
+    uin
+}
+
+pub(crate) fn cached_self_uin(client: &Client) -> Option<i64> {
+    state::get::<SelfUin>(client).map(|uin| uin.0)
+}
+
+/// 客户端当前登录的 QQ 号，未缓存时从客户端读取。
+pub(crate) async fn self_uin(client: &Client) -> i64 {
+    match cached_self_uin(client) {
+        Some(uin) => uin,
+        None => client.uin().await,
+    }
+}
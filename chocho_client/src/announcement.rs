@@ -0,0 +1,325 @@
+//! 群公告。
+//!
+//! ricq 只封装了设置纯文本群公告（memo）的请求，不支持图片、置顶等选项，也不能获取已发布的公告。
+//! 这里使用 `web.qun.qq.com` 的网页接口发布和获取公告，所需的 cookie 由客户端的登录凭证生成。
+
+use ricq::{Client, RQError, RQResult};
+use serde_json::Value;
+
+use crate::image;
+
+const UPLOAD_IMAGE_URL: &str = "https://web.qun.qq.com/cgi-bin/announce/upload_img";
+const ADD_NOTICE_URL: &str = "https://web.qun.qq.com/cgi-bin/announce/add_qun_notice";
+const LIST_NOTICE_URL: &str = "https://web.qun.qq.com/cgi-bin/announce/get_t_list";
+
+/// 获取群公告时单次请求的数量。
+const LIST_NOTICE_COUNT: usize = 20;
+
+/// 群公告。
+///
+/// # Examples
+///
+/// ```no_run
+/// use chocho::prelude::*;
+/// use chocho::client::announcement::GroupAnnouncement;
+///
+/// # async fn _f(client: RQClient) -> anyhow::Result<()> {
+/// let image = tokio::fs::read("./poster.png").await?;
+/// let announcement = GroupAnnouncement::new("本周六晚八点线上活动，欢迎参加！")
+///     .title("活动预告")
+///     .image(image)
+///     .pinned(true);
+/// client.group(12345678).publish_announcement(announcement).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupAnnouncement {
+    /// 标题。网页接口不区分标题与正文，标题会作为正文的第一行。
+    pub title: Option<String>,
+    /// 正文。
+    pub content: String,
+    /// 图片数据。
+    pub image: Option<Vec<u8>>,
+    /// 是否置顶。
+    pub pinned: bool,
+    /// 是否提示群成员修改群名片。
+    pub show_edit_card: bool,
+    /// 是否以弹窗形式展示。
+    pub popup: bool,
+    /// 是否需要群成员确认。
+    pub confirm_required: bool,
+}
+
+impl GroupAnnouncement {
+    /// 创建只有正文的群公告。
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 设置标题。
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// 设置图片。
+    pub fn image(mut self, image: impl Into<Vec<u8>>) -> Self {
+        self.image = Some(image.into());
+        self
+    }
+
+    /// 设置是否置顶。
+    pub fn pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    /// 设置是否提示群成员修改群名片。
+    pub fn show_edit_card(mut self, show_edit_card: bool) -> Self {
+        self.show_edit_card = show_edit_card;
+        self
+    }
+
+    /// 设置是否以弹窗形式展示。
+    pub fn popup(mut self, popup: bool) -> Self {
+        self.popup = popup;
+        self
+    }
+
+    /// 设置是否需要群成员确认。
+    pub fn confirm_required(mut self, confirm_required: bool) -> Self {
+        self.confirm_required = confirm_required;
+        self
+    }
+
+    fn text(&self) -> String {
+        match &self.title {
+            Some(title) if !title.is_empty() => format!("{}\n{}", title, self.content),
+            _ => self.content.clone(),
+        }
+    }
+}
+
+/// 已发布的群公告，由 [`Group::get_announcements`](crate::group::Group::get_announcements) 获取。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// 公告 ID。
+    pub id: String,
+    /// 发布者 QQ 号。
+    pub sender_uin: i64,
+    /// 发布时间，秒级 Unix 时间戳。
+    pub publish_time: i64,
+    /// 正文，包含标题。
+    pub content: String,
+    /// 是否置顶。
+    pub pinned: bool,
+}
+
+/// 公告图片上传后的信息。
+struct UploadedImage {
+    id: String,
+    width: String,
+    height: String,
+}
+
+/// 网页接口的登录凭证。
+pub(crate) struct WebCredential {
+    pub(crate) cookie: String,
+    pub(crate) bkn: i64,
+}
+
+impl WebCredential {
+    pub(crate) async fn new(client: &Client) -> Self {
+        let uin = client.uin().await;
+        let engine = client.engine.read().await;
+        let sig = &engine.transport.sig;
+        let skey = String::from_utf8_lossy(&sig.s_key).into_owned();
+        let mut cookie = format!("uin=o{uin}; skey={skey}; p_uin=o{uin}");
+        if let Some(p_skey) = sig.ps_key_map.get("qun.qq.com") {
+            cookie.push_str(&format!("; p_skey={}", String::from_utf8_lossy(p_skey)));
+        }
+        Self {
+            cookie,
+            bkn: bkn(&skey),
+        }
+    }
+}
+
+/// 由 skey 计算 CSRF token。
+fn bkn(skey: &str) -> i64 {
+    let mut hash: i64 = 5381;
+    for c in skey.bytes() {
+        hash += (hash << 5) + c as i64;
+    }
+    hash & 0x7fffffff
+}
+
+/// 发布群公告。
+///
+/// 带有图片时先上传图片，图片上传失败时不会发布公告。
+pub(crate) async fn publish(
+    client: &Client,
+    code: i64,
+    announcement: GroupAnnouncement,
+) -> RQResult<()> {
+    let credential = WebCredential::new(client).await;
+    let http = reqwest::Client::new();
+
+    let image = match announcement.image.clone() {
+        Some(data) => {
+            let data = image::check_image(data, "群公告图片")?;
+            let image = upload_image(&http, &credential, data)
+                .await
+                .map_err(|e| RQError::Other(format!("上传群公告图片失败，公告未发布：{}", e)))?;
+            Some(image)
+        }
+        None => None,
+    };
+
+    let settings = serde_json::json!({
+        "is_show_edit_card": announcement.show_edit_card as u8,
+        "tip_window_type": if announcement.popup { 0 } else { 1 },
+        "confirm_required": announcement.confirm_required as u8,
+    });
+    let mut form = vec![
+        ("qid", code.to_string()),
+        ("bkn", credential.bkn.to_string()),
+        ("text", announcement.text()),
+        ("pinned", (announcement.pinned as u8).to_string()),
+        ("type", "1".to_string()),
+        ("settings", settings.to_string()),
+    ];
+    if let Some(image) = image {
+        form.push(("pic", image.id));
+        form.push(("imgWidth", image.width));
+        form.push(("imgHeight", image.height));
+    }
+
+    let resp = http
+        .post(ADD_NOTICE_URL)
+        .query(&[("bkn", credential.bkn)])
+        .header(reqwest::header::COOKIE, &credential.cookie)
+        .form(&form)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| RQError::Other(format!("发布群公告失败：{}", e)))?;
+    let body = parse_response(resp).await?;
+    check(&body, "发布群公告失败")
+}
+
+/// 获取最近的群公告，置顶的公告排在前面。
+pub(crate) async fn list(client: &Client, code: i64) -> RQResult<Vec<Announcement>> {
+    let credential = WebCredential::new(client).await;
+    let resp = reqwest::Client::new()
+        .get(LIST_NOTICE_URL)
+        .query(&[
+            ("bkn", credential.bkn.to_string()),
+            ("qid", code.to_string()),
+            ("ft", "23".to_string()),
+            ("s", "-1".to_string()),
+            ("n", LIST_NOTICE_COUNT.to_string()),
+            ("ni", "1".to_string()),
+            ("i", "1".to_string()),
+        ])
+        .header(reqwest::header::COOKIE, &credential.cookie)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| RQError::Other(format!("获取群公告失败：{}", e)))?;
+    let body = parse_response(resp).await?;
+    check(&body, "获取群公告失败")?;
+
+    // 置顶的公告在 `inst` 中，其余在 `feeds` 中
+    let mut announcements = vec![];
+    for (key, pinned) in [("inst", true), ("feeds", false)] {
+        for feed in body[key].as_array().into_iter().flatten() {
+            announcements.push(Announcement {
+                id: feed["fid"].as_str().unwrap_or_default().to_string(),
+                sender_uin: feed["u"].as_i64().unwrap_or_default(),
+                publish_time: feed["pubt"].as_i64().unwrap_or_default(),
+                content: unescape(feed["msg"]["text"].as_str().unwrap_or_default()),
+                pinned,
+            });
+        }
+    }
+    Ok(announcements)
+}
+
+/// 还原网页接口返回的 HTML 转义字符。
+fn unescape(text: &str) -> String {
+    text.replace("&#10;", "\n")
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+async fn upload_image(
+    http: &reqwest::Client,
+    credential: &WebCredential,
+    data: Vec<u8>,
+) -> RQResult<UploadedImage> {
+    let part = reqwest::multipart::Part::bytes(data)
+        .file_name("image.png")
+        .mime_str("image/png")
+        .map_err(|e| RQError::Other(e.to_string()))?;
+    let form = reqwest::multipart::Form::new()
+        .text("bkn", credential.bkn.to_string())
+        .text("source", "troopNotice")
+        .text("m", "0")
+        .part("pic_up", part);
+    let resp = http
+        .post(UPLOAD_IMAGE_URL)
+        .header(reqwest::header::COOKIE, &credential.cookie)
+        .multipart(form)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| RQError::Other(e.to_string()))?;
+    let body = parse_response(resp).await?;
+    check(&body, "服务器拒绝了图片")?;
+
+    // `id` 字段是转义后的 JSON 字符串
+    let info = body["id"]
+        .as_str()
+        .map(|id| id.replace("&quot;", "\""))
+        .and_then(|id| serde_json::from_str::<Value>(&id).ok())
+        .ok_or_else(|| RQError::Other("图片上传响应缺少 id".to_string()))?;
+    let field = |name: &str| match &info[name] {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    };
+    Ok(UploadedImage {
+        id: field("id"),
+        width: field("w"),
+        height: field("h"),
+    })
+}
+
+pub(crate) async fn parse_response(resp: reqwest::Response) -> RQResult<Value> {
+    let body = resp
+        .bytes()
+        .await
+        .map_err(|e| RQError::Other(format!("读取响应失败：{}", e)))?;
+    serde_json::from_slice(&body).map_err(|e| RQError::Other(format!("响应解析失败：{}", e)))
+}
+
+fn check(body: &Value, context: &str) -> RQResult<()> {
+    match body["ec"].as_i64() {
+        Some(0) => Ok(()),
+        code => Err(RQError::Other(format!(
+            "{}（{}）：{}",
+            context,
+            code.unwrap_or(-1),
+            body["em"].as_str().unwrap_or_default()
+        ))),
+    }
+}
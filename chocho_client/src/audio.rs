@@ -0,0 +1,196 @@
+//! 语音编码。
+//!
+//! 将 PCM 或 WAV 音频编码为 QQ 语音使用的 SILK 格式，需要 `audio` 特性。
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chocho::prelude::*;
+//! use chocho_client::audio::AudioInput;
+//!
+//! # async fn _f(client: RQClient) -> RQResult<()> {
+//! let group = client.group(87654321);
+//! let audio = group.upload_audio_auto(AudioInput::file("./hello.wav")).await?;
+//! group.send_audio(audio).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{path::PathBuf, time::Duration};
+
+use ricq::{RQError, RQResult};
+
+use crate::structs::AudioCodeC;
+
+/// 编码 SILK 时使用的码率。
+const SILK_BIT_RATE: i32 = 24000;
+
+/// 待编码的音频。
+#[derive(Debug, Clone)]
+pub enum AudioInput {
+    /// 单声道、16 位小端序的 PCM 数据。
+    Pcm {
+        /// PCM 数据。
+        data: Vec<u8>,
+        /// 采样率。
+        sample_rate: u32,
+    },
+    /// WAV 文件的内容，需要是 16 位 PCM 编码，多声道会被混合为单声道。
+    Wav(Vec<u8>),
+    /// 音频文件的路径。
+    ///
+    /// 支持 WAV 文件，以及已经编码好的 SILK 和 AMR 文件（不会重新编码）。
+    File(PathBuf),
+}
+
+impl AudioInput {
+    /// 从文件路径创建。
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        AudioInput::File(path.into())
+    }
+}
+
+/// 编码后的语音。
+pub struct EncodedAudio {
+    /// 编码后的数据。
+    pub data: Vec<u8>,
+    /// 编码方式。
+    pub codec: AudioCodeC,
+    /// 语音时长。
+    pub duration: Duration,
+}
+
+/// 将音频编码为 QQ 语音可用的格式。
+///
+/// 已经是 SILK 或 AMR 格式的文件会原样返回。
+pub async fn encode(input: AudioInput) -> RQResult<EncodedAudio> {
+    let (pcm, sample_rate) = match input {
+        AudioInput::Pcm { data, sample_rate } => (data, sample_rate),
+        AudioInput::Wav(data) => parse_wav(&data)?,
+        AudioInput::File(path) => {
+            let data = tokio::fs::read(&path).await?;
+            if let Some(duration) = silk_duration(&data) {
+                return Ok(EncodedAudio {
+                    data,
+                    codec: AudioCodeC::Silk,
+                    duration,
+                });
+            }
+            if let Some(duration) = amr_duration(&data) {
+                return Ok(EncodedAudio {
+                    data,
+                    codec: AudioCodeC::Amr,
+                    duration,
+                });
+            }
+            parse_wav(&data)?
+        }
+    };
+    if sample_rate == 0 {
+        return Err(RQError::Other("编码语音失败：采样率为 0".to_string()));
+    }
+    let duration = Duration::from_secs_f64(pcm.len() as f64 / 2.0 / sample_rate as f64);
+    let silk = tokio::task::spawn_blocking(move || {
+        silk_rs::encode_silk(pcm, sample_rate as i32, SILK_BIT_RATE, true)
+    })
+    .await
+    .map_err(|e| RQError::Other(format!("编码语音失败：{}", e)))?
+    .map_err(|e| RQError::Other(format!("编码语音失败：{:?}", e)))?;
+    Ok(EncodedAudio {
+        data: silk,
+        codec: AudioCodeC::Silk,
+        duration,
+    })
+}
+
+/// 每一帧 SILK 或 AMR 数据的时长。
+const FRAME_DURATION: Duration = Duration::from_millis(20);
+
+/// 计算 SILK 数据的时长，不是 SILK 格式时返回 `None`。
+fn silk_duration(data: &[u8]) -> Option<Duration> {
+    let data = data.strip_prefix(b"\x02").unwrap_or(data);
+    let mut rest = data.strip_prefix(b"#!SILK_V3")?;
+    let mut frames = 0;
+    while rest.len() >= 2 {
+        let size = i16::from_le_bytes([rest[0], rest[1]]);
+        // 长度为负数表示结束
+        if size < 0 || rest.len() < 2 + size as usize {
+            break;
+        }
+        rest = &rest[2 + size as usize..];
+        frames += 1;
+    }
+    Some(FRAME_DURATION * frames)
+}
+
+/// 计算 AMR-NB 数据的时长，不是 AMR 格式时返回 `None`。
+fn amr_duration(data: &[u8]) -> Option<Duration> {
+    // 各模式下每帧的字节数（不含帧头）
+    const FRAME_SIZES: [usize; 16] = [12, 13, 15, 17, 19, 20, 26, 31, 5, 0, 0, 0, 0, 0, 0, 0];
+    let mut rest = data.strip_prefix(b"#!AMR\n")?;
+    let mut frames = 0;
+    while let Some(&header) = rest.first() {
+        let size = FRAME_SIZES[((header >> 3) & 0x0f) as usize];
+        if rest.len() < 1 + size {
+            break;
+        }
+        rest = &rest[1 + size..];
+        frames += 1;
+    }
+    Some(FRAME_DURATION * frames)
+}
+
+/// 解析 WAV 文件，返回单声道 PCM 数据和采样率。
+fn parse_wav(data: &[u8]) -> RQResult<(Vec<u8>, u32)> {
+    let invalid = |reason: &str| RQError::Other(format!("不支持的 WAV 文件：{}", reason));
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(invalid("文件头错误"));
+    }
+
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let body = &data[pos + 8..data.len().min(pos + 8 + size)];
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let audio_format = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]) as usize;
+                let sample_rate = u32::from_le_bytes(body[4..8].try_into().unwrap());
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                if audio_format != 1 || bits != 16 {
+                    return Err(invalid("仅支持 16 位 PCM 编码"));
+                }
+                if channels == 0 {
+                    return Err(invalid("声道数为 0"));
+                }
+                format = Some((channels, sample_rate));
+            }
+            b"data" => {
+                let (channels, sample_rate) = format.ok_or_else(|| invalid("缺少 fmt 块"))?;
+                return Ok((downmix(body, channels), sample_rate));
+            }
+            _ => {}
+        }
+        // 块按偶数字节对齐
+        pos += 8 + size + size % 2;
+    }
+    Err(invalid("缺少 data 块"))
+}
+
+/// 将多声道 PCM 混合为单声道。
+fn downmix(pcm: &[u8], channels: usize) -> Vec<u8> {
+    if channels == 1 {
+        return pcm.to_vec();
+    }
+    pcm.chunks_exact(2 * channels)
+        .flat_map(|frame| {
+            let sum: i32 = frame
+                .chunks_exact(2)
+                .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as i32)
+                .sum();
+            ((sum / channels as i32) as i16).to_le_bytes()
+        })
+        .collect()
+}
@@ -0,0 +1,101 @@
+//! 群精华消息。
+//!
+//! ricq 没有封装精华消息的请求，这里使用 `qun.qq.com` 的网页接口获取精华消息列表，
+//! 所需的 cookie 由客户端的登录凭证生成，参见 [`announcement`](crate::announcement)。
+
+use ricq::{Client, RQError, RQResult};
+use serde_json::Value;
+
+use crate::announcement::{parse_response, WebCredential};
+
+const DIGEST_LIST_URL: &str = "https://qun.qq.com/cgi-bin/group_digest/digest_list";
+
+/// 获取精华消息时每页的数量。
+const PAGE_LIMIT: usize = 20;
+
+/// 获取精华消息时最多请求的页数，避免服务器一直不返回结束标记时无限请求。
+const MAX_PAGES: usize = 50;
+
+/// 群精华消息，由 [`Group::get_essence_list`](crate::group::Group::get_essence_list) 获取。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EssenceMessage {
+    /// 消息序号。
+    pub msg_seq: i32,
+    /// 消息随机数。
+    pub msg_rand: i32,
+    /// 消息发送者。
+    pub sender_uin: i64,
+    /// 消息发送者的昵称。
+    pub sender_nick: String,
+    /// 消息发送时间，秒级 Unix 时间戳。
+    pub sender_time: i64,
+    /// 设为精华的操作者。
+    pub operator_uin: i64,
+    /// 设为精华的操作者的昵称。
+    pub operator_nick: String,
+    /// 设为精华的时间，秒级 Unix 时间戳。
+    pub operator_time: i64,
+}
+
+/// 获取群的所有精华消息。
+pub(crate) async fn list(client: &Client, code: i64) -> RQResult<Vec<EssenceMessage>> {
+    let credential = WebCredential::new(client).await;
+    let http = reqwest::Client::new();
+    let mut messages = vec![];
+    for page in 0..MAX_PAGES {
+        let resp = http
+            .get(DIGEST_LIST_URL)
+            .query(&[
+                ("bkn", credential.bkn.to_string()),
+                ("group_code", code.to_string()),
+                ("page_start", page.to_string()),
+                ("page_limit", PAGE_LIMIT.to_string()),
+            ])
+            .header(reqwest::header::COOKIE, &credential.cookie)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .map_err(|e| RQError::Other(format!("获取精华消息失败：{}", e)))?;
+        let body = parse_response(resp).await?;
+        if body["retcode"].as_i64() != Some(0) {
+            return Err(RQError::Other(format!(
+                "获取精华消息失败（{}）：{}",
+                body["retcode"].as_i64().unwrap_or(-1),
+                body["retmsg"].as_str().unwrap_or_default()
+            )));
+        }
+
+        let data = &body["data"];
+        let list = data["msg_list"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        messages.extend(list.iter().map(|msg| {
+            EssenceMessage {
+                msg_seq: int(&msg["msg_seq"]) as i32,
+                msg_rand: int(&msg["msg_random"]) as i32,
+                sender_uin: int(&msg["sender_uin"]),
+                sender_nick: msg["sender_nick"].as_str().unwrap_or_default().to_string(),
+                sender_time: int(&msg["sender_time"]),
+                operator_uin: int(&msg["add_digest_uin"]),
+                operator_nick: msg["add_digest_nick"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                operator_time: int(&msg["add_digest_time"]),
+            }
+        }));
+        if list.is_empty() || data["is_end"].as_bool().unwrap_or(true) {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+/// 网页接口中的整数有时以字符串形式返回。
+fn int(value: &Value) -> i64 {
+    match value {
+        Value::String(s) => s.parse().unwrap_or_default(),
+        value => value.as_i64().unwrap_or_default(),
+    }
+}
@@ -0,0 +1,549 @@
+//! 群文件。
+//!
+//! ricq 没有封装群文件系统，这里直接构造 `OidbSvc.0x6d6`（上传、下载）和 `OidbSvc.0x6d8`（列表）请求。
+
+use bytes::Bytes;
+use prost::Message as _;
+use ricq::{Client, RQError, RQResult};
+use ricq_core::highway::BdhInput;
+
+/// 根目录的 ID。
+pub const ROOT_FOLDER: &str = "/";
+
+const APP_ID: u32 = 3;
+const BUS_ID: u32 = 102;
+
+/// 群文件。
+#[derive(Debug, Clone)]
+pub struct GroupFile {
+    /// 文件 ID。
+    pub file_id: String,
+    /// 文件名。
+    pub name: String,
+    /// 文件大小（字节）。
+    pub size: u64,
+    /// 业务 ID，下载时需要。
+    pub bus_id: u32,
+    /// 所在目录的 ID。
+    pub folder_id: String,
+    /// 上传时间（秒级时间戳）。
+    pub upload_time: u32,
+    /// 过期时间（秒级时间戳），永久文件为 0。
+    pub dead_time: u32,
+    /// 下载次数。
+    pub download_times: u32,
+    /// 上传者 QQ 号。
+    pub uploader_uin: i64,
+    /// 上传者昵称。
+    pub uploader_name: String,
+}
+
+/// 群文件夹。
+#[derive(Debug, Clone)]
+pub struct GroupFolder {
+    /// 文件夹 ID。
+    pub folder_id: String,
+    /// 文件夹名。
+    pub name: String,
+    /// 创建时间（秒级时间戳）。
+    pub create_time: u32,
+    /// 创建者 QQ 号。
+    pub creator_uin: i64,
+    /// 文件夹中的文件数。
+    pub file_count: u32,
+}
+
+/// 获取目录下的文件和文件夹。
+pub(crate) async fn list(
+    client: &Client,
+    code: i64,
+    folder_id: &str,
+) -> RQResult<(Vec<GroupFile>, Vec<GroupFolder>)> {
+    let mut files = Vec::new();
+    let mut folders = Vec::new();
+    let mut start_index = 0;
+    loop {
+        let req = pb::D6d8ReqBody {
+            file_list_info_req: Some(pb::GetFileListReqBody {
+                group_code: Some(code as u64),
+                app_id: Some(APP_ID),
+                folder_id: Some(folder_id.to_string()),
+                file_count: Some(20),
+                all_file_count: Some(0),
+                req_from: Some(3),
+                sort_by: Some(1),
+                start_index: Some(start_index),
+                ..Default::default()
+            }),
+        };
+        let rsp: pb::D6d8RspBody = oidb(client, 0x6d8, 1, req).await?;
+        let rsp = rsp
+            .file_list_info_rsp
+            .ok_or(RQError::EmptyField("file_list_info_rsp"))?;
+        check(rsp.ret_code, rsp.ret_msg)?;
+        start_index += rsp.item_list.len() as u32;
+        for item in rsp.item_list {
+            match (item.r#type, item.file_info, item.folder_info) {
+                (Some(1), Some(file), _) => files.push(GroupFile {
+                    file_id: file.file_id.unwrap_or_default(),
+                    name: file.file_name.unwrap_or_default(),
+                    size: file.file_size.unwrap_or_default(),
+                    bus_id: file.bus_id.unwrap_or_default(),
+                    folder_id: file.parent_folder_id.unwrap_or_default(),
+                    upload_time: file.upload_time.unwrap_or_default(),
+                    dead_time: file.dead_time.unwrap_or_default(),
+                    download_times: file.download_times.unwrap_or_default(),
+                    uploader_uin: file.uploader_uin.unwrap_or_default() as i64,
+                    uploader_name: file.uploader_name.unwrap_or_default(),
+                }),
+                (Some(2), _, Some(folder)) => folders.push(GroupFolder {
+                    folder_id: folder.folder_id.unwrap_or_default(),
+                    name: folder.folder_name.unwrap_or_default(),
+                    create_time: folder.create_time.unwrap_or_default(),
+                    creator_uin: folder.create_uin.unwrap_or_default() as i64,
+                    file_count: folder.total_file_count.unwrap_or_default(),
+                }),
+                _ => {}
+            }
+        }
+        if rsp.is_end.unwrap_or(true) {
+            break;
+        }
+    }
+    Ok((files, folders))
+}
+
+/// 获取文件的下载链接。
+pub(crate) async fn url(client: &Client, code: i64, file_id: &str) -> RQResult<String> {
+    let req = pb::D6d6ReqBody {
+        download_file_req: Some(pb::DownloadFileReqBody {
+            group_code: Some(code as u64),
+            app_id: Some(APP_ID),
+            bus_id: Some(BUS_ID),
+            file_id: Some(file_id.to_string()),
+        }),
+        ..Default::default()
+    };
+    let rsp: pb::D6d6RspBody = oidb(client, 0x6d6, 2, req).await?;
+    let rsp = rsp
+        .download_file_rsp
+        .ok_or(RQError::EmptyField("download_file_rsp"))?;
+    check(rsp.ret_code, rsp.ret_msg)?;
+    Ok(format!(
+        "http://{}/ftn_handler/{}/",
+        rsp.download_ip.unwrap_or_default(),
+        hex::encode(rsp.download_url.unwrap_or_default())
+    ))
+}
+
+/// 上传文件，返回文件 ID。
+pub(crate) async fn upload(
+    client: &Client,
+    code: i64,
+    folder_id: &str,
+    name: &str,
+    data: &[u8],
+) -> RQResult<String> {
+    use sha1::Digest;
+
+    let md5 = md5::compute(data).to_vec();
+    let sha1 = sha1::Sha1::digest(data).to_vec();
+    let req = pb::D6d6ReqBody {
+        upload_file_req: Some(pb::UploadFileReqBody {
+            group_code: Some(code as u64),
+            app_id: Some(APP_ID),
+            bus_id: Some(BUS_ID),
+            entrance: Some(6),
+            parent_folder_id: Some(folder_id.to_string()),
+            file_name: Some(name.to_string()),
+            local_path: Some(format!("/storage/emulated/0/Pictures/files/s/{}", name)),
+            file_size: Some(data.len() as u64),
+            sha: Some(sha1.clone()),
+            md5: Some(md5.clone()),
+            support_multi_upload: Some(true),
+        }),
+        ..Default::default()
+    };
+    let rsp: pb::D6d6RspBody = oidb(client, 0x6d6, 0, req).await?;
+    let rsp = rsp
+        .upload_file_rsp
+        .ok_or(RQError::EmptyField("upload_file_rsp"))?;
+    check(rsp.ret_code, rsp.ret_msg)?;
+    let file_id = rsp.file_id.unwrap_or_default();
+    if rsp.bool_file_exist.unwrap_or_default() {
+        // 服务器上已有相同的文件，秒传
+        return Ok(file_id);
+    }
+
+    let addr = client
+        .highway_addrs
+        .read()
+        .await
+        .first()
+        .cloned()
+        .ok_or(RQError::EmptyField("highway_addrs"))?;
+    let ext = pb::GroupFileUploadExt {
+        unknown1: Some(100),
+        unknown2: Some(1),
+        entry: Some(pb::GroupFileUploadEntry {
+            business_head: Some(pb::ExcitingBusiHead {
+                bus_id: Some(BUS_ID as i32),
+                sender_uin: Some(client.uin().await),
+                receiver_uin: Some(code),
+                group_code: Some(code),
+            }),
+            client_info: Some(pb::ExcitingClientInfo {
+                client_type: Some(2),
+                app_id: Some(APP_ID.to_string()),
+                terminal_type: Some(2),
+                client_ver: Some("9e9c09dc".to_string()),
+                unknown: Some(4),
+            }),
+            file_entry: Some(pb::ExcitingFileEntry {
+                file_size: Some(data.len() as i64),
+                md5: Some(md5),
+                sha1: Some(sha1),
+                file_id: Some(file_id.clone().into_bytes()),
+                upload_key: rsp.check_key,
+            }),
+            file_name_info: Some(pb::ExcitingFileNameInfo {
+                file_name: Some(name.to_string()),
+            }),
+        }),
+        unknown3: Some(0),
+    };
+    client
+        .highway_upload_bdh(
+            addr,
+            BdhInput {
+                command_id: 71,
+                body: data.to_vec(),
+                ticket: client.highway_session.read().await.sig_session.to_vec(),
+                ext: ext.encode_to_vec(),
+                encrypt: false,
+                chunk_size: 256 * 1024,
+                send_echo: true,
+            },
+        )
+        .await?;
+    Ok(file_id)
+}
+
+/// 为同名文件生成新的文件名，如 `a.txt` 变为 `a(1).txt`。
+pub(crate) fn dedup_name(name: &str, exists: impl Fn(&str) -> bool) -> String {
+    if !exists(name) {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rfind('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|i| format!("{}({}){}", stem, i, ext))
+        .find(|name| !exists(name))
+        .unwrap()
+}
+
+fn check(ret_code: Option<i32>, ret_msg: Option<String>) -> RQResult<()> {
+    match ret_code.unwrap_or_default() {
+        0 => Ok(()),
+        code => Err(RQError::Other(format!(
+            "群文件操作失败（{}）：{}",
+            code,
+            ret_msg.unwrap_or_default()
+        ))),
+    }
+}
+
+async fn oidb<Req, Rsp>(client: &Client, command: u32, service_type: u32, req: Req) -> RQResult<Rsp>
+where
+    Req: prost::Message,
+    Rsp: prost::Message + Default,
+{
+    let pkg = pb::OidbSsoPkg {
+        command: Some(command),
+        service_type: Some(service_type),
+        result: None,
+        bodybuffer: Some(req.encode_to_vec()),
+        error_msg: None,
+        client_version: Some("android 8.9.58".to_string()),
+    };
+    let pkt = client.engine.read().await.uni_packet(
+        &format!("OidbSvc.0x{:x}_{}", command, service_type),
+        Bytes::from(pkg.encode_to_vec()),
+    );
+    let resp = client.send_and_wait(pkt).await?;
+    let pkg = pb::OidbSsoPkg::decode(resp.body).map_err(decode_error)?;
+    if pkg.result.unwrap_or_default() != 0 {
+        return Err(RQError::Other(format!(
+            "群文件操作失败（{}）：{}",
+            pkg.result.unwrap_or_default(),
+            pkg.error_msg.unwrap_or_default()
+        )));
+    }
+    Rsp::decode(&*pkg.bodybuffer.unwrap_or_default()).map_err(decode_error)
+}
+
+fn decode_error(e: prost::DecodeError) -> RQError {
+    RQError::Other(format!("群文件响应解析失败：{}", e))
+}
+
+mod pb {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct OidbSsoPkg {
+        #[prost(uint32, optional, tag = "1")]
+        pub command: Option<u32>,
+        #[prost(uint32, optional, tag = "2")]
+        pub service_type: Option<u32>,
+        #[prost(uint32, optional, tag = "3")]
+        pub result: Option<u32>,
+        #[prost(bytes = "vec", optional, tag = "4")]
+        pub bodybuffer: Option<Vec<u8>>,
+        #[prost(string, optional, tag = "5")]
+        pub error_msg: Option<String>,
+        #[prost(string, optional, tag = "6")]
+        pub client_version: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct D6d8ReqBody {
+        #[prost(message, optional, tag = "2")]
+        pub file_list_info_req: Option<GetFileListReqBody>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetFileListReqBody {
+        #[prost(uint64, optional, tag = "1")]
+        pub group_code: Option<u64>,
+        #[prost(uint32, optional, tag = "2")]
+        pub app_id: Option<u32>,
+        #[prost(string, optional, tag = "3")]
+        pub folder_id: Option<String>,
+        #[prost(uint32, optional, tag = "5")]
+        pub file_count: Option<u32>,
+        #[prost(uint32, optional, tag = "7")]
+        pub all_file_count: Option<u32>,
+        #[prost(uint32, optional, tag = "8")]
+        pub req_from: Option<u32>,
+        #[prost(uint32, optional, tag = "9")]
+        pub sort_by: Option<u32>,
+        #[prost(uint32, optional, tag = "13")]
+        pub start_index: Option<u32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct D6d8RspBody {
+        #[prost(message, optional, tag = "2")]
+        pub file_list_info_rsp: Option<GetFileListRspBody>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetFileListRspBody {
+        #[prost(int32, optional, tag = "1")]
+        pub ret_code: Option<i32>,
+        #[prost(string, optional, tag = "2")]
+        pub ret_msg: Option<String>,
+        #[prost(bool, optional, tag = "4")]
+        pub is_end: Option<bool>,
+        #[prost(message, repeated, tag = "5")]
+        pub item_list: Vec<GetFileListItem>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetFileListItem {
+        #[prost(uint32, optional, tag = "1")]
+        pub r#type: Option<u32>,
+        #[prost(message, optional, tag = "2")]
+        pub folder_info: Option<GroupFolderInfo>,
+        #[prost(message, optional, tag = "3")]
+        pub file_info: Option<GroupFileInfo>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GroupFolderInfo {
+        #[prost(string, optional, tag = "1")]
+        pub folder_id: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub folder_name: Option<String>,
+        #[prost(uint32, optional, tag = "4")]
+        pub create_time: Option<u32>,
+        #[prost(uint64, optional, tag = "6")]
+        pub create_uin: Option<u64>,
+        #[prost(uint32, optional, tag = "8")]
+        pub total_file_count: Option<u32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GroupFileInfo {
+        #[prost(string, optional, tag = "1")]
+        pub file_id: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        pub file_name: Option<String>,
+        #[prost(uint64, optional, tag = "3")]
+        pub file_size: Option<u64>,
+        #[prost(uint32, optional, tag = "4")]
+        pub bus_id: Option<u32>,
+        #[prost(uint32, optional, tag = "6")]
+        pub upload_time: Option<u32>,
+        #[prost(uint32, optional, tag = "7")]
+        pub dead_time: Option<u32>,
+        #[prost(uint32, optional, tag = "9")]
+        pub download_times: Option<u32>,
+        #[prost(string, optional, tag = "14")]
+        pub uploader_name: Option<String>,
+        #[prost(uint64, optional, tag = "15")]
+        pub uploader_uin: Option<u64>,
+        #[prost(string, optional, tag = "16")]
+        pub parent_folder_id: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct D6d6ReqBody {
+        #[prost(message, optional, tag = "1")]
+        pub upload_file_req: Option<UploadFileReqBody>,
+        #[prost(message, optional, tag = "3")]
+        pub download_file_req: Option<DownloadFileReqBody>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct UploadFileReqBody {
+        #[prost(uint64, optional, tag = "1")]
+        pub group_code: Option<u64>,
+        #[prost(uint32, optional, tag = "2")]
+        pub app_id: Option<u32>,
+        #[prost(uint32, optional, tag = "3")]
+        pub bus_id: Option<u32>,
+        #[prost(uint32, optional, tag = "4")]
+        pub entrance: Option<u32>,
+        #[prost(string, optional, tag = "5")]
+        pub parent_folder_id: Option<String>,
+        #[prost(string, optional, tag = "6")]
+        pub file_name: Option<String>,
+        #[prost(string, optional, tag = "7")]
+        pub local_path: Option<String>,
+        #[prost(uint64, optional, tag = "8")]
+        pub file_size: Option<u64>,
+        #[prost(bytes = "vec", optional, tag = "9")]
+        pub sha: Option<Vec<u8>>,
+        #[prost(bytes = "vec", optional, tag = "11")]
+        pub md5: Option<Vec<u8>>,
+        #[prost(bool, optional, tag = "15")]
+        pub support_multi_upload: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct DownloadFileReqBody {
+        #[prost(uint64, optional, tag = "1")]
+        pub group_code: Option<u64>,
+        #[prost(uint32, optional, tag = "2")]
+        pub app_id: Option<u32>,
+        #[prost(uint32, optional, tag = "3")]
+        pub bus_id: Option<u32>,
+        #[prost(string, optional, tag = "4")]
+        pub file_id: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct D6d6RspBody {
+        #[prost(message, optional, tag = "1")]
+        pub upload_file_rsp: Option<UploadFileRspBody>,
+        #[prost(message, optional, tag = "3")]
+        pub download_file_rsp: Option<DownloadFileRspBody>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct UploadFileRspBody {
+        #[prost(int32, optional, tag = "1")]
+        pub ret_code: Option<i32>,
+        #[prost(string, optional, tag = "2")]
+        pub ret_msg: Option<String>,
+        #[prost(string, optional, tag = "7")]
+        pub file_id: Option<String>,
+        #[prost(bytes = "vec", optional, tag = "8")]
+        pub check_key: Option<Vec<u8>>,
+        #[prost(bool, optional, tag = "10")]
+        pub bool_file_exist: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct DownloadFileRspBody {
+        #[prost(int32, optional, tag = "1")]
+        pub ret_code: Option<i32>,
+        #[prost(string, optional, tag = "2")]
+        pub ret_msg: Option<String>,
+        #[prost(string, optional, tag = "4")]
+        pub download_ip: Option<String>,
+        #[prost(bytes = "vec", optional, tag = "6")]
+        pub download_url: Option<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GroupFileUploadExt {
+        #[prost(int32, optional, tag = "1")]
+        pub unknown1: Option<i32>,
+        #[prost(int32, optional, tag = "2")]
+        pub unknown2: Option<i32>,
+        #[prost(int32, optional, tag = "3")]
+        pub unknown3: Option<i32>,
+        #[prost(message, optional, tag = "100")]
+        pub entry: Option<GroupFileUploadEntry>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GroupFileUploadEntry {
+        #[prost(message, optional, tag = "100")]
+        pub business_head: Option<ExcitingBusiHead>,
+        #[prost(message, optional, tag = "200")]
+        pub client_info: Option<ExcitingClientInfo>,
+        #[prost(message, optional, tag = "300")]
+        pub file_entry: Option<ExcitingFileEntry>,
+        #[prost(message, optional, tag = "400")]
+        pub file_name_info: Option<ExcitingFileNameInfo>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExcitingBusiHead {
+        #[prost(int32, optional, tag = "1")]
+        pub bus_id: Option<i32>,
+        #[prost(int64, optional, tag = "100")]
+        pub sender_uin: Option<i64>,
+        #[prost(int64, optional, tag = "200")]
+        pub receiver_uin: Option<i64>,
+        #[prost(int64, optional, tag = "400")]
+        pub group_code: Option<i64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExcitingClientInfo {
+        #[prost(int32, optional, tag = "100")]
+        pub client_type: Option<i32>,
+        #[prost(string, optional, tag = "200")]
+        pub app_id: Option<String>,
+        #[prost(int32, optional, tag = "300")]
+        pub terminal_type: Option<i32>,
+        #[prost(string, optional, tag = "400")]
+        pub client_ver: Option<String>,
+        #[prost(int32, optional, tag = "600")]
+        pub unknown: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExcitingFileEntry {
+        #[prost(int64, optional, tag = "100")]
+        pub file_size: Option<i64>,
+        #[prost(bytes = "vec", optional, tag = "200")]
+        pub md5: Option<Vec<u8>>,
+        #[prost(bytes = "vec", optional, tag = "300")]
+        pub sha1: Option<Vec<u8>>,
+        #[prost(bytes = "vec", optional, tag = "600")]
+        pub file_id: Option<Vec<u8>>,
+        #[prost(bytes = "vec", optional, tag = "700")]
+        pub upload_key: Option<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExcitingFileNameInfo {
+        #[prost(string, optional, tag = "100")]
+        pub file_name: Option<String>,
+    }
+}
@@ -14,29 +14,154 @@
 //! }
 //! ```
 
-use std::time::Duration;
+use std::{path::Path, time::Duration};
 
-use chocho_msg::{elem::FriendImage, Message};
+use chocho_msg::{
+    elem::{FriendImage, LightApp, RichMsg},
+    Message, RQElem,
+};
 use ricq::{
     structs::{FriendAudio, LinkShare, MessageReceipt, MusicShare, MusicVersion},
-    Client, RQResult,
+    RQResult,
+};
+
+use crate::{
+    image,
+    schedule::Priority,
+    sender::MessageSender,
+    structs::{PokeType, SentMessage, Target},
+    utils::{self, RetryPolicy},
+    ClientExt, RQClient,
 };
 
 /// 好友操作对象。
-pub struct Friend<'a> {
-    /// 客户端引用。
-    pub client: &'a Client,
+///
+/// `S` 为发送消息使用的 [`MessageSender`]，通过 [`ClientExt::friend`](crate::ClientExt::friend) 创建时为 [`RQClient`]。
+/// 发送消息的方法适用于任意的 `S`，因此可以在测试中使用 [`MockClient`](crate::sender::MockClient)；
+/// 其余方法需要 `S` 指向真实的客户端，即实现了 [`ClientExt`]，例如 `RQClient` 和 `&ricq::Client`。
+pub struct Friend<S = RQClient> {
+    /// 客户端。
+    pub client: S,
     /// 好友 QQ 号。
     pub uin: i64,
 }
 
-impl<'a> Friend<'a> {
+impl<S> Friend<S> {
+    /// 头像的 URL。
+    ///
+    /// 参见 [`GroupMember::avatar_url`](crate::group::GroupMember::avatar_url)。
+    pub fn avatar_url(&self, size: u32) -> String {
+        crate::avatar_url(self.uin, size)
+    }
+}
+
+impl<S: MessageSender + Clone> Friend<S> {
     /// 发送消息。
-    pub async fn send(&self, msg: impl Into<Message>) -> RQResult<MessageReceipt> {
+    ///
+    /// 返回的 [`SentMessage`] 可以直接撤回或引用回复，也可以转换为原始的 [`MessageReceipt`]。
+    pub async fn send(&self, msg: impl Into<Message>) -> RQResult<SentMessage<S>> {
+        self.send_with_priority(msg, Priority::Normal).await
+    }
+
+    /// 发送消息，临时性错误时按 `policy` 自动重试。
+    ///
+    /// 只有超时、网络错误等临时性错误会重试，被禁言、被风控等永久性错误会立即返回，参见
+    /// [`utils::is_retryable`](crate::utils::is_retryable)。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    /// use chocho::utils::RetryPolicy;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// client.friend(12345678)
+    ///     .send_retry("你好".to_string(), RetryPolicy::default())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_retry(
+        &self,
+        msg: impl Into<Message>,
+        policy: RetryPolicy,
+    ) -> RQResult<SentMessage<S>> {
+        let msg: Message = msg.into();
+        utils::retry_with_policy(policy, utils::is_retryable, || self.send(msg.clone())).await
+    }
+
+    /// 将消息切分为多段后依次发送。
+    ///
+    /// 参见 [`Group::send_chunked`](crate::group::Group::send_chunked)。
+    pub async fn send_chunked(
+        &self,
+        msg: impl Into<Message>,
+        max_len: usize,
+    ) -> RQResult<Vec<SentMessage<S>>> {
         let msg: Message = msg.into();
-        self.client.send_friend_message(self.uin, msg.into()).await
+        let mut sent = vec![];
+        for part in msg.split_by_len(max_len) {
+            sent.push(self.send(part).await?);
+        }
+        Ok(sent)
+    }
+
+    /// 以指定优先级发送消息。
+    ///
+    /// 设置了限流时，高优先级的消息会先于低优先级的消息发送，参见 [`schedule`](crate::schedule)。
+    ///
+    /// 消息中待上传的图片（[`Image`](chocho_msg::elem::Image)）会先上传为好友图片。
+    pub async fn send_with_priority(
+        &self,
+        msg: impl Into<Message>,
+        priority: Priority,
+    ) -> RQResult<SentMessage<S>> {
+        let target = Target::Friend(self.uin);
+        let receipt = self
+            .client
+            .send_message_with_priority(target, msg.into(), priority)
+            .await?;
+        Ok(SentMessage::new(self.client.clone(), target, receipt))
     }
 
+    /// 发送 XML 卡片消息。
+    ///
+    /// `service_id` 为卡片的服务 ID，`xml` 会原样发送。
+    pub async fn send_xml(
+        &self,
+        service_id: i32,
+        xml: impl Into<String>,
+    ) -> RQResult<SentMessage<S>> {
+        let msg: Message = [RQElem::RichMsg(RichMsg {
+            service_id,
+            template1: xml.into(),
+        })]
+        .into_iter()
+        .collect();
+        self.send(msg).await
+    }
+
+    /// 发送 JSON 卡片消息，如小程序分享。
+    ///
+    /// `json` 会原样发送，示例参见 [`Group::send_json`](crate::group::Group::send_json)。
+    pub async fn send_json(&self, json: impl Into<String>) -> RQResult<SentMessage<S>> {
+        let msg: Message = [RQElem::LightApp(LightApp {
+            content: json.into(),
+        })]
+        .into_iter()
+        .collect();
+        self.send(msg).await
+    }
+
+    /// 发送已上传的语音。
+    pub async fn send_audio(&self, audio: FriendAudio) -> RQResult<SentMessage<S>> {
+        let target = Target::Friend(self.uin);
+        let receipt = self.client.send_audio(target, audio.0).await?;
+        Ok(SentMessage::new(self.client.clone(), target, receipt))
+    }
+}
+
+impl<C: ClientExt> Friend<C> {
     /// 上传语音。
     pub async fn upload_audio(
         &self,
@@ -48,9 +173,16 @@ impl<'a> Friend<'a> {
             .await
     }
 
-    /// 发送语音。
-    pub async fn send_audio(&self, audio: FriendAudio) -> RQResult<MessageReceipt> {
-        self.client.send_friend_audio(self.uin, audio).await
+    /// 编码并上传语音。
+    ///
+    /// 普通的 WAV 或 PCM 音频会先编码为 SILK 格式，参见 [`audio::encode`](crate::audio::encode)。
+    #[cfg(feature = "audio")]
+    pub async fn upload_audio_auto(
+        &self,
+        input: crate::audio::AudioInput,
+    ) -> RQResult<FriendAudio> {
+        let audio = crate::audio::encode(input).await?;
+        self.upload_audio(audio.data, audio.duration).await
     }
 
     /// 获取语音下载链接。
@@ -66,10 +198,27 @@ impl<'a> Friend<'a> {
     }
 
     /// 上传图片。
+    ///
+    /// 上传前会检查图片格式，不支持的格式返回错误；开启 `image-convert` 特性时会转码为 PNG，参见 [`image`](crate::image)。
     pub async fn upload_image(&self, image: impl AsRef<[u8]>) -> RQResult<FriendImage> {
-        self.client
-            .upload_friend_image(self.uin, image.as_ref())
-            .await
+        let image = image::prepare_image(image.as_ref(), "图片")?;
+        self.client.upload_friend_image(self.uin, &image).await
+    }
+
+    /// 从文件上传图片。
+    ///
+    /// 文件不存在、不是图片或超过 [`MAX_IMAGE_SIZE`](crate::image::MAX_IMAGE_SIZE) 时返回错误。
+    pub async fn upload_image_from_path(&self, path: impl AsRef<Path>) -> RQResult<FriendImage> {
+        let data = image::read_image(path.as_ref()).await?;
+        self.upload_image(data).await
+    }
+
+    /// 从 URL 下载并上传图片。
+    ///
+    /// 请求失败、返回内容不是图片或超过 [`MAX_IMAGE_SIZE`](crate::image::MAX_IMAGE_SIZE) 时返回错误。
+    pub async fn upload_image_from_url(&self, url: &str) -> RQResult<FriendImage> {
+        let data = image::download_image(url).await?;
+        self.upload_image(data).await
     }
 
     /// 发送链接分享。
@@ -85,8 +234,14 @@ impl<'a> Friend<'a> {
     }
 
     /// 戳一戳。
-    pub async fn poke(&self) -> RQResult<()> {
-        self.client.friend_poke(self.uin).await
+    ///
+    /// `poke_type` 为 `None` 或 [`PokeType::Poke`] 时发送普通的戳一戳，其余类型以消息形式发送特效。
+    /// ricq 的戳一戳接口不支持指定类型，因此特效通过消息中的戳一戳元素实现。
+    pub async fn poke(&self, poke_type: Option<PokeType>) -> RQResult<()> {
+        match poke_type {
+            None | Some(PokeType::Poke) => self.client.friend_poke(self.uin).await,
+            Some(poke_type) => self.send(poke_type.to_message()).await.map(|_| ()),
+        }
     }
 
     /// 删除好友。
@@ -0,0 +1,163 @@
+//! 好友分组管理。
+//!
+//! ricq 只能获取好友分组，没有封装分组的修改，这里直接构造 `friendlist.SetGroupReq`（创建分组）和
+//! `friendlist.MovGroupMemReq`（移动好友）请求。
+
+use std::collections::HashMap;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use jcers::{JceGet, JcePut};
+use ricq::{structs::FriendGroupInfo, Client, RQError, RQResult};
+use ricq_core::jce::{RequestDataVersion3, RequestPacket};
+
+use crate::DEFAULT_FRIEND_GROUP_ID;
+
+/// 好友分组数量的上限，包括默认分组。
+pub const MAX_FRIEND_GROUPS: usize = 30;
+
+const SERVANT_NAME: &str = "mqq.IMService.FriendListServiceServantObj";
+
+#[derive(Debug, Default, JcePut)]
+struct SetGroupReq {
+    #[jce(0)]
+    reqtype: i32,
+    #[jce(1)]
+    uin: i64,
+    #[jce(2)]
+    vec_body: Bytes,
+}
+
+#[derive(Debug, Default, JcePut)]
+struct MovGroupMemReq {
+    #[jce(0)]
+    uin: i64,
+    #[jce(1)]
+    reqtype: i16,
+    #[jce(2)]
+    vec_body: Bytes,
+}
+
+/// `SetGroupResp` 与 `MovGroupMemResp` 的公共部分。
+#[derive(Debug, Default, JceGet)]
+struct FriendListResp {
+    #[jce(1)]
+    result: i32,
+}
+
+/// 获取好友分组列表，按分组 ID 排序。
+pub(crate) async fn list(client: &Client) -> RQResult<Vec<FriendGroupInfo>> {
+    let mut groups: Vec<_> = client
+        .get_friend_list()
+        .await?
+        .friend_groups
+        .into_values()
+        .collect();
+    groups.sort_by_key(|group| group.group_id);
+    Ok(groups)
+}
+
+/// 创建好友分组。
+pub(crate) async fn create(client: &Client, name: &str) -> RQResult<FriendGroupInfo> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > u8::MAX as usize {
+        return Err(RQError::Other(format!(
+            "好友分组名称不能为空，且不能超过 {} 字节",
+            u8::MAX
+        )));
+    }
+    let groups = list(client).await?;
+    if groups.len() >= MAX_FRIEND_GROUPS {
+        return Err(RQError::Other(format!(
+            "好友分组数量已达上限（{} 个）",
+            MAX_FRIEND_GROUPS
+        )));
+    }
+    if groups.iter().any(|group| group.group_name == name) {
+        return Err(RQError::Other(format!("好友分组「{}」已存在", name)));
+    }
+
+    let mut body = BytesMut::new();
+    body.put_u8(0); // 排序 ID，由服务器分配
+    body.put_u8(name.len() as u8);
+    body.put_slice(name.as_bytes());
+    let req = SetGroupReq {
+        reqtype: 0,
+        uin: client.uin().await,
+        vec_body: body.freeze(),
+    };
+    request(client, "SetGroupReq", req.freeze()).await?;
+
+    // 响应中没有新分组的 ID，重新获取分组列表
+    list(client)
+        .await?
+        .into_iter()
+        .find(|group| group.group_name == name)
+        .ok_or_else(|| RQError::Other(format!("创建好友分组「{}」后未找到该分组", name)))
+}
+
+/// 将好友移动到分组。
+pub(crate) async fn move_friend(client: &Client, uin: i64, group_id: u8) -> RQResult<()> {
+    // 默认分组总是存在，不在分组列表中时也可以移入
+    if group_id != DEFAULT_FRIEND_GROUP_ID
+        && !list(client)
+            .await?
+            .iter()
+            .any(|group| group.group_id == group_id)
+    {
+        return Err(RQError::Other(format!("好友分组 {} 不存在", group_id)));
+    }
+
+    let mut body = BytesMut::new();
+    body.put_u16(1); // 好友数量
+    body.put_u32(uin as u32);
+    body.put_u8(group_id);
+    body.put_u8(0);
+    let req = MovGroupMemReq {
+        uin: client.uin().await,
+        reqtype: 0,
+        vec_body: body.freeze(),
+    };
+    request(client, "MovGroupMemReq", req.freeze()).await
+}
+
+async fn request(client: &Client, func: &str, req: Bytes) -> RQResult<()> {
+    let mut data = BytesMut::new();
+    data.put_u8(0x0A);
+    data.put_slice(&req);
+    data.put_u8(0x0B);
+    let buf = RequestDataVersion3 {
+        map: HashMap::from([(func.to_string(), data.freeze())]),
+    };
+    let pkt = RequestPacket {
+        i_version: 3,
+        i_request_id: 1,
+        s_servant_name: SERVANT_NAME.to_string(),
+        s_func_name: func.to_string(),
+        s_buffer: buf.freeze(),
+        ..Default::default()
+    };
+    let pkt = client
+        .engine
+        .read()
+        .await
+        .uni_packet(&format!("friendlist.{}", func), pkt.freeze());
+    let mut payload = client.send_and_wait(pkt).await?.body;
+
+    let mut resp: RequestPacket = jcers::from_buf(&mut payload).map_err(decode_error)?;
+    let data: RequestDataVersion3 = jcers::from_buf(&mut resp.s_buffer).map_err(decode_error)?;
+    let mut body = data
+        .map
+        .into_values()
+        .next()
+        .ok_or_else(|| RQError::Other("好友分组响应为空".to_string()))?;
+    body.advance(1);
+    let resp: FriendListResp = jcers::from_buf(&mut body).map_err(decode_error)?;
+    match resp.result {
+        0 => Ok(()),
+        code => Err(RQError::Other(format!("好友分组操作失败（{}）", code))),
+    }
+}
+
+fn decode_error(e: jcers::JceError) -> RQError {
+    RQError::Other(format!("好友分组响应解析失败：{:?}", e))
+}
@@ -1,67 +1,446 @@
 //! 群组操作。
 
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use chocho_msg::{
-    elem::{Anonymous, GroupImage},
-    Message,
+    elem::{Anonymous, GroupImage, LightApp, RichMsg},
+    forward::ForwardNode,
+    Message, RQElem,
 };
 use ricq::{
     structs::{
-        GroupAudio, GroupInfo, GroupMemberInfo, GroupMemberPermission, LinkShare, MessageReceipt,
-        MusicShare, MusicVersion,
+        GroupAudio, GroupInfo, GroupMemberInfo, GroupMemberPermission, GroupMessage, LinkShare,
+        MessageReceipt, MusicShare, MusicVersion,
     },
-    Client, RQResult,
+    RQError, RQResult,
 };
 use ricq_core::command::oidb_svc::GroupAtAllRemainInfo;
 
-use crate::structs::AudioCodeC;
+use crate::announcement::{self, Announcement, GroupAnnouncement};
+use crate::essence::{self, EssenceMessage};
+use crate::file::{self, GroupFile, GroupFolder, ROOT_FOLDER};
+use crate::image;
+use crate::permission::{self, Required};
+use crate::schedule::Priority;
+use crate::sender::MessageSender;
+use crate::structs::{AudioCodeC, PokeType, SentMessage, Target};
+use crate::utils::{self, RetryPolicy};
+use crate::{ClientExt, RQClient};
+
+/// 拉取群历史消息时每页的消息数，也是服务器单次请求允许的上限。
+pub const HISTORY_PAGE_SIZE: usize = 20;
 
 /// 群组操作对象。
-pub struct Group<'a> {
-    /// 客户端引用。
-    pub client: &'a Client,
+///
+/// `S` 为发送消息使用的 [`MessageSender`]，通过 [`ClientExt::group`](crate::ClientExt::group) 创建时为 [`RQClient`]。
+/// 发送消息的方法适用于任意的 `S`，因此可以在测试中使用 [`MockClient`](crate::sender::MockClient)；
+/// 其余方法需要 `S` 指向真实的客户端，即实现了 [`ClientExt`]，例如 `RQClient` 和 `&ricq::Client`。
+pub struct Group<S = RQClient> {
+    /// 客户端。
+    pub client: S,
     /// 群号。
     pub code: i64,
 }
 
-impl<'a> Group<'a> {
+impl<S: Clone> Group<S> {
     /// 进行群成员操作。
-    pub fn member(&self, uin: i64) -> GroupMember {
+    pub fn member(&self, uin: i64) -> GroupMember<S> {
         GroupMember {
-            client: self.client,
+            client: self.client.clone(),
             code: self.code,
             uin,
         }
     }
+}
 
+impl<S: MessageSender + Clone> Group<S> {
     /// 发送消息。
-    pub async fn send(&self, msg: impl Into<Message>) -> RQResult<MessageReceipt> {
+    ///
+    /// 返回的 [`SentMessage`] 可以直接撤回或引用回复，也可以转换为原始的 [`MessageReceipt`]。
+    pub async fn send(&self, msg: impl Into<Message>) -> RQResult<SentMessage<S>> {
+        self.send_with_priority(msg, Priority::Normal).await
+    }
+
+    /// 发送消息，临时性错误时按 `policy` 自动重试。
+    ///
+    /// 只有超时、网络错误等临时性错误会重试，被禁言、被风控等永久性错误会立即返回，参见
+    /// [`utils::is_retryable`](crate::utils::is_retryable)。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    /// use chocho::utils::RetryPolicy;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// client.group(87654321)
+    ///     .send_retry("你好".to_string(), RetryPolicy::default())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_retry(
+        &self,
+        msg: impl Into<Message>,
+        policy: RetryPolicy,
+    ) -> RQResult<SentMessage<S>> {
+        let msg: Message = msg.into();
+        utils::retry_with_policy(policy, utils::is_retryable, || self.send(msg.clone())).await
+    }
+
+    /// 将消息切分为多段后依次发送。
+    ///
+    /// 消息按 [`Message::split_by_len`] 切分，每段长度不超过 `max_len`，At、图片等元素不会被拆开。
+    /// 某一段发送失败时立即返回错误，之前发送成功的段不会撤回。返回发送的每一段。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient, log: String) -> RQResult<()> {
+    /// let sent = client.group(87654321).send_chunked(log, 2000).await?;
+    /// println!("分 {} 段发送", sent.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_chunked(
+        &self,
+        msg: impl Into<Message>,
+        max_len: usize,
+    ) -> RQResult<Vec<SentMessage<S>>> {
         let msg: Message = msg.into();
-        if msg.is_long() {
-            self.client
-                .send_group_long_message(self.code, msg.into())
-                .await
-        } else {
-            self.client.send_group_message(self.code, msg.into()).await
+        let mut sent = vec![];
+        for part in msg.split_by_len(max_len) {
+            sent.push(self.send(part).await?);
         }
+        Ok(sent)
+    }
+
+    /// 以指定优先级发送消息。
+    ///
+    /// 设置了限流时，高优先级的消息会先于低优先级的消息发送，参见 [`schedule`](crate::schedule)。
+    ///
+    /// 消息中待上传的图片（[`Image`](chocho_msg::elem::Image)）会先上传为群图片。
+    pub async fn send_with_priority(
+        &self,
+        msg: impl Into<Message>,
+        priority: Priority,
+    ) -> RQResult<SentMessage<S>> {
+        let target = Target::Group(self.code);
+        let receipt = self
+            .client
+            .send_message_with_priority(target, msg.into(), priority)
+            .await?;
+        Ok(SentMessage::new(self.client.clone(), target, receipt))
     }
 
+    /// 发送 XML 卡片消息。
+    ///
+    /// `service_id` 为卡片的服务 ID，`xml` 会原样发送。
+    pub async fn send_xml(
+        &self,
+        service_id: i32,
+        xml: impl Into<String>,
+    ) -> RQResult<SentMessage<S>> {
+        let msg: Message = [RQElem::RichMsg(RichMsg {
+            service_id,
+            template1: xml.into(),
+        })]
+        .into_iter()
+        .collect();
+        self.send(msg).await
+    }
+
+    /// 发送 JSON 卡片消息，如小程序分享。
+    ///
+    /// `json` 会原样发送。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let card = r#"{"app":"com.tencent.miniapp","desc":"","view":"notification","ver":"0.0.0.1","prompt":"[应用]","meta":{"notification":{"appInfo":{"appName":"chocho","appType":4,"appid":2174106925},"title":"你好","data":[{"title":"内容","value":"来自 chocho 的卡片"}]}}}"#;
+    /// client.group(87654321).send_json(card).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_json(&self, json: impl Into<String>) -> RQResult<SentMessage<S>> {
+        let msg: Message = [RQElem::LightApp(LightApp {
+            content: json.into(),
+        })]
+        .into_iter()
+        .collect();
+        self.send(msg).await
+    }
+
+    /// 发送合并转发消息。
+    ///
+    /// 转发的每条消息都会经过[钩子](crate::hook)，其中待上传的图片会先上传为群图片。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    /// use chocho_msg::forward::ForwardMessage;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let inner = ForwardMessage::new().message(12345678, "小明", 1680000000, "被转发的消息".to_string());
+    /// let forward = ForwardMessage::new()
+    ///     .message(12345678, "小明", 1680000000, "你好".to_string())
+    ///     .forward(12345678, "小明", 1680000060, inner);
+    /// client.group(87654321).send_forward(forward).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_forward(
+        &self,
+        nodes: impl Into<Vec<ForwardNode>>,
+    ) -> RQResult<SentMessage<S>> {
+        let target = Target::Group(self.code);
+        let receipt = self.client.send_forward(target, nodes.into()).await?;
+        Ok(SentMessage::new(self.client.clone(), target, receipt))
+    }
+
+    /// 发送已上传的语音。
+    pub async fn send_audio(&self, audio: GroupAudio) -> RQResult<SentMessage<S>> {
+        let target = Target::Group(self.code);
+        let receipt = self.client.send_audio(target, audio.0).await?;
+        Ok(SentMessage::new(self.client.clone(), target, receipt))
+    }
+}
+
+impl<C: ClientExt> Group<C> {
     /// 获取群信息。
     pub async fn get_info(&self) -> RQResult<Option<GroupInfo>> {
         self.client.get_group_info(self.code).await
     }
 
+    /// 获取最近的 `count` 条群消息，按时间从早到晚排列。
+    ///
+    /// 从群的最新消息开始向前分页拉取，每页 [`HISTORY_PAGE_SIZE`] 条，参见 [`get_history_before`](Self::get_history_before)。
+    /// 服务器只保留有限的漫游消息（通常为最近几天、最多数千条），更早的消息无法获取，此时返回的消息会少于 `count` 条。
+    /// 群不存在或机器人不在群中时返回错误。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// for msg in client.group(87654321).get_history(50).await? {
+    ///     println!("{}", msg);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_history(&self, count: usize) -> RQResult<Vec<Message>> {
+        let info = self
+            .get_info()
+            .await?
+            .ok_or_else(|| RQError::Other(format!("群 {} 不存在或未加入", self.code)))?;
+        let history = self
+            .get_history_before(info.last_msg_seq as i32 + 1, count)
+            .await?;
+        Ok(history
+            .into_iter()
+            .map(|msg| Message::from(msg.elements))
+            .collect())
+    }
+
+    /// 获取序号小于 `before_seq` 的最近 `count` 条群消息，按时间从早到晚排列。
+    ///
+    /// 返回原始的 [`GroupMessage`]，包含发送者、时间和序号。将结果中最早一条消息的序号作为下一次的 `before_seq`，
+    /// 即可继续向前翻页。没有更早的消息或更早的消息已无法获取时，返回的消息会少于 `count` 条。
+    pub async fn get_history_before(
+        &self,
+        before_seq: i32,
+        count: usize,
+    ) -> RQResult<Vec<GroupMessage>> {
+        let mut history = vec![];
+        let mut end = before_seq - 1;
+        while history.len() < count && end > 0 {
+            let page = (count - history.len()).min(HISTORY_PAGE_SIZE) as i32;
+            let begin = (end - page + 1).max(1);
+            let mut messages = match self.client.get_group_msgs(self.code, begin, end).await {
+                Ok(messages) => messages,
+                // 第一页就失败时说明无法获取历史消息，否则视为已到达漫游消息的尽头
+                Err(e) if history.is_empty() => return Err(e),
+                Err(_) => break,
+            };
+            if messages.is_empty() {
+                break;
+            }
+            messages.sort_by_key(|msg| msg.seqs.first().copied().unwrap_or_default());
+            messages.append(&mut history);
+            history = messages;
+            end = begin - 1;
+        }
+        // 一页中的消息可能多于需要的数量，只保留最近的
+        let skip = history.len().saturating_sub(count);
+        history.drain(..skip);
+        Ok(history)
+    }
+
     /// 获取群成员列表。
     pub async fn get_member_list(&self, owner: i64) -> RQResult<Vec<GroupMemberInfo>> {
         self.client.get_group_member_list(self.code, owner).await
     }
 
+    /// 获取群成员列表，自动获取群主 QQ 号。
+    ///
+    /// 内部先调用 [`get_info`](Self::get_info) 获取群主，再获取成员列表，因此比 [`get_member_list`](Self::get_member_list)
+    /// 多一次网络请求。已知群主时，请直接使用 `get_member_list`。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// for member in client.group(87654321).get_member_list_auto().await? {
+    ///     println!("{} ({})", member.card_name, member.uin);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_member_list_auto(&self) -> RQResult<Vec<GroupMemberInfo>> {
+        let info = self
+            .get_info()
+            .await?
+            .ok_or_else(|| RQError::Other(format!("群 {} 不存在", self.code)))?;
+        self.get_member_list(info.owner_uin).await
+    }
+
+    /// 筛选群成员。
+    ///
+    /// 返回可链式添加筛选条件的 [`MemberQuery`]，调用 [`collect`](MemberQuery::collect) 时只请求一次成员列表，
+    /// 并在内存中按所有条件筛选。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// // 2023 年之前入群、目前被禁言的管理员
+    /// let members = client
+    ///     .group(87654321)
+    ///     .members()
+    ///     .admins()
+    ///     .muted()
+    ///     .joined_before(1672531200)
+    ///     .collect()
+    ///     .await?;
+    /// for member in members {
+    ///     println!("{} ({})", member.card_name, member.uin);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn members(&self) -> MemberQuery<C> {
+        MemberQuery {
+            client: self.client.clone(),
+            code: self.code,
+            filters: vec![],
+        }
+    }
+
+    /// 获取机器人自身在群内的权限。
+    ///
+    /// 需要请求一次自身的群成员信息。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    /// use chocho::ricq::structs::GroupMemberPermission;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let group = client.group(87654321);
+    /// if !matches!(group.my_permission().await?, GroupMemberPermission::Member) {
+    ///     group.set_name("新群名").await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn my_permission(&self) -> RQResult<GroupMemberPermission> {
+        permission::my_permission(&self.client, self.code).await
+    }
+
     /// 获取群主/管理员列表。
     pub async fn get_admin_list(&self) -> RQResult<HashMap<i64, GroupMemberPermission>> {
         self.client.get_group_admin_list(self.code).await
     }
 
+    /// 批量禁言。
+    ///
+    /// 逐个禁言成员，某个成员失败不影响其它成员。返回每个成员的操作结果，顺序与 `uins` 相同。
+    /// 开启了权限检查时，只在开始前检查一次机器人的权限，权限不足时所有成员都返回该错误。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) {
+    /// let results = client
+    ///     .group(87654321)
+    ///     .mute_members(&[12345678, 23456789], Duration::from_secs(600))
+    ///     .await;
+    /// for (uin, result) in results {
+    ///     if let Err(e) = result {
+    ///         println!("禁言 {} 失败：{}", uin, e);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn mute_members(&self, uins: &[i64], time: Duration) -> Vec<(i64, RQResult<()>)> {
+        if let Err(e) =
+            permission::require(&self.client, self.code, Required::Admin, "禁言成员").await
+        {
+            return fail_all(uins, e);
+        }
+        let mut results = Vec::with_capacity(uins.len());
+        for &uin in uins {
+            results.push((uin, self.client.group_mute(self.code, uin, time).await));
+        }
+        results
+    }
+
+    /// 批量踢出群聊。
+    ///
+    /// 所有成员通过一次请求踢出，因此结果要么全部成功，要么全部失败。
+    /// 返回每个成员的操作结果，顺序与 `uins` 相同。
+    pub async fn kick_members(
+        &self,
+        uins: &[i64],
+        message: &str,
+        ban: bool,
+    ) -> Vec<(i64, RQResult<()>)> {
+        let result = match permission::require(&self.client, self.code, Required::Admin, "踢出成员")
+            .await
+        {
+            Ok(()) => {
+                self.client
+                    .group_kick(self.code, uins.to_vec(), message, ban)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => uins.iter().map(|&uin| (uin, Ok(()))).collect(),
+            Err(e) => fail_all(uins, e),
+        }
+    }
+
     /// 上传语音。
     pub async fn upload_audio(
         &self,
@@ -77,9 +456,13 @@ impl<'a> Group<'a> {
             .await
     }
 
-    /// 发送语音。
-    pub async fn send_audio(&self, audio: GroupAudio) -> RQResult<MessageReceipt> {
-        self.client.send_group_audio(self.code, audio).await
+    /// 编码并上传语音。
+    ///
+    /// 普通的 WAV 或 PCM 音频会先编码为 SILK 格式，参见 [`audio::encode`](crate::audio::encode)。
+    #[cfg(feature = "audio")]
+    pub async fn upload_audio_auto(&self, input: crate::audio::AudioInput) -> RQResult<GroupAudio> {
+        let audio = crate::audio::encode(input).await?;
+        self.upload_audio(audio.data, audio.codec).await
     }
 
     /// 撤回消息。
@@ -90,10 +473,82 @@ impl<'a> Group<'a> {
     }
 
     /// 上传图片。
+    ///
+    /// 上传前会检查图片格式，不支持的格式返回错误；开启 `image-convert` 特性时会转码为 PNG，参见 [`image`](crate::image)。
     pub async fn upload_image(&self, image: impl AsRef<[u8]>) -> RQResult<GroupImage> {
-        self.client
-            .upload_group_image(self.code, image.as_ref())
-            .await
+        let image = image::prepare_image(image.as_ref(), "图片")?;
+        self.client.upload_group_image(self.code, &image).await
+    }
+
+    /// 从文件上传图片。
+    ///
+    /// 文件不存在、不是图片或超过 [`MAX_IMAGE_SIZE`](crate::image::MAX_IMAGE_SIZE) 时返回错误。
+    pub async fn upload_image_from_path(&self, path: impl AsRef<Path>) -> RQResult<GroupImage> {
+        let data = image::read_image(path.as_ref()).await?;
+        self.upload_image(data).await
+    }
+
+    /// 从 URL 下载并上传图片。
+    ///
+    /// 请求失败、返回内容不是图片或超过 [`MAX_IMAGE_SIZE`](crate::image::MAX_IMAGE_SIZE) 时返回错误。
+    pub async fn upload_image_from_url(&self, url: &str) -> RQResult<GroupImage> {
+        let data = image::download_image(url).await?;
+        self.upload_image(data).await
+    }
+
+    /// 上传群文件到根目录，返回文件 ID。
+    ///
+    /// 目录中已有同名文件时，会自动重命名为 `name(1).ext` 的形式。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let group = client.group(87654321);
+    /// group.upload_file("hello.txt", "你好，世界").await?;
+    /// for file in group.list_files().await? {
+    ///     println!("{} ({} 字节)", file.name, file.size);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn upload_file(&self, name: &str, data: impl AsRef<[u8]>) -> RQResult<String> {
+        self.upload_file_to(ROOT_FOLDER, name, data).await
+    }
+
+    /// 上传群文件到指定目录，返回文件 ID。
+    ///
+    /// `folder_id` 为 [`GroupFolder::folder_id`]，根目录为 [`ROOT_FOLDER`]。
+    /// 目录中已有同名文件时，会自动重命名为 `name(1).ext` 的形式。
+    pub async fn upload_file_to(
+        &self,
+        folder_id: &str,
+        name: &str,
+        data: impl AsRef<[u8]>,
+    ) -> RQResult<String> {
+        let (files, _) = file::list(&self.client, self.code, folder_id).await?;
+        let name = file::dedup_name(name, |name| files.iter().any(|f| f.name == name));
+        file::upload(&self.client, self.code, folder_id, &name, data.as_ref()).await
+    }
+
+    /// 获取根目录下的群文件。
+    pub async fn list_files(&self) -> RQResult<Vec<GroupFile>> {
+        Ok(file::list(&self.client, self.code, ROOT_FOLDER).await?.0)
+    }
+
+    /// 获取指定目录下的群文件和文件夹。
+    pub async fn list_folder(
+        &self,
+        folder_id: &str,
+    ) -> RQResult<(Vec<GroupFile>, Vec<GroupFolder>)> {
+        file::list(&self.client, self.code, folder_id).await
+    }
+
+    /// 获取群文件的下载链接。
+    pub async fn get_file_url(&self, file_id: &str) -> RQResult<String> {
+        file::url(&self.client, self.code, file_id).await
     }
 
     /// 发送链接分享。
@@ -108,9 +563,15 @@ impl<'a> Group<'a> {
             .await
     }
 
-    /// 戳一戳。
-    pub async fn poke(&self, uin: i64) -> RQResult<()> {
-        self.client.group_poke(self.code, uin).await
+    /// 戳一戳群成员。
+    ///
+    /// `poke_type` 为 `None` 或 [`PokeType::Poke`] 时戳一戳指定的成员，其余类型以消息形式向群内发送特效，
+    /// 此时忽略 `uin`。参见 [`Friend::poke`](crate::friend::Friend::poke)。
+    pub async fn poke(&self, uin: i64, poke_type: Option<PokeType>) -> RQResult<()> {
+        match poke_type {
+            None | Some(PokeType::Poke) => self.client.group_poke(self.code, uin).await,
+            Some(poke_type) => self.send(poke_type.to_message()).await.map(|_| ()),
+        }
     }
 
     /// 退出群聊。
@@ -120,16 +581,92 @@ impl<'a> Group<'a> {
 
     /// 设置群名称。
     pub async fn set_name(&self, name: impl Into<String>) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "修改群名称").await?;
         self.client.update_group_name(self.code, name.into()).await
     }
 
+    /// 开启或关闭全员禁言。
+    ///
+    /// 全员禁言与 [`GroupMember::mute`] 的个人禁言互不影响：关闭全员禁言不会解除成员的个人禁言，
+    /// 开启全员禁言也不会改变成员个人禁言的剩余时间。群主和管理员不受全员禁言影响。
+    ///
+    /// 需要管理员权限。开启了[权限检查](crate::permission)时，权限不足会在请求前返回错误；
+    /// 否则请求失败后会查询机器人的权限，确认不是管理员时在错误中附带提示，其它错误原样返回。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let group = client.group(87654321);
+    /// group.mute_all(true).await?;
+    /// tokio::time::sleep(Duration::from_secs(3600)).await;
+    /// group.mute_all(false).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn mute_all(&self, enable: bool) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "设置全员禁言").await?;
+        let Err(e) = self.client.group_mute_all(self.code, enable).await else {
+            return Ok(());
+        };
+        // 只有确认机器人不是管理员时才附加提示，其它错误原样返回
+        let permission = permission::my_permission(&self.client, self.code).await;
+        if matches!(permission, Ok(GroupMemberPermission::Member)) {
+            return Err(RQError::Other(format!(
+                "设置群 {} 的全员禁言失败，机器人不是管理员：{}",
+                self.code, e
+            )));
+        }
+        Err(e)
+    }
+
     /// 设置群公告。
     pub async fn set_announcement(&self, announcement: impl Into<String>) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "设置群公告").await?;
         self.client
             .update_group_memo(self.code, announcement.into())
             .await
     }
 
+    /// 发布群公告，支持标题、图片、置顶等选项。
+    ///
+    /// 通过网页接口发布，参见 [`GroupAnnouncement`]。带有图片时会先上传图片，
+    /// 图片无效或上传失败时返回错误，不会发布缺少图片的公告。
+    pub async fn publish_announcement(&self, announcement: GroupAnnouncement) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "发布群公告").await?;
+        announcement::publish(&self.client, self.code, announcement).await
+    }
+
+    /// 获取最近发布的群公告，置顶的公告排在前面。
+    ///
+    /// 通过网页接口获取，只返回最近的 20 条公告。
+    pub async fn get_announcements(&self) -> RQResult<Vec<Announcement>> {
+        announcement::list(&self.client, self.code).await
+    }
+
+    /// 获取群的所有精华消息。
+    ///
+    /// 通过网页接口获取，参见 [`essence`](crate::essence)。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// for essence in client.group(87654321).get_essence_list().await? {
+    ///     println!("{} 设置了 {} 的消息", essence.operator_nick, essence.sender_nick);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_essence_list(&self) -> RQResult<Vec<EssenceMessage>> {
+        essence::list(&self.client, self.code).await
+    }
+
     /// 邀请入群。
     #[doc(hidden)]
     pub async fn invite(&self, uin: i64) -> RQResult<()> {
@@ -152,37 +689,199 @@ impl<'a> Group<'a> {
     }
 }
 
+/// 群成员筛选条件，第二个参数是筛选时的 Unix 时间戳。
+type MemberFilter = Box<dyn Fn(&GroupMemberInfo, i64) -> bool + Send + Sync>;
+
+/// 群成员的筛选，由 [`Group::members`] 创建。
+///
+/// 多个筛选条件之间是“且”的关系。时间戳均为秒级 Unix 时间戳。
+pub struct MemberQuery<C = RQClient> {
+    client: C,
+    code: i64,
+    filters: Vec<MemberFilter>,
+}
+
+impl<C: ClientExt> MemberQuery<C> {
+    /// 按自定义条件筛选。
+    pub fn filter(self, f: impl Fn(&GroupMemberInfo) -> bool + Send + Sync + 'static) -> Self {
+        self.filter_at(move |member, _| f(member))
+    }
+
+    /// 按依赖当前时间的条件筛选，时间在 [`collect`](Self::collect) 时读取。
+    fn filter_at(
+        mut self,
+        f: impl Fn(&GroupMemberInfo, i64) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filters.push(Box::new(f));
+        self
+    }
+
+    /// 只保留群主和管理员。
+    pub fn admins(self) -> Self {
+        self.filter(|member| !matches!(member.permission, GroupMemberPermission::Member))
+    }
+
+    /// 只保留普通成员。
+    pub fn ordinary(self) -> Self {
+        self.filter(|member| matches!(member.permission, GroupMemberPermission::Member))
+    }
+
+    /// 只保留正在被禁言的成员。
+    ///
+    /// 以获取到成员列表时的时间判断是否仍在禁言中。
+    pub fn muted(self) -> Self {
+        self.filter_at(|member, now| member.shut_up_timestamp > now)
+    }
+
+    /// 只保留在 `timestamp` 之前入群的成员。
+    pub fn joined_before(self, timestamp: i64) -> Self {
+        self.filter(move |member| member.join_time < timestamp)
+    }
+
+    /// 只保留在 `timestamp` 及之后入群的成员。
+    pub fn joined_after(self, timestamp: i64) -> Self {
+        self.filter(move |member| member.join_time >= timestamp)
+    }
+
+    /// 只保留在 `timestamp` 及之后发过言的成员。
+    pub fn active_since(self, timestamp: i64) -> Self {
+        self.filter(move |member| member.last_speak_time >= timestamp)
+    }
+
+    /// 只保留在 `timestamp` 之后没有发过言的成员。
+    pub fn inactive_since(self, timestamp: i64) -> Self {
+        self.filter(move |member| member.last_speak_time < timestamp)
+    }
+
+    /// 请求成员列表，返回满足所有条件的成员。
+    ///
+    /// 成员列表通过 [`Group::get_member_list_auto`] 获取。
+    pub async fn collect(self) -> RQResult<Vec<GroupMemberInfo>> {
+        let group = Group {
+            client: self.client,
+            code: self.code,
+        };
+        let mut members = group.get_member_list_auto().await?;
+        let now = now();
+        members.retain(|member| self.filters.iter().all(|f| f(member, now)));
+        Ok(members)
+    }
+}
+
+/// 批量操作整体失败时，每个成员都返回同样的错误。
+fn fail_all(uins: &[i64], e: RQError) -> Vec<(i64, RQResult<()>)> {
+    let e = e.to_string();
+    uins.iter()
+        .map(|&uin| (uin, Err(RQError::Other(e.clone()))))
+        .collect()
+}
+
+/// 当前的 Unix 时间戳。
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// 计算禁言剩余时间时，容忍的本地时间与服务器时间的偏差。
+pub const MUTE_CLOCK_TOLERANCE: Duration = Duration::from_secs(5);
+
 /// 群成员操作对象。
-pub struct GroupMember<'a> {
-    /// 客户端引用。
-    pub client: &'a Client,
+///
+/// 与 [`Group`] 相同，发送临时消息适用于任意的 [`MessageSender`]，其余方法需要 `S` 实现 [`ClientExt`]。
+pub struct GroupMember<S = RQClient> {
+    /// 客户端。
+    pub client: S,
     /// 群号。
     pub code: i64,
     /// 群成员 QQ 号。
     pub uin: i64,
 }
 
-impl<'a> GroupMember<'a> {
+impl<S> GroupMember<S> {
+    /// 头像的 URL。
+    ///
+    /// `size` 为头像边长，QQ 支持 40、100、140 和 640。URL 由 QQ 号直接拼接得到，不需要网络请求。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chocho::prelude::*;
+    /// # fn _f(client: RQClient) {
+    /// let url = client.group(87654321).member(12345678).avatar_url(640);
+    /// assert_eq!(url, "https://q.qlogo.cn/g?b=qq&nk=12345678&s=640");
+    /// # }
+    /// ```
+    pub fn avatar_url(&self, size: u32) -> String {
+        crate::avatar_url(self.uin, size)
+    }
+}
+
+impl<S: MessageSender + Clone> GroupMember<S> {
     /// 发送群成员临时消息。
-    pub async fn send_temp_msg(&self, msg: impl Into<Message>) -> RQResult<MessageReceipt> {
-        let msg: Message = msg.into();
-        self.client
-            .send_group_temp_message(self.code, self.uin, msg.into())
-            .await
+    ///
+    /// 临时消息不支持待上传的图片（[`Image`](chocho_msg::elem::Image)），请先上传为群图片。
+    /// 群临时会话中的消息不能撤回。
+    pub async fn send_temp_msg(&self, msg: impl Into<Message>) -> RQResult<SentMessage<S>> {
+        let target = Target::GroupTemp {
+            group: self.code,
+            uin: self.uin,
+        };
+        let receipt = self.client.send_message(target, msg.into()).await?;
+        Ok(SentMessage::new(self.client.clone(), target, receipt))
     }
+}
 
+impl<C: ClientExt> GroupMember<C> {
     /// 获取群成员信息。
     pub async fn get_info(&self) -> RQResult<GroupMemberInfo> {
         self.client.get_group_member_info(self.code, self.uin).await
     }
 
+    /// 获取昵称。
+    ///
+    /// 返回的是 QQ 昵称，而不是群名片。
+    pub async fn nickname(&self) -> RQResult<String> {
+        Ok(self.get_info().await?.nickname)
+    }
+
     /// 禁言。
     pub async fn mute(&self, time: Duration) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "禁言成员").await?;
         self.client.group_mute(self.code, self.uin, time).await
     }
 
+    /// 获取剩余的禁言时间，未被禁言或已解禁时返回 `None`。
+    ///
+    /// 剩余时间由群成员信息中的解禁时间戳与本地时间计算得到。为了容忍本地时间与服务器时间的偏差，
+    /// 剩余时间不足 [`MUTE_CLOCK_TOLERANCE`] 时视为已解禁。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let member = client.group(87654321).member(12345678);
+    /// if member.mute_remaining().await?.is_none() {
+    ///     member.mute(Duration::from_secs(600)).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn mute_remaining(&self) -> RQResult<Option<Duration>> {
+        let info = self.get_info().await?;
+        let now = now();
+        let remaining =
+            Duration::from_secs(info.shut_up_timestamp.saturating_sub(now).max(0) as u64);
+        Ok((remaining > MUTE_CLOCK_TOLERANCE).then_some(remaining))
+    }
+
     /// 解除禁言。
     pub async fn unmute(&self) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "解除禁言").await?;
         self.client
             .group_mute(self.code, self.uin, Duration::ZERO)
             .await
@@ -190,11 +889,13 @@ impl<'a> GroupMember<'a> {
 
     /// 设置管理员。
     pub async fn set_admin(&self) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Owner, "设置管理员").await?;
         self.client.group_set_admin(self.code, self.uin, true).await
     }
 
     /// 取消管理员。
     pub async fn unset_admin(&self) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Owner, "取消管理员").await?;
         self.client
             .group_set_admin(self.code, self.uin, false)
             .await
@@ -202,6 +903,7 @@ impl<'a> GroupMember<'a> {
 
     /// 踢出群聊。
     pub async fn kick(self, message: impl AsRef<str>, ban: bool) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Admin, "踢出成员").await?;
         self.client
             .group_kick(self.code, vec![self.uin], message.as_ref(), ban)
             .await
@@ -209,6 +911,7 @@ impl<'a> GroupMember<'a> {
 
     /// 设置群头衔。
     pub async fn set_special_title(&self, title: impl Into<String>) -> RQResult<()> {
+        permission::require(&self.client, self.code, Required::Owner, "设置群头衔").await?;
         self.client
             .group_edit_special_title(self.code, self.uin, title.into())
             .await
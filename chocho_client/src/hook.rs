@@ -0,0 +1,88 @@
+//! 消息发送前的钩子。
+//!
+//! 为客户端注册钩子后，通过 [`Friend`](crate::friend::Friend)、[`Group`](crate::group::Group)
+//! 和 [`GroupMember`](crate::group::GroupMember) 发送的消息，在真正发出前都会依次经过所有钩子。
+//! 钩子可以修改消息，例如过滤敏感词、追加签名、记录日志；也可以返回 [`SendAction::Cancel`] 取消发送，
+//! 此时 `send` 返回错误，后面的钩子不再执行。
+//!
+//! 钩子按注册顺序执行，在上传待上传的图片和等待限流之前执行。[`send_chunked`](crate::group::Group::send_chunked)
+//! 切分后的每一段会分别经过钩子，合并转发中的每条消息也会分别经过钩子。
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chocho::prelude::*;
+//! use chocho_client::hook::{add_send_hook, SendAction};
+//!
+//! #[chocho::main]
+//! async fn main(client: RQClient) -> RQResult<()> {
+//!     add_send_hook(&client, |_target, msg| {
+//!         if msg.text().contains("敏感词") {
+//!             return SendAction::Cancel;
+//!         }
+//!         msg.push("\n—— 来自 chocho");
+//!         SendAction::Continue
+//!     });
+//!
+//!     client.group(87654321).send("你好".to_string()).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use chocho_msg::Message;
+use ricq::{Client, RQError, RQResult};
+
+use crate::{state, structs::Target};
+
+/// 钩子的处理结果。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendAction {
+    /// 继续发送。
+    #[default]
+    Continue,
+    /// 取消发送。
+    Cancel,
+}
+
+type SendHook = Arc<dyn Fn(Target, &mut Message) -> SendAction + Send + Sync>;
+
+/// 客户端注册的所有钩子。
+#[derive(Default)]
+struct SendHooks(Mutex<Vec<SendHook>>);
+
+/// 为客户端注册消息发送前的钩子。
+///
+/// 可以注册多个钩子，按注册顺序执行。钩子随客户端释放而清除。
+pub fn add_send_hook(
+    client: &Arc<Client>,
+    hook: impl Fn(Target, &mut Message) -> SendAction + Send + Sync + 'static,
+) {
+    state::get_or_insert_with(client, SendHooks::default)
+        .0
+        .lock()
+        .expect("Failed locking SendHooks")
+        .push(Arc::new(hook));
+}
+
+/// 清除客户端的所有钩子。
+pub fn clear_send_hooks(client: &Client) {
+    state::remove::<SendHooks>(client);
+}
+
+/// 依次执行钩子。发送被取消时返回错误。
+pub(crate) fn before_send(client: &Client, target: Target, msg: &mut Message) -> RQResult<()> {
+    let hooks = state::get::<SendHooks>(client)
+        .map(|hooks| hooks.0.lock().expect("Failed locking SendHooks").clone());
+    // 在锁外执行，允许钩子中注册新的钩子
+    for hook in hooks.into_iter().flatten() {
+        if hook(target, msg) == SendAction::Cancel {
+            return Err(RQError::Other(format!(
+                "发送到 {:?} 的消息已被取消",
+                target
+            )));
+        }
+    }
+    Ok(())
+}
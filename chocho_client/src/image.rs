@@ -0,0 +1,127 @@
+//! 图片读取与下载。
+//!
+//! 上传前会根据文件头检查图片格式，只允许 PNG、JPEG、GIF、BMP 和 WebP。
+//! 开启 `image-convert` 特性后，其它能被 [`image`](https://docs.rs/image) 识别的格式（如 TIFF、ICO）会自动转码为 PNG。
+
+use std::{borrow::Cow, path::Path};
+
+use chocho_msg::ImageRef;
+use ricq::{RQError, RQResult};
+
+/// 允许上传的最大图片大小（30 MiB）。
+pub const MAX_IMAGE_SIZE: u64 = 30 * 1024 * 1024;
+
+/// 根据文件头判断是否为支持的图片格式（PNG、JPEG、GIF、BMP、WebP）。
+pub(crate) fn is_image(data: &[u8]) -> bool {
+    data.starts_with(b"\x89PNG\r\n\x1a\n")
+        || data.starts_with(b"\xff\xd8\xff")
+        || data.starts_with(b"GIF87a")
+        || data.starts_with(b"GIF89a")
+        || data.starts_with(b"BM")
+        || (data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP")
+}
+
+fn check_size(size: u64) -> RQResult<()> {
+    if size > MAX_IMAGE_SIZE {
+        return Err(RQError::Other(format!(
+            "图片过大：{} 字节，最大允许 {} 字节",
+            size, MAX_IMAGE_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// 检查图片格式，不支持的格式尝试转码。
+pub(crate) fn prepare_image<'a>(data: &'a [u8], source: &str) -> RQResult<Cow<'a, [u8]>> {
+    check_size(data.len() as u64)?;
+    if is_image(data) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let data = convert(data, source)?;
+    check_size(data.len() as u64)?;
+    Ok(Cow::Owned(data))
+}
+
+pub(crate) fn check_image(data: Vec<u8>, source: &str) -> RQResult<Vec<u8>> {
+    match prepare_image(&data, source)? {
+        Cow::Borrowed(_) => Ok(data),
+        Cow::Owned(converted) => Ok(converted),
+    }
+}
+
+/// 将图片转码为 PNG。
+#[cfg(feature = "image-convert")]
+fn convert(data: &[u8], source: &str) -> RQResult<Vec<u8>> {
+    let image = ::image::load_from_memory(data)
+        .map_err(|e| RQError::Other(format!("{} 不是支持的图片格式：{}", source, e)))?;
+    let mut png = std::io::Cursor::new(vec![]);
+    image
+        .write_to(&mut png, ::image::ImageOutputFormat::Png)
+        .map_err(|e| RQError::Other(format!("{} 转码为 PNG 失败：{}", source, e)))?;
+    tracing::debug!("{} 不是 QQ 支持的图片格式，已转码为 PNG", source);
+    Ok(png.into_inner())
+}
+
+#[cfg(not(feature = "image-convert"))]
+fn convert(_data: &[u8], source: &str) -> RQResult<Vec<u8>> {
+    Err(RQError::Other(format!(
+        "{} 不是支持的图片格式（支持 PNG、JPEG、GIF、BMP、WebP，开启 `image-convert` 特性可以自动转码其它格式）",
+        source
+    )))
+}
+
+/// 从文件读取图片。
+pub(crate) async fn read_image(path: &Path) -> RQResult<Vec<u8>> {
+    let metadata = tokio::fs::metadata(path).await?;
+    check_size(metadata.len())?;
+    let data = tokio::fs::read(path).await?;
+    check_image(data, &path.display().to_string())
+}
+
+/// 从 URL 下载图片。
+pub(crate) async fn download_image(url: &str) -> RQResult<Vec<u8>> {
+    let data = fetch(url).await?;
+    check_image(data, url)
+}
+
+/// 下载消息中的图片原图，并校验 md5。
+///
+/// 消息中的链接可能已经过期，此时改用由 md5 拼接的链接重新下载。
+pub(crate) async fn download_image_ref(image: &ImageRef) -> RQResult<Vec<u8>> {
+    let md5 = image.md5();
+    let url = image.url();
+    let fallback = format!(
+        "https://gchat.qpic.cn/gchatpic_new/0/0-0-{}/0?term=2",
+        hex::encode_upper(md5)
+    );
+
+    let mut error = None;
+    for url in [url.as_str(), fallback.as_str()] {
+        match fetch(url).await {
+            Ok(data) if md5.is_empty() || md5::compute(&data).0 == md5 => return Ok(data),
+            Ok(_) => {
+                error = Some(RQError::Other(format!("图片 md5 校验失败：{}", url)));
+            }
+            Err(e) => error = Some(e),
+        }
+        if url != fallback {
+            tracing::debug!("图片链接可能已过期，改用 {} 重新下载", fallback);
+        }
+    }
+    Err(error.unwrap())
+}
+
+async fn fetch(url: &str) -> RQResult<Vec<u8>> {
+    let resp = reqwest::get(url)
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| RQError::Other(format!("下载图片失败：{}", e)))?;
+    if let Some(len) = resp.content_length() {
+        check_size(len)?;
+    }
+    let data = resp
+        .bytes()
+        .await
+        .map_err(|e| RQError::Other(format!("下载图片失败：{}", e)))?;
+    Ok(data.to_vec())
+}
@@ -13,37 +13,443 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! ## Feature flags
+//!
+//! - `audio`: 将普通音频编码为 SILK 格式后发送语音，参见 [`audio`]。
+//! - `image-convert`: 上传图片时，将 QQ 不支持的图片格式转码为 PNG，参见 [`image`]。
 #![deny(missing_docs)]
 
+pub mod account;
+pub mod announcement;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod essence;
+pub mod file;
 pub mod friend;
+pub mod friend_group;
 pub mod group;
+pub mod hook;
+pub mod image;
+pub mod permission;
+pub mod schedule;
+pub mod sender;
+pub mod state;
 pub mod structs;
+pub mod utils;
 
+use std::ops::Deref;
+
+use chocho_msg::{ImageRef, Message};
 use friend::Friend;
 use group::Group;
+use ricq::{
+    structs::{
+        FriendGroupInfo, FriendInfo, GroupInfo, JoinGroupRequest, NewFriendRequest, SelfInvited,
+    },
+    RQResult,
+};
+use ricq_core::command::friendlist::FriendListResponse;
+use sender::MessageSender;
+use structs::{AccountInfo, Gender, SentMessage, Target};
+
+/// 默认好友分组「我的好友」的 ID。
+pub const DEFAULT_FRIEND_GROUP_ID: u8 = 0;
 
 /// `ricq` 客户端的别名。
 pub type RQClient = std::sync::Arc<ricq::Client>;
 
 /// 客户端扩展方法。
+///
+/// 为所有指向 `ricq::Client` 的客户端句柄实现，例如 [`RQClient`] 和 `&ricq::Client`。
+/// [`friend`](ClientExt::friend) 和 [`group`](ClientExt::group) 创建的操作对象持有句柄的副本，
+/// 句柄为 `RQClient` 时，操作对象和发送结果可以脱离原来的客户端单独保存。
 #[async_trait::async_trait]
-pub trait ClientExt {
+pub trait ClientExt: Deref<Target = ricq::Client> + MessageSender + Clone {
     /// 进行好友操作。
-    fn friend(&self, uin: i64) -> Friend;
+    fn friend(&self, uin: i64) -> Friend<Self>;
 
     /// 进行群操作。
-    fn group(&self, uin: i64) -> Group;
+    fn group(&self, uin: i64) -> Group<Self>;
+
+    /// 当前登录账号的 QQ 号。
+    ///
+    /// 需要先通过 [`account::cache_self_uin`] 缓存，未缓存时返回 `None`。
+    /// 在 `#[chocho::main]` 的主函数和事件处理器收到的消息事件中，QQ 号已经被缓存。
+    ///
+    /// # Examples
+    ///
+    /// 在事件处理器中忽略机器人自己发送的群消息：
+    ///
+    /// ```no_run
+    /// use async_trait::async_trait;
+    /// use chocho::prelude::*;
+    /// use chocho::ricq::{client::event::GroupMessageEvent, handler::PartlyHandler};
+    ///
+    /// struct Handler;
+    ///
+    /// #[async_trait]
+    /// impl PartlyHandler for Handler {
+    ///     async fn handle_group_message(&self, GroupMessageEvent { client, inner }: GroupMessageEvent) {
+    ///         if client.self_uin() == Some(inner.from_uin) {
+    ///             return;
+    ///         }
+    ///         // ...
+    ///     }
+    /// }
+    /// ```
+    fn self_uin(&self) -> Option<i64>;
+
+    /// 获取当前登录账号的信息。
+    ///
+    /// 信息来自登录时服务器返回的缓存，不会发送网络请求。未登录时返回默认值，参见 [`AccountInfo`]。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// #[chocho::main]
+    /// async fn main(client: RQClient) {
+    ///     let account = client.account().await;
+    ///     tracing::info!("已登录 {}（{}）", account.nickname, account.uin);
+    /// }
+    /// ```
+    async fn account(&self) -> AccountInfo;
+
+    /// 获取好友列表。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// #[chocho::main]
+    /// async fn main(client: RQClient) -> RQResult<()> {
+    ///     for friend in client.friends().await? {
+    ///         println!("{} ({})", friend.nick, friend.uin);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn friends(&self) -> RQResult<Vec<FriendInfo>>;
+
+    /// 获取好友列表及好友分组信息。
+    async fn friend_list(&self) -> RQResult<FriendListResponse>;
+
+    /// 获取好友分组列表，按分组 ID 排序。
+    ///
+    /// 默认分组「我的好友」的 ID 为 [`DEFAULT_FRIEND_GROUP_ID`]。
+    /// 好友所在的分组可以通过 [`FriendInfo::group_id`] 获取。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let friends = client.friends().await?;
+    /// for group in client.friend_groups().await? {
+    ///     let count = friends.iter().filter(|f| f.group_id == group.group_id).count();
+    ///     println!("{}（{} 人）", group.group_name, count);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn friend_groups(&self) -> RQResult<Vec<FriendGroupInfo>>;
+
+    /// 将好友移动到分组。
+    ///
+    /// `group_id` 为目标分组的 ID，移回默认分组时使用 [`DEFAULT_FRIEND_GROUP_ID`]。分组不存在时返回错误。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let group = client.create_friend_group("同学").await?;
+    /// client.move_friend_to_group(12345678, group.group_id).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn move_friend_to_group(&self, uin: i64, group_id: u8) -> RQResult<()>;
+
+    /// 创建好友分组，返回新分组的信息。
+    ///
+    /// 分组名称会去除首尾空白，不能为空，也不能与已有分组重名。
+    /// 分组数量（包括默认分组）已达 [`friend_group::MAX_FRIEND_GROUPS`] 时返回错误。
+    async fn create_friend_group(&self, name: &str) -> RQResult<FriendGroupInfo>;
+
+    /// 获取群列表。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    ///
+    /// #[chocho::main]
+    /// async fn main(client: RQClient) -> RQResult<()> {
+    ///     for group in client.groups().await? {
+    ///         println!("{} ({})", group.name, group.code);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn groups(&self) -> RQResult<Vec<GroupInfo>>;
+
+    /// 同意好友请求。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_trait::async_trait;
+    /// use chocho::prelude::*;
+    /// use chocho::ricq::{client::event::FriendRequestEvent, handler::PartlyHandler};
+    ///
+    /// struct Handler;
+    ///
+    /// #[async_trait]
+    /// impl PartlyHandler for Handler {
+    ///     async fn handle_friend_request(&self, FriendRequestEvent { client, inner }: FriendRequestEvent) {
+    ///         let result = if inner.message.contains("chocho") {
+    ///             client.approve_friend_request(&inner).await
+    ///         } else {
+    ///             client.reject_friend_request(&inner).await
+    ///         };
+    ///         if let Err(e) = result {
+    ///             eprintln!("处理好友请求失败: {}", e);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    async fn approve_friend_request(&self, req: &NewFriendRequest) -> RQResult<()>;
+
+    /// 拒绝好友请求。
+    async fn reject_friend_request(&self, req: &NewFriendRequest) -> RQResult<()>;
+
+    /// 同意加群请求。
+    async fn approve_join_group_request(&self, req: &JoinGroupRequest) -> RQResult<()>;
+
+    /// 拒绝加群请求。
+    ///
+    /// `reason` 为拒绝理由，`block` 为是否不再接受此人的加群请求。
+    async fn reject_join_group_request(
+        &self,
+        req: &JoinGroupRequest,
+        reason: &str,
+        block: bool,
+    ) -> RQResult<()>;
+
+    /// 接受入群邀请。
+    async fn accept_group_invitation(&self, req: &SelfInvited) -> RQResult<()>;
+
+    /// 拒绝入群邀请。
+    async fn reject_group_invitation(&self, req: &SelfInvited) -> RQResult<()>;
+
+    /// 下载消息中的图片原图。
+    ///
+    /// 下载后会校验图片的 md5。消息中的链接过期或 md5 不符时，改用由 md5 拼接的链接重新下载。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_trait::async_trait;
+    /// use chocho::prelude::*;
+    /// use chocho::ricq::{client::event::GroupMessageEvent, handler::PartlyHandler};
+    ///
+    /// struct Handler;
+    ///
+    /// #[async_trait]
+    /// impl PartlyHandler for Handler {
+    ///     async fn handle_group_message(&self, GroupMessageEvent { client, inner }: GroupMessageEvent) {
+    ///         let msg = Message::from(inner.elements);
+    ///         for image in msg.images() {
+    ///             match client.download_image(&image).await {
+    ///                 Ok(data) => {
+    ///                     let name = format!("{}.img", hex::encode(image.md5()));
+    ///                     let _ = tokio::fs::write(name, data).await;
+    ///                 }
+    ///                 Err(e) => tracing::error!("下载图片失败：{}", e),
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    async fn download_image(&self, image: &ImageRef) -> RQResult<Vec<u8>>;
+
+    /// 向任意会话发送消息。
+    ///
+    /// 与 [`Friend::send`]、[`Group::send`] 和 [`GroupMember::send_temp_msg`](group::GroupMember::send_temp_msg)
+    /// 的发送流程相同，参见 [`MessageSender`]。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho::prelude::*;
+    /// use chocho::common::Target;
+    ///
+    /// # async fn _f(client: RQClient) -> RQResult<()> {
+    /// let targets = [
+    ///     Target::Friend(12345678),
+    ///     Target::Group(87654321),
+    ///     Target::GroupTemp { group: 87654321, uin: 23456789 },
+    /// ];
+    /// for target in targets {
+    ///     let sent = client.send_to(target, "你好".to_string()).await?;
+    ///     println!("{:?}", sent.receipt().seqs);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn send_to<M>(&self, target: Target, msg: M) -> RQResult<SentMessage<Self>>
+    where
+        M: Into<Message> + Send;
 }
 
-impl ClientExt for ricq::Client {
-    fn friend(&self, uin: i64) -> Friend {
-        Friend { client: self, uin }
+#[async_trait::async_trait]
+impl<C> ClientExt for C
+where
+    C: Deref<Target = ricq::Client> + MessageSender + Clone,
+{
+    fn friend(&self, uin: i64) -> Friend<Self> {
+        Friend {
+            client: self.clone(),
+            uin,
+        }
     }
 
-    fn group(&self, uin: i64) -> Group {
+    fn group(&self, uin: i64) -> Group<Self> {
         Group {
-            client: self,
+            client: self.clone(),
             code: uin,
         }
     }
+
+    fn self_uin(&self) -> Option<i64> {
+        account::cached_self_uin(self)
+    }
+
+    async fn account(&self) -> AccountInfo {
+        let uin = self.uin().await;
+        let info = self.account_info.read().await;
+        AccountInfo {
+            uin,
+            nickname: info.nickname.clone(),
+            age: info.age,
+            gender: Gender::from(info.gender),
+        }
+    }
+
+    async fn friends(&self) -> RQResult<Vec<FriendInfo>> {
+        Ok(self.get_friend_list().await?.friends)
+    }
+
+    async fn friend_list(&self) -> RQResult<FriendListResponse> {
+        self.get_friend_list().await
+    }
+
+    async fn friend_groups(&self) -> RQResult<Vec<FriendGroupInfo>> {
+        friend_group::list(self).await
+    }
+
+    async fn move_friend_to_group(&self, uin: i64, group_id: u8) -> RQResult<()> {
+        friend_group::move_friend(self, uin, group_id).await
+    }
+
+    async fn create_friend_group(&self, name: &str) -> RQResult<FriendGroupInfo> {
+        friend_group::create(self, name).await
+    }
+
+    async fn groups(&self) -> RQResult<Vec<GroupInfo>> {
+        self.get_group_list().await
+    }
+
+    async fn approve_friend_request(&self, req: &NewFriendRequest) -> RQResult<()> {
+        self.solve_friend_system_message(req.msg_seq, req.req_uin, true)
+            .await
+    }
+
+    async fn reject_friend_request(&self, req: &NewFriendRequest) -> RQResult<()> {
+        self.solve_friend_system_message(req.msg_seq, req.req_uin, false)
+            .await
+    }
+
+    async fn approve_join_group_request(&self, req: &JoinGroupRequest) -> RQResult<()> {
+        self.solve_group_system_message(
+            req.msg_seq,
+            req.req_uin,
+            req.group_code,
+            req.suspicious,
+            false,
+            true,
+            false,
+            String::new(),
+        )
+        .await
+    }
+
+    async fn reject_join_group_request(
+        &self,
+        req: &JoinGroupRequest,
+        reason: &str,
+        block: bool,
+    ) -> RQResult<()> {
+        self.solve_group_system_message(
+            req.msg_seq,
+            req.req_uin,
+            req.group_code,
+            req.suspicious,
+            false,
+            false,
+            block,
+            reason.to_string(),
+        )
+        .await
+    }
+
+    async fn accept_group_invitation(&self, req: &SelfInvited) -> RQResult<()> {
+        self.solve_group_system_message(
+            req.msg_seq,
+            req.invitor_uin,
+            req.group_code,
+            false,
+            true,
+            true,
+            false,
+            String::new(),
+        )
+        .await
+    }
+
+    async fn reject_group_invitation(&self, req: &SelfInvited) -> RQResult<()> {
+        self.solve_group_system_message(
+            req.msg_seq,
+            req.invitor_uin,
+            req.group_code,
+            false,
+            true,
+            false,
+            false,
+            String::new(),
+        )
+        .await
+    }
+
+    async fn download_image(&self, image: &ImageRef) -> RQResult<Vec<u8>> {
+        image::download_image_ref(image).await
+    }
+
+    async fn send_to<M>(&self, target: Target, msg: M) -> RQResult<SentMessage<Self>>
+    where
+        M: Into<Message> + Send,
+    {
+        let receipt = self.send_message(target, msg.into()).await?;
+        Ok(SentMessage::new(self.clone(), target, receipt))
+    }
+}
+
+/// QQ 头像的 URL。
+fn avatar_url(uin: i64, size: u32) -> String {
+    format!("https://q.qlogo.cn/g?b=qq&nk={}&s={}", uin, size)
 }
@@ -0,0 +1,89 @@
+//! 群管理操作的权限检查。
+//!
+//! 机器人不是管理员时，执行禁言、踢人、修改群名称等操作会收到服务器返回的错误，错误信息往往难以理解。
+//! 调用 [`set_permission_check`] 开启权限检查后，这些操作会先查询机器人在群内的权限，
+//! 权限不足时直接返回「权限不足」的错误。
+//!
+//! 权限检查需要额外请求一次群成员信息，因此默认关闭。也可以在操作前手动调用
+//! [`Group::my_permission`](crate::group::Group::my_permission) 检查。
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use chocho::prelude::*;
+//! use chocho::client::permission::set_permission_check;
+//!
+//! # async fn _f(client: RQClient) {
+//! set_permission_check(&client, true);
+//! if let Err(e) = client.group(87654321).member(12345678).mute(Duration::from_secs(600)).await {
+//!     println!("{}", e); // 权限不足：在群 87654321 中禁言成员需要管理员权限
+//! }
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use ricq::{structs::GroupMemberPermission, Client, RQError, RQResult};
+
+use crate::state;
+
+/// 客户端开启了权限检查的标记。
+struct PermissionCheck;
+
+/// 开启或关闭客户端的权限检查，默认关闭。
+pub fn set_permission_check(client: &Arc<Client>, enabled: bool) {
+    if enabled {
+        state::insert(client, PermissionCheck);
+    } else {
+        state::remove::<PermissionCheck>(client);
+    }
+}
+
+/// 客户端是否开启了权限检查。
+pub fn permission_check_enabled(client: &Client) -> bool {
+    state::get::<PermissionCheck>(client).is_some()
+}
+
+/// 获取机器人自身在群内的权限。
+pub(crate) async fn my_permission(client: &Client, code: i64) -> RQResult<GroupMemberPermission> {
+    let uin = crate::account::self_uin(client).await;
+    Ok(client.get_group_member_info(code, uin).await?.permission)
+}
+
+/// 执行操作所需的权限。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Required {
+    /// 管理员或群主。
+    Admin,
+    /// 群主。
+    Owner,
+}
+
+/// 开启了权限检查时，检查机器人是否有执行 `action` 的权限。
+pub(crate) async fn require(
+    client: &Client,
+    code: i64,
+    required: Required,
+    action: &str,
+) -> RQResult<()> {
+    if !permission_check_enabled(client) {
+        return Ok(());
+    }
+    let permission = my_permission(client, code).await?;
+    let allowed = match required {
+        Required::Admin => !matches!(permission, GroupMemberPermission::Member),
+        Required::Owner => matches!(permission, GroupMemberPermission::Owner),
+    };
+    if allowed {
+        return Ok(());
+    }
+    let required = match required {
+        Required::Admin => "管理员",
+        Required::Owner => "群主",
+    };
+    Err(RQError::Other(format!(
+        "权限不足：在群 {} 中{}需要{}权限",
+        code, action, required
+    )))
+}
@@ -0,0 +1,374 @@
+//! 发送调度。
+//!
+//! 为客户端设置限流后，通过 [`Friend`](crate::friend::Friend)、[`Group`](crate::group::Group)
+//! 与 [`GroupMember`](crate::group::GroupMember) 发送的消息都会进入发送队列，按令牌桶算法限速。队列按优先级出队，高优先级的消息可以插队到低优先级之前；
+//! 同优先级的消息按提交顺序发送。
+//!
+//! 未设置限流时，消息会立即发送。
+//!
+//! 除了整个客户端共用的限流，还可以通过 [`set_target_rate_limit`] 为每个群、每个好友分别限流，
+//! 避免向同一个会话刷屏。两种限流可以同时设置，消息需要先后取得两者的许可。
+//! 通过 [`RateLimit::max_wait`] 设置最长等待时间后，等待超时的消息不会发送，`send` 返回错误。
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use chocho::prelude::*;
+//! use chocho_client::schedule::{set_rate_limit, Priority, RateLimit};
+//!
+//! #[chocho::main]
+//! async fn main(client: RQClient) -> RQResult<()> {
+//!     // 最多连续发送 5 条消息，之后每秒发送 1 条
+//!     set_rate_limit(&client, RateLimit::new(5, Duration::from_secs(1)));
+//!
+//!     let group = client.group(12345678);
+//!     group.send("早上好".to_string()).await?;
+//!     group
+//!         .send_with_priority("服务器宕机了！".to_string(), Priority::High)
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! 群发通知时，每个群每秒最多 1 条，等待超过 10 秒则放弃：
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use chocho::prelude::*;
+//! use chocho_client::schedule::{set_target_rate_limit, RateLimit};
+//!
+//! #[chocho::main]
+//! async fn main(client: RQClient) {
+//!     set_target_rate_limit(
+//!         &client,
+//!         RateLimit::per_second(1, 3).max_wait(Duration::from_secs(10)),
+//!     );
+//!     for group in [12345678, 87654321] {
+//!         if let Err(e) = client.group(group).send("通知".to_string()).await {
+//!             tracing::warn!("群 {} 通知发送失败: {}", group, e);
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::{BinaryHeap, HashMap},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
+
+use ricq::{Client, RQError, RQResult};
+use tokio::{
+    sync::{oneshot, Notify},
+    time::Instant,
+};
+
+use crate::{state, structs::Target};
+
+/// 消息发送优先级。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// 低优先级，例如闲聊回复。
+    Low,
+    /// 普通优先级，`send` 方法的默认值。
+    #[default]
+    Normal,
+    /// 高优先级，例如告警通知。
+    High,
+}
+
+/// 令牌桶限流参数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// 令牌桶容量，即最多可以连续发送的消息数。
+    pub burst: u32,
+    /// 每生成一个令牌的间隔。
+    pub interval: Duration,
+    /// 最长等待时间，`None` 表示一直等待。
+    pub max_wait: Option<Duration>,
+}
+
+impl RateLimit {
+    /// 创建限流参数。
+    ///
+    /// # Arguments
+    ///
+    /// * `burst` - 最多可以连续发送的消息数，至少为 1。
+    /// * `interval` - 每生成一个令牌的间隔。
+    pub fn new(burst: u32, interval: Duration) -> Self {
+        Self {
+            burst: burst.max(1),
+            interval,
+            max_wait: None,
+        }
+    }
+
+    /// 按每秒消息数创建限流参数。
+    ///
+    /// # Arguments
+    ///
+    /// * `per_second` - 每秒最多发送的消息数，至少为 1。
+    /// * `burst` - 最多可以连续发送的消息数，至少为 1。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use chocho_client::schedule::RateLimit;
+    ///
+    /// let limit = RateLimit::per_second(4, 10);
+    /// assert_eq!(limit, RateLimit::new(10, Duration::from_millis(250)));
+    /// ```
+    pub fn per_second(per_second: u32, burst: u32) -> Self {
+        Self::new(burst, Duration::from_secs(1) / per_second.max(1))
+    }
+
+    /// 设置最长等待时间。等待许可超过此时间的消息不会发送，返回错误。
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    /// 直接构造的参数可能绕过 [`new`](Self::new) 的检查，`burst` 至少为 1。
+    fn normalized(self) -> Self {
+        Self {
+            burst: self.burst.max(1),
+            ..self
+        }
+    }
+}
+
+fn wait_timeout() -> RQError {
+    RQError::Other("等待发送许可超时".to_string())
+}
+
+struct Ticket {
+    priority: Priority,
+    seq: u64,
+    permit: oneshot::Sender<()>,
+}
+
+impl PartialEq for Ticket {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Ticket {}
+
+impl PartialOrd for Ticket {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ticket {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        // 优先级高者先出队，同优先级先提交者先出队
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct Scheduler {
+    queue: Mutex<BinaryHeap<Ticket>>,
+    notify: Notify,
+    seq: AtomicU64,
+    closed: AtomicBool,
+    max_wait: Option<Duration>,
+}
+
+/// 队列为空时，调度器检查客户端是否已释放的间隔。
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl Scheduler {
+    /// 调度器的后台任务。调度器被关闭或客户端被释放后退出。
+    async fn run(self: Arc<Self>, client: Weak<Client>, limit: RateLimit) {
+        let mut tokens = limit.burst;
+        let mut last_refill = Instant::now();
+        while !self.closed.load(Ordering::Acquire) && client.strong_count() > 0 {
+            // 补充令牌
+            let interval = limit.interval.as_nanos().max(1);
+            let refilled = (last_refill.elapsed().as_nanos() / interval).min(limit.burst as u128);
+            if refilled > 0 {
+                tokens = (tokens + refilled as u32).min(limit.burst);
+                last_refill += limit.interval * refilled as u32;
+            }
+            if tokens == limit.burst {
+                last_refill = Instant::now();
+            }
+            if tokens == 0 {
+                tokio::time::sleep_until(last_refill + limit.interval).await;
+                continue;
+            }
+
+            let ticket = self.queue.lock().expect("Failed locking queue").pop();
+            match ticket {
+                // 等待者已经放弃时，不消耗令牌
+                Some(ticket) => {
+                    if ticket.permit.send(()).is_ok() {
+                        tokens -= 1;
+                    }
+                }
+                None => {
+                    let _ = tokio::time::timeout(IDLE_CHECK_INTERVAL, self.notify.notified()).await;
+                }
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+        // 放行所有排队中的消息
+        let queue = std::mem::take(&mut *self.queue.lock().expect("Failed locking queue"));
+        for ticket in queue {
+            let _ = ticket.permit.send(());
+        }
+    }
+}
+
+/// 客户端的发送限流。释放时关闭调度器，放行排队中的消息。
+struct RateLimiter(Arc<Scheduler>);
+
+impl Drop for RateLimiter {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// 为客户端设置发送限流。
+///
+/// 重复调用会替换之前的设置。此函数需要在 tokio 运行时中调用。
+/// 限流的后台任务在取消限流或客户端释放后退出。`burst` 为 0 时按 1 处理。
+pub fn set_rate_limit(client: &Arc<Client>, limit: RateLimit) {
+    let limit = limit.normalized();
+    let scheduler = Arc::new(Scheduler {
+        max_wait: limit.max_wait,
+        ..Default::default()
+    });
+    tokio::spawn(scheduler.clone().run(Arc::downgrade(client), limit));
+    state::insert(client, RateLimiter(scheduler));
+}
+
+/// 取消客户端的发送限流，排队中的消息会立即发送。
+pub fn clear_rate_limit(client: &Client) {
+    state::remove::<RateLimiter>(client);
+}
+
+/// 按会话限流的令牌桶。
+///
+/// 每个会话只记录下一个令牌的理论到达时间（GCRA 算法），不需要后台任务。
+struct TargetLimiter {
+    limit: RateLimit,
+    arrivals: HashMap<Target, Instant>,
+}
+
+impl TargetLimiter {
+    /// 预约一个令牌，返回可以发送的时间。等待时间超过 `max_wait` 时不预约，返回 `None`。
+    fn reserve(&mut self, target: Target) -> Option<Instant> {
+        let now = Instant::now();
+        if self.arrivals.len() > 1024 {
+            self.arrivals.retain(|_, arrival| *arrival > now);
+        }
+        let arrival = self.arrivals.get(&target).copied().unwrap_or(now).max(now);
+        let tolerance = self.limit.interval * self.limit.burst.saturating_sub(1);
+        let start = arrival.checked_sub(tolerance).unwrap_or(now).max(now);
+        if matches!(self.limit.max_wait, Some(max_wait) if start - now > max_wait) {
+            return None;
+        }
+        self.arrivals.insert(target, arrival + self.limit.interval);
+        Some(start)
+    }
+
+    /// 归还 [`reserve`](Self::reserve) 预约的令牌，用于消息最终没有发送的情况。
+    fn release(&mut self, target: Target) {
+        if let Some(arrival) = self.arrivals.get_mut(&target) {
+            if let Some(released) = arrival.checked_sub(self.limit.interval) {
+                *arrival = released;
+            }
+        }
+    }
+}
+
+/// 为客户端设置按会话的发送限流，每个群、每个好友分别计算令牌。
+///
+/// 重复调用会替换之前的设置，并重置所有会话的令牌。按会话限流不区分优先级。`burst` 为 0 时按 1 处理。
+pub fn set_target_rate_limit(client: &Arc<Client>, limit: RateLimit) {
+    state::insert(
+        client,
+        Mutex::new(TargetLimiter {
+            limit: limit.normalized(),
+            arrivals: HashMap::new(),
+        }),
+    );
+}
+
+/// 取消客户端的按会话发送限流。
+pub fn clear_target_rate_limit(client: &Client) {
+    state::remove::<Mutex<TargetLimiter>>(client);
+}
+
+/// 等待发送许可。未设置限流时立即返回，等待超时时返回错误。
+///
+/// 先取得按会话限流的令牌，再等待整个客户端的限流；后者超时时归还会话的令牌。
+pub(crate) async fn acquire(client: &Client, target: Target, priority: Priority) -> RQResult<()> {
+    let target_limiter = state::get::<Mutex<TargetLimiter>>(client);
+    if let Some(limiter) = &target_limiter {
+        let start = limiter
+            .lock()
+            .expect("Failed locking TargetLimiter")
+            .reserve(target);
+        match start {
+            Some(start) => tokio::time::sleep_until(start).await,
+            None => return Err(wait_timeout()),
+        }
+    }
+
+    let result = acquire_global(client, priority).await;
+    if let (Err(_), Some(limiter)) = (&result, target_limiter) {
+        limiter
+            .lock()
+            .expect("Failed locking TargetLimiter")
+            .release(target);
+    }
+    result
+}
+
+/// 等待整个客户端的发送许可。
+async fn acquire_global(client: &Client, priority: Priority) -> RQResult<()> {
+    // 只持有调度器，取消或替换限流时调度器可以立即关闭
+    let scheduler = state::get::<RateLimiter>(client).map(|limiter| limiter.0.clone());
+    let Some(scheduler) = scheduler else {
+        return Ok(());
+    };
+    let (permit, wait) = oneshot::channel();
+    scheduler
+        .queue
+        .lock()
+        .expect("Failed locking queue")
+        .push(Ticket {
+            priority,
+            seq: scheduler.seq.fetch_add(1, Ordering::Relaxed),
+            permit,
+        });
+    scheduler.notify.notify_one();
+    match scheduler.max_wait {
+        // 超时后丢弃接收端，调度器不会为此消耗令牌
+        Some(max_wait) => match tokio::time::timeout(max_wait, wait).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(wait_timeout()),
+        },
+        None => {
+            let _ = wait.await;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! 消息发送的抽象。
+//!
+//! [`MessageSender`] 抽象了“向会话发送消息”的能力，`ricq::Client` 实现了此特性。
+//! [`Friend`](crate::friend::Friend)、[`Group`](crate::group::Group) 和
+//! [`GroupMember`](crate::group::GroupMember) 的发送方法都通过此特性发送消息，
+//! 因此处理消息的逻辑可以在测试中使用 [`MockClient`]，不需要真实的连接就能断言机器人发送了哪些消息。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_client::sender::{MessageSender, MockClient};
+//! use chocho_client::structs::Target;
+//! use chocho_msg::{msg, Message};
+//!
+//! /// 收到“ping”时回复“pong”。
+//! async fn on_message(sender: &impl MessageSender, target: Target, msg: Message) {
+//!     if msg.text() == "ping" {
+//!         sender.send_message(target, msg!["pong"]).await.unwrap();
+//!     }
+//! }
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mock = MockClient::new();
+//! on_message(&mock, Target::Group(87654321), msg!["ping"]).await;
+//! on_message(&mock, Target::Group(87654321), msg!["hello"]).await;
+//! assert_eq!(mock.sent(), [(Target::Group(87654321), msg!["pong"])]);
+//! # });
+//! ```
+//!
+//! 通过 [`MockClient::group`] 和 [`MockClient::friend`] 测试使用 `Group`、`Friend` 发送消息的代码：
+//!
+//! ```
+//! use chocho_client::group::Group;
+//! use chocho_client::sender::{MessageSender, MockClient};
+//! use chocho_client::structs::Target;
+//! use chocho_msg::msg;
+//! use ricq::RQResult;
+//!
+//! async fn greet<S: MessageSender + Clone>(group: Group<S>) -> RQResult<()> {
+//!     let sent = group.send("大家好".to_string()).await?;
+//!     sent.recall().await
+//! }
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mock = MockClient::new();
+//! greet(mock.group(87654321)).await.unwrap();
+//! assert_eq!(mock.sent_to(Target::Group(87654321)), [msg!["大家好"]]);
+//! assert_eq!(mock.recalled().len(), 1);
+//! # });
+//! ```
+
+use std::sync::{
+    atomic::{AtomicI32, Ordering},
+    Arc, Mutex,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chocho_msg::{forward::ForwardNode, Message, RQElem};
+use ricq::{
+    structs::{FriendAudio, GroupAudio, MessageReceipt},
+    Client, RQError, RQResult,
+};
+use ricq_core::pb::msg::Ptt;
+
+use crate::friend::Friend;
+use crate::group::Group;
+use crate::schedule::{self, Priority};
+use crate::structs::Target;
+use crate::{account, hook, image};
+
+/// 可以发送消息的对象。
+#[async_trait::async_trait]
+pub trait MessageSender: Send + Sync {
+    /// 向 `target` 发送消息。
+    async fn send_message(&self, target: Target, msg: Message) -> RQResult<MessageReceipt>;
+
+    /// 以指定优先级向 `target` 发送消息。
+    ///
+    /// 默认忽略优先级，直接调用 [`send_message`](Self::send_message)。
+    async fn send_message_with_priority(
+        &self,
+        target: Target,
+        msg: Message,
+        priority: Priority,
+    ) -> RQResult<MessageReceipt> {
+        let _ = priority;
+        self.send_message(target, msg).await
+    }
+
+    /// 向 `target` 发送合并转发消息。
+    ///
+    /// 默认返回错误。
+    async fn send_forward(
+        &self,
+        target: Target,
+        nodes: Vec<ForwardNode>,
+    ) -> RQResult<MessageReceipt> {
+        let _ = nodes;
+        Err(RQError::Other(format!(
+            "不支持向 {:?} 发送合并转发消息",
+            target
+        )))
+    }
+
+    /// 向 `target` 发送已上传的语音。
+    ///
+    /// 默认返回错误。
+    async fn send_audio(&self, target: Target, audio: Ptt) -> RQResult<MessageReceipt> {
+        let _ = audio;
+        Err(RQError::Other(format!("不支持向 {:?} 发送语音", target)))
+    }
+
+    /// 撤回发送到 `target` 的消息。
+    ///
+    /// 默认返回错误。
+    async fn recall_message(&self, target: Target, receipt: MessageReceipt) -> RQResult<()> {
+        let _ = receipt;
+        Err(RQError::Other(format!(
+            "不支持撤回发送到 {:?} 的消息",
+            target
+        )))
+    }
+
+    /// 发送者自身的 QQ 号，用于构造引用回复。
+    ///
+    /// 默认返回 0。
+    async fn sender_uin(&self) -> i64 {
+        0
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageSender + ?Sized> MessageSender for &T {
+    async fn send_message(&self, target: Target, msg: Message) -> RQResult<MessageReceipt> {
+        (**self).send_message(target, msg).await
+    }
+
+    async fn send_message_with_priority(
+        &self,
+        target: Target,
+        msg: Message,
+        priority: Priority,
+    ) -> RQResult<MessageReceipt> {
+        (**self)
+            .send_message_with_priority(target, msg, priority)
+            .await
+    }
+
+    async fn send_forward(
+        &self,
+        target: Target,
+        nodes: Vec<ForwardNode>,
+    ) -> RQResult<MessageReceipt> {
+        (**self).send_forward(target, nodes).await
+    }
+
+    async fn send_audio(&self, target: Target, audio: Ptt) -> RQResult<MessageReceipt> {
+        (**self).send_audio(target, audio).await
+    }
+
+    async fn recall_message(&self, target: Target, receipt: MessageReceipt) -> RQResult<()> {
+        (**self).recall_message(target, receipt).await
+    }
+
+    async fn sender_uin(&self) -> i64 {
+        (**self).sender_uin().await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: MessageSender + ?Sized> MessageSender for Arc<T> {
+    async fn send_message(&self, target: Target, msg: Message) -> RQResult<MessageReceipt> {
+        (**self).send_message(target, msg).await
+    }
+
+    async fn send_message_with_priority(
+        &self,
+        target: Target,
+        msg: Message,
+        priority: Priority,
+    ) -> RQResult<MessageReceipt> {
+        (**self)
+            .send_message_with_priority(target, msg, priority)
+            .await
+    }
+
+    async fn send_forward(
+        &self,
+        target: Target,
+        nodes: Vec<ForwardNode>,
+    ) -> RQResult<MessageReceipt> {
+        (**self).send_forward(target, nodes).await
+    }
+
+    async fn send_audio(&self, target: Target, audio: Ptt) -> RQResult<MessageReceipt> {
+        (**self).send_audio(target, audio).await
+    }
+
+    async fn recall_message(&self, target: Target, receipt: MessageReceipt) -> RQResult<()> {
+        (**self).recall_message(target, receipt).await
+    }
+
+    async fn sender_uin(&self) -> i64 {
+        (**self).sender_uin().await
+    }
+}
+
+/// 发送消息的完整流程：依次执行[钩子](crate::hook)、上传待上传的图片、等待[限流](crate::schedule)，最后发送。
+///
+/// 合并转发中的每条消息分别经过钩子并上传图片，语音只等待限流。
+#[async_trait::async_trait]
+impl MessageSender for Client {
+    async fn send_message(&self, target: Target, msg: Message) -> RQResult<MessageReceipt> {
+        self.send_message_with_priority(target, msg, Priority::Normal)
+            .await
+    }
+
+    async fn send_message_with_priority(
+        &self,
+        target: Target,
+        mut msg: Message,
+        priority: Priority,
+    ) -> RQResult<MessageReceipt> {
+        hook::before_send(self, target, &mut msg)?;
+        upload_images(self, target, &mut msg).await?;
+        schedule::acquire(self, target, priority).await?;
+        match target {
+            Target::Friend(uin) => self.send_friend_message(uin, msg.into()).await,
+            Target::Group(code) => {
+                if msg.is_long() {
+                    self.send_group_long_message(code, msg.into()).await
+                } else {
+                    self.send_group_message(code, msg.into()).await
+                }
+            }
+            Target::GroupTemp { group, uin } => {
+                self.send_group_temp_message(group, uin, msg.into()).await
+            }
+        }
+    }
+
+    async fn send_forward(
+        &self,
+        target: Target,
+        mut nodes: Vec<ForwardNode>,
+    ) -> RQResult<MessageReceipt> {
+        let Target::Group(code) = target else {
+            return Err(RQError::Other(format!(
+                "不支持向 {:?} 发送合并转发消息",
+                target
+            )));
+        };
+        for msg in forward_messages(&mut nodes) {
+            hook::before_send(self, target, msg)?;
+            upload_images(self, target, msg).await?;
+        }
+        schedule::acquire(self, target, Priority::Normal).await?;
+        let nodes = nodes.into_iter().map(Into::into).collect();
+        self.send_group_forward_message(code, nodes).await
+    }
+
+    async fn send_audio(&self, target: Target, audio: Ptt) -> RQResult<MessageReceipt> {
+        match target {
+            Target::Friend(uin) => {
+                schedule::acquire(self, target, Priority::Normal).await?;
+                self.send_friend_audio(uin, FriendAudio(audio)).await
+            }
+            Target::Group(code) => {
+                schedule::acquire(self, target, Priority::Normal).await?;
+                self.send_group_audio(code, GroupAudio(audio)).await
+            }
+            Target::GroupTemp { .. } => Err(RQError::Other("群临时会话不支持发送语音".to_string())),
+        }
+    }
+
+    async fn recall_message(&self, target: Target, receipt: MessageReceipt) -> RQResult<()> {
+        match target {
+            Target::Friend(uin) => {
+                self.recall_friend_message(uin, receipt.time, receipt.seqs, receipt.rands)
+                    .await
+            }
+            Target::Group(code) => {
+                self.recall_group_message(code, receipt.seqs, receipt.rands)
+                    .await
+            }
+            Target::GroupTemp { .. } => {
+                Err(RQError::Other("群临时会话中的消息不能撤回".to_string()))
+            }
+        }
+    }
+
+    async fn sender_uin(&self) -> i64 {
+        account::self_uin(self).await
+    }
+}
+
+/// 上传消息中待上传的图片。
+async fn upload_images(client: &Client, target: Target, msg: &mut Message) -> RQResult<()> {
+    match target {
+        Target::Friend(uin) => {
+            msg.upload_images(|data| async move {
+                let image = image::prepare_image(&data, "图片")?;
+                client
+                    .upload_friend_image(uin, &image)
+                    .await
+                    .map(RQElem::from)
+            })
+            .await
+        }
+        Target::Group(code) => {
+            msg.upload_images(|data| async move {
+                let image = image::prepare_image(&data, "图片")?;
+                client
+                    .upload_group_image(code, &image)
+                    .await
+                    .map(RQElem::from)
+            })
+            .await
+        }
+        Target::GroupTemp { .. } if msg.has_pending_images() => {
+            Err(RQError::Other("临时消息不支持待上传的图片".to_string()))
+        }
+        Target::GroupTemp { .. } => Ok(()),
+    }
+}
+
+/// 合并转发中的所有消息，包括嵌套的合并转发中的消息。
+fn forward_messages(nodes: &mut [ForwardNode]) -> Vec<&mut Message> {
+    let mut messages = vec![];
+    for node in nodes {
+        match node {
+            ForwardNode::Message { message, .. } => messages.push(message),
+            ForwardNode::Forward { nodes, .. } => messages.extend(forward_messages(nodes)),
+        }
+    }
+    messages
+}
+
+/// 记录发送的消息而不实际发送的客户端，用于测试。
+///
+/// 返回的回执中，`seqs` 从 1 开始按发送顺序递增，`rands` 为空。
+/// 普通消息、合并转发消息和语音分别记录，共用同一个序号。
+#[derive(Debug, Default)]
+pub struct MockClient {
+    seq: AtomicI32,
+    sent: Mutex<Vec<(Target, Message)>>,
+    forwarded: Mutex<Vec<(Target, Vec<ForwardNode>)>>,
+    audios: Mutex<Vec<(Target, Ptt)>>,
+    recalled: Mutex<Vec<(Target, MessageReceipt)>>,
+}
+
+impl MockClient {
+    /// 创建客户端。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 进行好友操作，只能使用发送消息相关的方法。
+    pub fn friend(&self, uin: i64) -> Friend<&Self> {
+        Friend { client: self, uin }
+    }
+
+    /// 进行群操作，只能使用发送消息相关的方法。
+    pub fn group(&self, code: i64) -> Group<&Self> {
+        Group { client: self, code }
+    }
+
+    /// 已发送的所有消息，按发送顺序排列。
+    pub fn sent(&self) -> Vec<(Target, Message)> {
+        self.sent.lock().expect("Failed locking sent").clone()
+    }
+
+    /// 取出已发送的所有消息，并清空记录。
+    pub fn take_sent(&self) -> Vec<(Target, Message)> {
+        std::mem::take(&mut *self.sent.lock().expect("Failed locking sent"))
+    }
+
+    /// 发送到 `target` 的所有消息。
+    pub fn sent_to(&self, target: Target) -> Vec<Message> {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(t, _)| *t == target)
+            .map(|(_, msg)| msg.clone())
+            .collect()
+    }
+
+    /// 已发送的所有合并转发消息，按发送顺序排列。
+    pub fn forwarded(&self) -> Vec<(Target, Vec<ForwardNode>)> {
+        self.forwarded
+            .lock()
+            .expect("Failed locking forwarded")
+            .clone()
+    }
+
+    /// 已发送的所有语音，按发送顺序排列。
+    pub fn audios(&self) -> Vec<(Target, Ptt)> {
+        self.audios.lock().expect("Failed locking audios").clone()
+    }
+
+    /// 生成下一条消息的回执。
+    fn next_receipt(&self) -> MessageReceipt {
+        MessageReceipt {
+            seqs: vec![self.seq.fetch_add(1, Ordering::SeqCst) + 1],
+            rands: vec![],
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64,
+        }
+    }
+
+    /// 已撤回的所有消息的会话与回执，按撤回顺序排列。
+    pub fn recalled(&self) -> Vec<(Target, MessageReceipt)> {
+        self.recalled
+            .lock()
+            .expect("Failed locking recalled")
+            .clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageSender for MockClient {
+    async fn send_message(&self, target: Target, msg: Message) -> RQResult<MessageReceipt> {
+        self.sent
+            .lock()
+            .expect("Failed locking sent")
+            .push((target, msg));
+        Ok(self.next_receipt())
+    }
+
+    async fn send_forward(
+        &self,
+        target: Target,
+        nodes: Vec<ForwardNode>,
+    ) -> RQResult<MessageReceipt> {
+        self.forwarded
+            .lock()
+            .expect("Failed locking forwarded")
+            .push((target, nodes));
+        Ok(self.next_receipt())
+    }
+
+    async fn send_audio(&self, target: Target, audio: Ptt) -> RQResult<MessageReceipt> {
+        self.audios
+            .lock()
+            .expect("Failed locking audios")
+            .push((target, audio));
+        Ok(self.next_receipt())
+    }
+
+    async fn recall_message(&self, target: Target, receipt: MessageReceipt) -> RQResult<()> {
+        self.recalled
+            .lock()
+            .expect("Failed locking recalled")
+            .push((target, receipt));
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+//! 与客户端生命周期绑定的状态。
+//!
+//! ricq 的 [`Client`] 不能附加额外的数据，发送钩子、限流、权限检查等扩展功能的状态保存在这里，
+//! 每个客户端按类型各保存一份。
+//!
+//! 登记状态时会持有客户端的弱引用：客户端释放前，它的内存不会被复用，新的客户端不会误用旧客户端的状态；
+//! 客户端释放后，它的状态在下一次访问任意客户端的状态时被清理。
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use chocho::prelude::*;
+//! use chocho_client::state;
+//!
+//! #[derive(Default)]
+//! struct Counter(AtomicUsize);
+//!
+//! # fn _f(client: RQClient) {
+//! state::get_or_insert_with(&client, Counter::default)
+//!     .0
+//!     .fetch_add(1, Ordering::Relaxed);
+//! let count = state::get::<Counter>(&client).map_or(0, |c| c.0.load(Ordering::Relaxed));
+//! assert_eq!(count, 1);
+//! # }
+//! ```
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use once_cell::sync::Lazy;
+use ricq::Client;
+
+type Value = Arc<dyn Any + Send + Sync>;
+
+struct Entry {
+    client: Weak<Client>,
+    values: HashMap<TypeId, Value>,
+}
+
+static STATES: Lazy<Mutex<HashMap<usize, Entry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn client_key(client: &Client) -> usize {
+    client as *const Client as usize
+}
+
+/// 清理已释放的客户端的状态后，在锁内执行 `f`。
+///
+/// 被清理的状态在锁外释放，以便状态的 `Drop` 中可以再次访问状态。
+fn with_states<R>(f: impl FnOnce(&mut HashMap<usize, Entry>) -> R) -> R {
+    let (result, _stale) = {
+        let mut states = STATES.lock().expect("Failed locking STATES");
+        let keys: Vec<_> = states
+            .iter()
+            .filter(|(_, entry)| entry.client.strong_count() == 0)
+            .map(|(key, _)| *key)
+            .collect();
+        let stale: Vec<_> = keys.iter().filter_map(|key| states.remove(key)).collect();
+        (f(&mut states), stale)
+    };
+    result
+}
+
+fn entry<'a>(states: &'a mut HashMap<usize, Entry>, client: &Arc<Client>) -> &'a mut Entry {
+    states.entry(client_key(client)).or_insert_with(|| Entry {
+        client: Arc::downgrade(client),
+        values: HashMap::new(),
+    })
+}
+
+fn downcast<T: Any + Send + Sync>(value: Value) -> Arc<T> {
+    value
+        .downcast()
+        .unwrap_or_else(|_| unreachable!("state is keyed by its type id"))
+}
+
+/// 获取客户端的 `T` 类型状态。
+pub fn get<T: Any + Send + Sync>(client: &Client) -> Option<Arc<T>> {
+    with_states(|states| {
+        states
+            .get(&client_key(client))?
+            .values
+            .get(&TypeId::of::<T>())
+            .cloned()
+    })
+    .map(downcast)
+}
+
+/// 获取客户端的 `T` 类型状态，不存在时用 `init` 创建。
+///
+/// `init` 在锁内执行，其中不能访问客户端的状态。
+pub fn get_or_insert_with<T: Any + Send + Sync>(
+    client: &Arc<Client>,
+    init: impl FnOnce() -> T,
+) -> Arc<T> {
+    let value = with_states(|states| {
+        entry(states, client)
+            .values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(init()))
+            .clone()
+    });
+    downcast(value)
+}
+
+/// 设置客户端的 `T` 类型状态，返回之前的状态。
+pub fn insert<T: Any + Send + Sync>(client: &Arc<Client>, value: T) -> Option<Arc<T>> {
+    with_states(|states| {
+        entry(states, client)
+            .values
+            .insert(TypeId::of::<T>(), Arc::new(value))
+    })
+    .map(downcast)
+}
+
+/// 移除客户端的 `T` 类型状态，返回之前的状态。
+pub fn remove<T: Any + Send + Sync>(client: &Client) -> Option<Arc<T>> {
+    with_states(|states| {
+        states
+            .get_mut(&client_key(client))?
+            .values
+            .remove(&TypeId::of::<T>())
+    })
+    .map(downcast)
+}
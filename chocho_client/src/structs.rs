@@ -1,5 +1,7 @@
 //! 一些结构体。
 
+use crate::{sender::MessageSender, RQClient};
+
 /// 音频编码。
 ///
 /// # Examples
@@ -20,3 +22,246 @@ pub enum AudioCodeC {
     /// SILK 编码。
     Silk,
 }
+
+/// 消息发送的目标会话，参见 [`ClientExt::send_to`](crate::ClientExt::send_to)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// 好友。
+    Friend(i64),
+    /// 群。
+    Group(i64),
+    /// 群临时会话。
+    GroupTemp {
+        /// 群号。
+        group: i64,
+        /// 群成员 QQ 号。
+        uin: i64,
+    },
+}
+
+/// 当前登录账号的信息，参见 [`ClientExt::account`](crate::ClientExt::account)。
+///
+/// 未登录或服务器未返回的字段取默认值：QQ 号为 0，昵称为空，年龄为 0，性别为 [`Gender::Unknown`]。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountInfo {
+    /// QQ 号。
+    pub uin: i64,
+    /// 昵称。
+    pub nickname: String,
+    /// 年龄。
+    pub age: u8,
+    /// 性别。
+    pub gender: Gender,
+}
+
+/// 性别。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Gender {
+    /// 男。
+    Male,
+    /// 女。
+    Female,
+    /// 未知或未设置。
+    #[default]
+    Unknown,
+}
+
+impl From<u8> for Gender {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Male,
+            1 => Self::Female,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// 已发送的消息，可以直接撤回或引用回复。
+///
+/// 持有发送时使用的客户端 `S`，不借用发送时的 [`Group`](crate::group::Group)，因此可以在 `Group` 被丢弃后继续使用。
+/// 撤回和回复通过 [`MessageSender`] 进行。
+/// 可以通过 [`receipt`](SentMessage::receipt) 或 `From` 转换获取原始的回执。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use chocho::prelude::*;
+///
+/// # async fn _f(client: RQClient) -> RQResult<()> {
+/// let sent = client.group(87654321).send("五秒后撤回".to_string()).await?;
+/// let reply = sent.reply("这是对上一条消息的回复".to_string()).await?;
+/// tokio::time::sleep(Duration::from_secs(5)).await;
+/// sent.recall().await?;
+/// reply.recall().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SentMessage<S = RQClient> {
+    client: S,
+    target: Target,
+    receipt: ricq::structs::MessageReceipt,
+}
+
+impl<S> SentMessage<S> {
+    pub(crate) fn new(client: S, target: Target, receipt: ricq::structs::MessageReceipt) -> Self {
+        Self {
+            client,
+            target,
+            receipt,
+        }
+    }
+
+    /// 消息发送到的会话。
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// 原始的回执。
+    pub fn receipt(&self) -> &ricq::structs::MessageReceipt {
+        &self.receipt
+    }
+}
+
+impl<S: MessageSender + Clone> SentMessage<S> {
+    /// 撤回消息。群临时会话中的消息不能撤回。
+    pub async fn recall(&self) -> ricq::RQResult<()> {
+        self.client
+            .recall_message(self.target, self.receipt.clone())
+            .await
+    }
+
+    /// 在同一会话中引用此消息回复。
+    ///
+    /// 回执中不包含原消息的内容，因此引用中不会显示原消息，参见
+    /// [`Message::reply_to_receipt`](chocho_msg::Message::reply_to_receipt)。
+    pub async fn reply(
+        &self,
+        msg: impl Into<chocho_msg::Message>,
+    ) -> ricq::RQResult<SentMessage<S>> {
+        let mut msg = msg.into();
+        let sender = self.client.sender_uin().await;
+        msg.reply_to_receipt(&self.receipt, sender);
+        let receipt = self.client.send_message(self.target, msg).await?;
+        Ok(SentMessage::new(self.client.clone(), self.target, receipt))
+    }
+}
+
+impl<S> std::fmt::Debug for SentMessage<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SentMessage")
+            .field("target", &self.target)
+            .field("receipt", &self.receipt)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> From<SentMessage<S>> for ricq::structs::MessageReceipt {
+    fn from(sent: SentMessage<S>) -> Self {
+        sent.receipt
+    }
+}
+
+/// 戳一戳的类型。
+///
+/// 除 [`PokeType::Poke`] 外，其余类型是以消息形式发送的特效，不针对特定的成员。
+///
+/// # Examples
+///
+/// ```
+/// # use chocho::prelude::*;
+/// # async fn test(client: RQClient) -> anyhow::Result<()> {
+/// use chocho::common::PokeType;
+///
+/// client.friend(12345678).poke(Some(PokeType::ShowLove)).await?;
+/// client.group(87654321).poke(12345678, None).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PokeType {
+    /// 戳一戳。
+    Poke,
+    /// 比心。
+    ShowLove,
+    /// 点赞。
+    Like,
+    /// 心碎。
+    Heartbroken,
+    /// 666。
+    SixSixSix,
+    /// 放大招。
+    FangDaZhao,
+    /// 其它类型，参数为戳一戳的类型 ID。
+    Custom(u32),
+}
+
+impl PokeType {
+    /// 戳一戳的类型 ID。
+    pub fn id(&self) -> u32 {
+        match self {
+            PokeType::Poke => 1,
+            PokeType::ShowLove => 2,
+            PokeType::Like => 3,
+            PokeType::Heartbroken => 4,
+            PokeType::SixSixSix => 5,
+            PokeType::FangDaZhao => 6,
+            PokeType::Custom(id) => *id,
+        }
+    }
+
+    /// 特效名称。
+    pub fn name(&self) -> &'static str {
+        match self {
+            PokeType::Poke => "戳一戳",
+            PokeType::ShowLove => "比心",
+            PokeType::Like => "点赞",
+            PokeType::Heartbroken => "心碎",
+            PokeType::SixSixSix => "666",
+            PokeType::FangDaZhao => "放大招",
+            PokeType::Custom(_) => "",
+        }
+    }
+
+    /// 以消息形式发送时的消息。
+    pub(crate) fn to_message(self) -> chocho_msg::Message {
+        use prost::Message as _;
+        use ricq_core::pb::msg::{elem::Elem, CommonElem};
+
+        let info = pb::MsgElemInfoServtype2 {
+            poke_type: Some(self.id()),
+            vaspoke_id: Some(u32::MAX),
+            vaspoke_name: Some(self.name().as_bytes().to_vec()),
+            vaspoke_minver: Some(b"7.2.0".to_vec()),
+            ..Default::default()
+        };
+        let elem = Elem::CommonElem(CommonElem {
+            service_type: Some(2),
+            pb_elem: Some(info.encode_to_vec()),
+            business_type: Some(self.id() as i32),
+        });
+        ricq::msg::MessageChain::new(vec![elem]).into()
+    }
+}
+
+mod pb {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MsgElemInfoServtype2 {
+        #[prost(uint32, optional, tag = "1")]
+        pub poke_type: Option<u32>,
+        #[prost(bytes = "vec", optional, tag = "2")]
+        pub poke_summary: Option<Vec<u8>>,
+        #[prost(uint32, optional, tag = "3")]
+        pub double_hit: Option<u32>,
+        #[prost(uint32, optional, tag = "4")]
+        pub vaspoke_id: Option<u32>,
+        #[prost(bytes = "vec", optional, tag = "5")]
+        pub vaspoke_name: Option<Vec<u8>>,
+        #[prost(bytes = "vec", optional, tag = "6")]
+        pub vaspoke_minver: Option<Vec<u8>>,
+        #[prost(uint32, optional, tag = "7")]
+        pub poke_strength: Option<u32>,
+    }
+}
@@ -0,0 +1,284 @@
+//! 工具函数。
+
+use std::{
+    fmt::Display,
+    future::Future,
+    io::Write,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use ricq::RQError;
+use tokio::time::{timeout, timeout_at, Instant};
+
+/// 重试一个异步操作。
+///
+/// 最多调用 `f` `times` 次（至少一次），返回第一次成功的结果；全部失败时返回最后一次的错误。
+///
+/// # Examples
+///
+/// ```
+/// # let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+/// # rt.block_on(async {
+/// let mut count = 0;
+/// let result = chocho::utils::retry(3, || {
+///     count += 1;
+///     let ok = count == 2;
+///     async move { if ok { Ok(count) } else { Err("失败") } }
+/// })
+/// .await;
+/// assert_eq!(result, Ok(2));
+/// # });
+/// ```
+pub async fn retry<T, E, Fut>(times: usize, mut f: impl FnMut() -> Fut) -> Result<T, E>
+where
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut result = f().await;
+    for _ in 1..times {
+        if result.is_ok() {
+            break;
+        }
+        result = f().await;
+    }
+    result
+}
+
+/// [`retry_with_timeout`] 的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryError<E> {
+    /// 最后一次调用超时，或超过了总时限。
+    Timeout,
+    /// 最后一次调用返回的错误。
+    Inner(E),
+}
+
+impl<E: Display> Display for RetryError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryError::Timeout => write!(f, "操作超时"),
+            RetryError::Inner(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for RetryError<E> {}
+
+/// 带超时的重试。
+///
+/// 与 [`retry`] 相同，但每次调用最多等待 `per_call_timeout`，超时视为失败并继续重试。
+/// 指定 `overall_deadline` 时，到达该时刻后立即中止正在进行的调用，不再重试。
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use chocho::utils::{retry_with_timeout, RetryError};
+/// use tokio::time::Instant;
+///
+/// # let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+/// # rt.block_on(async {
+/// // 永远不会完成的操作
+/// let result: Result<(), RetryError<()>> = retry_with_timeout(
+///     3,
+///     Duration::from_millis(10),
+///     Some(Instant::now() + Duration::from_millis(15)),
+///     || std::future::pending(),
+/// )
+/// .await;
+/// assert_eq!(result, Err(RetryError::Timeout));
+/// # });
+/// ```
+pub async fn retry_with_timeout<T, E, Fut>(
+    times: usize,
+    per_call_timeout: Duration,
+    overall_deadline: Option<Instant>,
+    mut f: impl FnMut() -> Fut,
+) -> Result<T, RetryError<E>>
+where
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut error = RetryError::Timeout;
+    for _ in 0..times.max(1) {
+        if overall_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(RetryError::Timeout);
+        }
+        let call = timeout(per_call_timeout, f());
+        let result = match overall_deadline {
+            Some(deadline) => match timeout_at(deadline, call).await {
+                Ok(result) => result,
+                Err(_) => return Err(RetryError::Timeout),
+            },
+            None => call.await,
+        };
+        match result {
+            Ok(Ok(value)) => return Ok(value),
+            Ok(Err(e)) => error = RetryError::Inner(e),
+            Err(_) => error = RetryError::Timeout,
+        }
+    }
+    Err(error)
+}
+
+/// 重试策略。
+///
+/// 第一次失败后等待 `delay`，之后每次等待的时间乘以 `backoff`，但不超过 `max_delay`。
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use chocho::utils::RetryPolicy;
+///
+/// let policy = RetryPolicy {
+///     times: 5,
+///     delay: Duration::from_millis(500),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最多调用的次数（至少一次），默认为 3 次。
+    pub times: usize,
+    /// 第一次重试前等待的时间，默认为 1 秒。
+    pub delay: Duration,
+    /// 每次重试后等待时间的倍数，默认为 2。
+    pub backoff: u32,
+    /// 每次重试前最长等待的时间，默认为 1 分钟。
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            times: 3,
+            delay: Duration::from_secs(1),
+            backoff: 2,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// 判断 ricq 的错误是否是临时性的，可以重试。
+///
+/// 超时、网络错误和 IO 错误视为临时性错误；其余错误（例如被禁言、被风控时服务器返回的错误）
+/// 重试也不会成功，视为永久性错误。
+pub fn is_retryable(error: &RQError) -> bool {
+    matches!(error, RQError::Timeout | RQError::Network | RQError::IO(_))
+}
+
+/// 按重试策略重试一个异步操作，只重试 `retryable` 返回 `true` 的错误。
+///
+/// 返回第一次成功的结果；遇到不可重试的错误，或重试次数用尽时，返回该次的错误。每次重试都会记录日志。
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use chocho::utils::{retry_with_policy, RetryPolicy};
+///
+/// # let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+/// # rt.block_on(async {
+/// let policy = RetryPolicy { delay: Duration::from_millis(1), ..Default::default() };
+/// let mut count = 0;
+/// let result: Result<(), &str> = retry_with_policy(policy, |e| *e == "临时错误", || {
+///     count += 1;
+///     async move { Err(if count == 1 { "临时错误" } else { "永久错误" }) }
+/// })
+/// .await;
+/// assert_eq!(result, Err("永久错误"));
+/// assert_eq!(count, 2);
+/// # });
+/// ```
+pub async fn retry_with_policy<T, E, Fut>(
+    policy: RetryPolicy,
+    retryable: impl Fn(&E) -> bool,
+    mut f: impl FnMut() -> Fut,
+) -> Result<T, E>
+where
+    E: Display,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let times = policy.times.max(1);
+    let mut delay = policy.delay.min(policy.max_delay);
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < times && retryable(&e) => {
+                tracing::warn!(
+                    "第 {}/{} 次尝试失败：{}，{:?} 后重试",
+                    attempt,
+                    times,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                delay = delay.saturating_mul(policy.backoff).min(policy.max_delay);
+                attempt += 1;
+            }
+            Err(e) => {
+                if attempt > 1 {
+                    tracing::error!("第 {}/{} 次尝试失败，放弃重试：{}", attempt, times, e);
+                }
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// 原子地写入文件。
+///
+/// 先写入同目录下的临时文件并刷入磁盘，再重命名为目标文件，避免进程在写入途中退出时留下损坏的文件。
+/// 临时文件名包含进程号和序号，同时写入同一个文件时不会共用临时文件。
+/// 任何情况下都不会先删除目标文件，重命名失败时目标文件保持原样。
+///
+/// 此函数会阻塞当前线程，在异步代码中请通过 [`tokio::task::spawn_blocking`] 调用。
+///
+/// # Examples
+///
+/// ```
+/// use chocho::utils::atomic_write;
+///
+/// let path = std::env::temp_dir().join("chocho_atomic_write_example.json");
+/// atomic_write(&path, "{}").unwrap();
+/// assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn atomic_write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+
+    let path = path.as_ref();
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    // 标准库的 rename 在 Windows 上使用 `MoveFileExW(MOVEFILE_REPLACE_EXISTING)`，可以直接覆盖目标文件。
+    // 重命名失败时保留临时文件，其中是完整的新内容，可以手动恢复
+    std::fs::rename(&tmp_path, path).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!(
+                "无法将 {} 重命名为 {}，新内容保留在临时文件中：{}",
+                tmp_path.display(),
+                path.display(),
+                e
+            ),
+        )
+    })
+}
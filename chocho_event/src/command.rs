@@ -0,0 +1,181 @@
+//! 命令路由。
+
+use std::{future::Future, pin::Pin};
+
+use async_trait::async_trait;
+use chocho_client::{structs::SentMessage, ClientExt};
+use chocho_msg::Message;
+use ricq::{
+    handler::{Handler, QEvent},
+    RQResult,
+};
+
+use crate::message::{IncomingMessage, MessageSource};
+
+type CommandHandler =
+    Box<dyn Fn(Command) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// 解析得到的命令。
+#[derive(Debug, Clone)]
+pub struct Command {
+    /// 命令名，不含前缀。
+    pub name: String,
+    /// 以空白分隔的参数。
+    pub args: Vec<String>,
+    /// 命令名之后的原始文本，去除了首尾空白。
+    pub raw_args: String,
+    /// 触发命令的消息。
+    pub message: IncomingMessage,
+}
+
+impl Command {
+    /// 在消息所在的会话中回复。
+    pub async fn reply(&self, msg: impl Into<Message>) -> RQResult<SentMessage> {
+        let client = &self.message.client;
+        match self.message.source {
+            MessageSource::Group(code) => client.group(code).send(msg).await,
+            MessageSource::Friend => client.friend(self.message.sender).send(msg).await,
+            MessageSource::GroupTemp(code) => {
+                client
+                    .group(code)
+                    .member(self.message.sender)
+                    .send_temp_msg(msg)
+                    .await
+            }
+        }
+    }
+}
+
+/// 命令路由器。
+///
+/// 按前缀识别消息中的命令，解析参数后调用注册的处理函数，同时适用于群消息、好友消息和群临时会话消息。
+/// 不是命令的消息和其它事件会交给 [`fallback`](Self::fallback) 指定的事件处理器，因此可以与已有的
+/// `PartlyHandler` 组合使用。
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_trait::async_trait;
+/// use chocho::prelude::*;
+/// use chocho::event::{Command, CommandRouter};
+/// use chocho::ricq::handler::PartlyHandler;
+///
+/// struct Handler;
+///
+/// #[async_trait]
+/// impl PartlyHandler for Handler {
+///     async fn handle_login(&self, uin: i64) {
+///         tracing::info!("登录成功: {}", uin);
+///     }
+/// }
+///
+/// async fn echo(cmd: Command) {
+///     if let Err(e) = cmd.reply(cmd.raw_args.clone()).await {
+///         tracing::error!("回复失败: {}", e);
+///     }
+/// }
+///
+/// fn handler() -> CommandRouter {
+///     CommandRouter::new()
+///         .on("echo", echo)
+///         .on("ping", |cmd: Command| async move {
+///             let _ = cmd.reply("pong".to_string()).await;
+///         })
+///         .fallback(Handler)
+/// }
+///
+/// #[chocho::main(handler = handler())]
+/// async fn main(client: RQClient) {}
+/// ```
+pub struct CommandRouter {
+    prefix: String,
+    commands: Vec<(String, CommandHandler)>,
+    fallback: Option<Box<dyn Handler + Send + Sync>>,
+}
+
+impl Default for CommandRouter {
+    fn default() -> Self {
+        Self {
+            prefix: "/".to_string(),
+            commands: vec![],
+            fallback: None,
+        }
+    }
+}
+
+impl CommandRouter {
+    /// 创建命令路由器，命令前缀为 `/`。
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 设置命令前缀，可以为空。
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// 注册命令。
+    ///
+    /// `name` 可以带有前缀，例如 `"/music"` 与 `"music"` 等价。同名命令以先注册的为准。
+    pub fn on<F, Fut>(mut self, name: &str, handler: F) -> Self
+    where
+        F: Fn(Command) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.strip_prefix(self.prefix.as_str()).unwrap_or(name);
+        self.commands.push((
+            name.to_string(),
+            Box::new(move |cmd| Box::pin(handler(cmd))),
+        ));
+        self
+    }
+
+    /// 设置处理其它事件的事件处理器。
+    pub fn fallback(mut self, handler: impl Handler + Send + Sync + 'static) -> Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// 解析消息中的命令，不是已注册的命令时返回 `None`。
+    pub fn parse(&self, message: &IncomingMessage) -> Option<Command> {
+        let text = message.message.text();
+        let text = text.trim_start().strip_prefix(self.prefix.as_str())?;
+        let (name, raw_args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        self.commands.iter().find(|(command, _)| command == name)?;
+        Some(Command {
+            name: name.to_string(),
+            args: raw_args.split_whitespace().map(str::to_string).collect(),
+            raw_args: raw_args.trim().to_string(),
+            message: message.clone(),
+        })
+    }
+
+    /// 尝试将消息作为命令处理，返回消息是否是已注册的命令。
+    pub async fn dispatch(&self, message: &IncomingMessage) -> bool {
+        let Some(command) = self.parse(message) else {
+            return false;
+        };
+        let (_, handler) = self
+            .commands
+            .iter()
+            .find(|(name, _)| *name == command.name)
+            .expect("command should be registered");
+        handler(command).await;
+        true
+    }
+}
+
+#[async_trait]
+impl Handler for CommandRouter {
+    async fn handle(&self, event: QEvent) {
+        if let Some(message) = IncomingMessage::from_event(&event) {
+            if self.dispatch(&message).await {
+                return;
+            }
+        }
+        if let Some(fallback) = &self.fallback {
+            fallback.handle(event).await;
+        }
+    }
+}
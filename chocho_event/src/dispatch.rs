@@ -0,0 +1,64 @@
+//! 事件分发。
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ricq::handler::{Handler, QEvent};
+
+use crate::{
+    message::IncomingMessage,
+    middleware::{MessageMiddleware, Next},
+    scope, wait,
+};
+
+/// 事件分发器。
+///
+/// 包装一个事件处理器，在事件交给它之前进行统一处理，例如跳过被 [`ScopeExt`](crate::ScopeExt) 静默的消息、
+/// 唤醒 [`WaitExt`](crate::WaitExt) 的等待者、缓存当前账号的 QQ 号。
+///
+/// 消息事件会先经过注册的 [`MessageMiddleware`]，中间件放行后再进行上述处理。
+///
+/// `#[chocho::main]` 会自动使用此包装。
+pub struct Dispatcher<H> {
+    inner: H,
+    middlewares: Vec<Arc<dyn MessageMiddleware>>,
+}
+
+impl<H> Dispatcher<H> {
+    /// 包装事件处理器。
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            middlewares: vec![],
+        }
+    }
+
+    /// 在中间件链的末尾添加中间件。
+    pub fn middleware(mut self, middleware: impl MessageMiddleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+}
+
+#[async_trait]
+impl<H> Handler for Dispatcher<H>
+where
+    H: Handler + Send + Sync,
+{
+    async fn handle(&self, event: QEvent) {
+        let Some(message) = IncomingMessage::from_event(&event) else {
+            return self.inner.handle(event).await;
+        };
+        chocho_client::account::cache_self_uin(&message.client).await;
+        let endpoint = {
+            let message = message.clone();
+            Box::pin(async move {
+                if scope::is_blocked(&message) || wait::feed(message).is_none() {
+                    return;
+                }
+                self.inner.handle(event).await
+            })
+        };
+        Next::new(&message, &self.middlewares, endpoint).run().await
+    }
+}
@@ -0,0 +1,81 @@
+//! # chocho_event
+//!
+//! [chocho](https://github.com/Wybxc/chocho) 的事件处理模块。
+//!
+//! [`Event`] 是 chocho 的统一事件抽象，包含 ricq 推送的原始事件，以及 chocho 自行解析或轮询得到的事件。
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use chocho::prelude::*;
+//! use chocho::event::{notice::AnnouncementWatcher, Event};
+//!
+//! #[chocho::main]
+//! async fn main(client: RQClient) {
+//!     let mut watcher = AnnouncementWatcher::new(client, [12345678]);
+//!     watcher
+//!         .run(|event| async move {
+//!             if let Event::Announcement(event) = event {
+//!                 tracing::info!("群 {} 发布了新公告：{}", event.group_code, event.announcement.content);
+//!             }
+//!         })
+//!         .await;
+//! }
+//! ```
+#![deny(missing_docs)]
+#![feature(never_type)]
+
+use ricq::handler::QEvent;
+
+mod command;
+mod dispatch;
+mod message;
+mod middleware;
+pub mod notice;
+mod permission;
+mod recall;
+mod scope;
+mod stream;
+mod wait;
+
+pub use command::{Command, CommandRouter};
+pub use dispatch::Dispatcher;
+pub use message::{IncomingMessage, MessageSource};
+pub use middleware::{MessageMiddleware, Next};
+pub use permission::{AdminChangeAdapter, AdminChangeEvent, AdminChangeHandler};
+pub use recall::{RecallEvent, RecallLogger};
+pub use scope::{Scope, ScopeExt};
+pub use stream::{EventStream, OverflowPolicy, StreamHandler};
+pub use wait::WaitExt;
+
+use notice::{AnnouncementEvent, EssenceChangeEvent};
+
+/// 统一事件。
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    /// ricq 推送的原始事件。
+    Ricq(QEvent),
+    /// 群公告发布。
+    Announcement(AnnouncementEvent),
+    /// 群精华消息变更。
+    EssenceChange(EssenceChangeEvent),
+}
+
+impl From<QEvent> for Event {
+    fn from(event: QEvent) -> Self {
+        Self::Ricq(event)
+    }
+}
+
+impl From<AnnouncementEvent> for Event {
+    fn from(event: AnnouncementEvent) -> Self {
+        Self::Announcement(event)
+    }
+}
+
+impl From<EssenceChangeEvent> for Event {
+    fn from(event: EssenceChangeEvent) -> Self {
+        Self::EssenceChange(event)
+    }
+}
@@ -0,0 +1,97 @@
+//! 统一的消息事件。
+
+use std::sync::Arc;
+
+use chocho_msg::{elem::Reply, Message, ReplySource};
+use ricq::{handler::QEvent, Client};
+
+/// 消息来源。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageSource {
+    /// 群消息，包含群号。
+    Group(i64),
+    /// 好友消息。
+    Friend,
+    /// 群临时会话消息，包含群号。
+    GroupTemp(i64),
+}
+
+/// 收到的消息。
+///
+/// 统一了群消息、好友消息和群临时会话消息。
+#[derive(Debug, Clone)]
+pub struct IncomingMessage {
+    /// 收到消息的客户端。
+    pub client: Arc<Client>,
+    /// 消息来源。
+    pub source: MessageSource,
+    /// 发送者 QQ 号。
+    pub sender: i64,
+    /// 消息内容。
+    pub message: Message,
+    /// 消息序号。
+    pub seqs: Vec<i32>,
+    /// 消息随机数。
+    pub rands: Vec<i32>,
+    /// 发送时间。
+    pub time: i32,
+}
+
+impl IncomingMessage {
+    /// 从 ricq 事件中提取消息，非消息事件返回 `None`。
+    pub fn from_event(event: &QEvent) -> Option<Self> {
+        match event {
+            QEvent::GroupMessage(e) => Some(Self {
+                client: e.client.clone(),
+                source: MessageSource::Group(e.inner.group_code),
+                sender: e.inner.from_uin,
+                message: e.inner.elements.clone().into(),
+                seqs: e.inner.seqs.clone(),
+                rands: e.inner.rands.clone(),
+                time: e.inner.time,
+            }),
+            QEvent::FriendMessage(e) => Some(Self {
+                client: e.client.clone(),
+                source: MessageSource::Friend,
+                sender: e.inner.from_uin,
+                message: e.inner.elements.clone().into(),
+                seqs: e.inner.seqs.clone(),
+                rands: e.inner.rands.clone(),
+                time: e.inner.time,
+            }),
+            QEvent::GroupTempMessage(e) => Some(Self {
+                client: e.client.clone(),
+                source: MessageSource::GroupTemp(e.inner.group_code),
+                sender: e.inner.from_uin,
+                message: e.inner.elements.clone().into(),
+                seqs: e.inner.seqs.clone(),
+                rands: e.inner.rands.clone(),
+                time: e.inner.time,
+            }),
+            _ => None,
+        }
+    }
+
+    /// 是否与另一条消息来自同一会话（同一个群，或同一个好友）。
+    pub fn same_session(&self, other: &IncomingMessage) -> bool {
+        match (self.source, other.source) {
+            (MessageSource::Group(a), MessageSource::Group(b)) => a == b,
+            (MessageSource::Friend, MessageSource::Friend) => self.sender == other.sender,
+            (MessageSource::GroupTemp(a), MessageSource::GroupTemp(b)) => {
+                a == b && self.sender == other.sender
+            }
+            _ => false,
+        }
+    }
+}
+
+impl ReplySource for IncomingMessage {
+    fn to_reply(&self) -> Reply {
+        Reply {
+            reply_seq: self.seqs.first().copied().unwrap_or_default(),
+            sender: self.sender,
+            time: self.time,
+            elements: self.message.clone().into(),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! 消息中间件。
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::message::IncomingMessage;
+
+type Endpoint<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// 消息中间件。
+///
+/// 中间件在消息到达事件处理器之前执行，可以用于统一的日志、限流、黑名单过滤等。
+/// 多个中间件按注册顺序组成链，每个中间件调用 [`Next::run`] 将消息交给下一个中间件；
+/// 不调用 `next` 时消息被拦截，不会到达后续的中间件、[`WaitExt`](crate::WaitExt) 的等待者和事件处理器。
+///
+/// 中间件只处理消息事件，其它事件直接交给事件处理器。
+///
+/// 在 `#[chocho::main]` 中使用 `#[chocho(middleware = [...])]` 注册中间件，
+/// 或者使用 [`Dispatcher::middleware`](crate::Dispatcher::middleware)。
+///
+/// # Examples
+///
+/// 限制每个用户发送消息的频率：
+///
+/// ```no_run
+/// use std::{collections::HashMap, sync::Mutex, time::{Duration, Instant}};
+/// use async_trait::async_trait;
+/// use chocho::prelude::*;
+/// use chocho::event::{IncomingMessage, MessageMiddleware, Next};
+///
+/// struct LogMw;
+///
+/// #[async_trait]
+/// impl MessageMiddleware for LogMw {
+///     async fn handle(&self, message: &IncomingMessage, next: Next<'_>) {
+///         tracing::info!("{} 发送了消息：{}", message.sender, message.message);
+///         next.run().await
+///     }
+/// }
+///
+/// /// 每个用户在 `interval` 内只处理一条消息。
+/// struct RateLimitMw {
+///     interval: Duration,
+///     last: Mutex<HashMap<i64, Instant>>,
+/// }
+///
+/// impl Default for RateLimitMw {
+///     fn default() -> Self {
+///         Self {
+///             interval: Duration::from_secs(3),
+///             last: Mutex::new(HashMap::new()),
+///         }
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl MessageMiddleware for RateLimitMw {
+///     async fn handle(&self, message: &IncomingMessage, next: Next<'_>) {
+///         let now = Instant::now();
+///         let limited = {
+///             let mut last = self.last.lock().unwrap();
+///             match last.get(&message.sender) {
+///                 Some(&time) if now.duration_since(time) < self.interval => true,
+///                 _ => {
+///                     last.insert(message.sender, now);
+///                     false
+///                 }
+///             }
+///         };
+///         if limited {
+///             tracing::debug!("{} 发送消息过于频繁，已忽略", message.sender);
+///             return;
+///         }
+///         next.run().await
+///     }
+/// }
+///
+/// #[chocho::main(middleware = [LogMw, RateLimitMw::default()])]
+/// async fn main(client: RQClient) {}
+/// ```
+#[async_trait]
+pub trait MessageMiddleware: Send + Sync {
+    /// 处理消息。调用 `next.run()` 将消息交给下一个中间件。
+    async fn handle(&self, message: &IncomingMessage, next: Next<'_>);
+}
+
+#[async_trait]
+impl<T: MessageMiddleware + ?Sized> MessageMiddleware for Arc<T> {
+    async fn handle(&self, message: &IncomingMessage, next: Next<'_>) {
+        (**self).handle(message, next).await
+    }
+}
+
+/// 中间件链的剩余部分。
+pub struct Next<'a> {
+    message: &'a IncomingMessage,
+    middlewares: &'a [Arc<dyn MessageMiddleware>],
+    endpoint: Endpoint<'a>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        message: &'a IncomingMessage,
+        middlewares: &'a [Arc<dyn MessageMiddleware>],
+        endpoint: Endpoint<'a>,
+    ) -> Self {
+        Self {
+            message,
+            middlewares,
+            endpoint,
+        }
+    }
+
+    /// 将消息交给下一个中间件，所有中间件都执行完毕后，交给事件处理器。
+    pub async fn run(self) {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                let next = Next::new(self.message, rest, self.endpoint);
+                middleware.handle(self.message, next).await
+            }
+            None => self.endpoint.await,
+        }
+    }
+}
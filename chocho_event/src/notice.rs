@@ -0,0 +1,222 @@
+//! 群公告与群精华变更事件。
+//!
+//! ricq 目前不会推送公告与精华的变更通知，因此通过网页接口轮询得到：
+//! [`AnnouncementWatcher`] 轮询 [`Group::get_announcements`](chocho_client::group::Group::get_announcements)，
+//! 在发布新公告时产生 [`AnnouncementEvent`]；[`EssenceWatcher`] 轮询
+//! [`Group::get_essence_list`](chocho_client::group::Group::get_essence_list)，在精华消息增减时产生 [`EssenceChangeEvent`]。
+//!
+//! 轮询时某个群请求失败，只记录日志并跳过该群，不影响其它群。
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+
+use chocho_client::{announcement::Announcement, essence::EssenceMessage, ClientExt};
+use ricq::Client;
+
+use crate::Event;
+
+/// 群公告发布事件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnouncementEvent {
+    /// 群号。
+    pub group_code: i64,
+    /// 新发布的公告。
+    pub announcement: Announcement,
+}
+
+/// 群精华消息变更事件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EssenceChangeEvent {
+    /// 群号。
+    pub group_code: i64,
+    /// 消息序号。
+    pub msg_seq: i32,
+    /// 消息随机数。
+    pub msg_rand: i32,
+    /// 消息发送者。
+    pub sender_uin: i64,
+    /// 操作者。精华消息列表中不记录移出精华的操作者，因此移出精华时为 0。
+    pub operator_uin: i64,
+    /// `true` 表示设为精华，`false` 表示移出精华。
+    pub added: bool,
+}
+
+/// 群公告轮询器。
+///
+/// 定期获取各群最近的公告，在出现新公告时产生 [`AnnouncementEvent`]。
+/// 每个群首次轮询成功时只记录当前的公告，不产生事件；删除公告不产生事件。
+pub struct AnnouncementWatcher {
+    client: Arc<Client>,
+    groups: Vec<i64>,
+    interval: Duration,
+    seen: HashMap<i64, HashSet<String>>,
+}
+
+impl AnnouncementWatcher {
+    /// 创建轮询器，默认每 60 秒轮询一次。
+    pub fn new(client: Arc<Client>, groups: impl IntoIterator<Item = i64>) -> Self {
+        Self {
+            client,
+            groups: groups.into_iter().collect(),
+            interval: Duration::from_secs(60),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// 设置轮询间隔。
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// 进行一次轮询，返回自上次轮询以来新发布的公告。
+    ///
+    /// 获取失败的群会记录日志并跳过，下次轮询时重试。
+    pub async fn poll(&mut self) -> Vec<AnnouncementEvent> {
+        let mut events = vec![];
+        for &group_code in &self.groups {
+            let announcements = match self.client.group(group_code).get_announcements().await {
+                Ok(announcements) => announcements,
+                Err(e) => {
+                    tracing::warn!("轮询群 {} 的公告失败: {}", group_code, e);
+                    continue;
+                }
+            };
+            let first = !self.seen.contains_key(&group_code);
+            let seen = self.seen.entry(group_code).or_default();
+            for announcement in announcements {
+                if seen.insert(announcement.id.clone()) && !first {
+                    events.push(AnnouncementEvent {
+                        group_code,
+                        announcement,
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    /// 持续轮询，在发布新公告时调用 `on_event`。
+    pub async fn run<Fut>(&mut self, on_event: impl Fn(Event) -> Fut) -> !
+    where
+        Fut: Future<Output = ()>,
+    {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            for event in self.poll().await {
+                on_event(event.into()).await;
+            }
+        }
+    }
+}
+
+/// 群精华消息轮询器。
+///
+/// 定期获取各群的精华消息列表，与上一次的结果比较，在消息被设为精华或移出精华时产生 [`EssenceChangeEvent`]。
+/// 每个群首次轮询成功时只记录当前的精华消息，不产生事件。
+///
+/// # Examples
+///
+/// ```no_run
+/// use chocho::prelude::*;
+/// use chocho::event::{notice::EssenceWatcher, Event};
+///
+/// #[chocho::main]
+/// async fn main(client: RQClient) {
+///     let mut watcher = EssenceWatcher::new(client, [12345678]);
+///     watcher
+///         .run(|event| async move {
+///             if let Event::EssenceChange(event) = event {
+///                 if event.added {
+///                     tracing::info!("{} 将 {} 的消息设为精华", event.operator_uin, event.sender_uin);
+///                 }
+///             }
+///         })
+///         .await;
+/// }
+/// ```
+pub struct EssenceWatcher {
+    client: Arc<Client>,
+    groups: Vec<i64>,
+    interval: Duration,
+    essences: HashMap<i64, HashMap<(i32, i32), EssenceMessage>>,
+}
+
+impl EssenceWatcher {
+    /// 创建轮询器，默认每 60 秒轮询一次。
+    pub fn new(client: Arc<Client>, groups: impl IntoIterator<Item = i64>) -> Self {
+        Self {
+            client,
+            groups: groups.into_iter().collect(),
+            interval: Duration::from_secs(60),
+            essences: HashMap::new(),
+        }
+    }
+
+    /// 设置轮询间隔。
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// 进行一次轮询，返回自上次轮询以来的精华消息变更。
+    ///
+    /// 获取失败的群会记录日志并跳过，下次轮询时重试。
+    pub async fn poll(&mut self) -> Vec<EssenceChangeEvent> {
+        let mut events = vec![];
+        for &group_code in &self.groups {
+            let list = match self.client.group(group_code).get_essence_list().await {
+                Ok(list) => list,
+                Err(e) => {
+                    tracing::warn!("轮询群 {} 的精华消息失败: {}", group_code, e);
+                    continue;
+                }
+            };
+            let current: HashMap<_, _> = list
+                .into_iter()
+                .map(|essence| ((essence.msg_seq, essence.msg_rand), essence))
+                .collect();
+            let Some(previous) = self.essences.insert(group_code, current.clone()) else {
+                continue;
+            };
+            let event = |essence: &EssenceMessage, operator_uin, added| EssenceChangeEvent {
+                group_code,
+                msg_seq: essence.msg_seq,
+                msg_rand: essence.msg_rand,
+                sender_uin: essence.sender_uin,
+                operator_uin,
+                added,
+            };
+            for (key, essence) in &current {
+                if !previous.contains_key(key) {
+                    events.push(event(essence, essence.operator_uin, true));
+                }
+            }
+            for (key, essence) in &previous {
+                if !current.contains_key(key) {
+                    events.push(event(essence, 0, false));
+                }
+            }
+        }
+        events
+    }
+
+    /// 持续轮询，在精华消息变更时调用 `on_event`。
+    pub async fn run<Fut>(&mut self, on_event: impl Fn(Event) -> Fut) -> !
+    where
+        Fut: Future<Output = ()>,
+    {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            for event in self.poll().await {
+                on_event(event.into()).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! 群成员权限变更事件。
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chocho_client::ClientExt;
+use ricq::{
+    handler::{Handler, QEvent},
+    structs::GroupMemberPermission,
+    Client,
+};
+
+/// 群管理员变更事件。
+///
+/// 群成员被设为管理员或被取消管理员时产生，对应 ricq 的 `MemberPermissionChange` 事件。
+///
+/// 通常通过 [`AdminChangeHandler`] 处理，也可以用 [`from_event`](Self::from_event) 从 ricq 事件中提取。
+#[derive(Debug, Clone)]
+pub struct AdminChangeEvent {
+    /// 收到事件的客户端。
+    pub client: Arc<Client>,
+    /// 群号。
+    pub group: i64,
+    /// 权限变更的群成员。
+    pub target_uin: i64,
+    /// 变更后的权限。
+    pub new_permission: GroupMemberPermission,
+}
+
+impl AdminChangeEvent {
+    /// 从 ricq 事件中提取管理员变更事件，其它事件返回 `None`。
+    pub fn from_event(event: &QEvent) -> Option<Self> {
+        match event {
+            QEvent::MemberPermissionChange(e) => Some(Self {
+                client: e.client.clone(),
+                group: e.inner.group_code,
+                target_uin: e.inner.member_uin,
+                new_permission: e.inner.new_permission.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// 是否被设为管理员。
+    pub fn is_promoted(&self) -> bool {
+        matches!(self.new_permission, GroupMemberPermission::Administrator)
+    }
+
+    /// 是否被取消管理员。
+    pub fn is_demoted(&self) -> bool {
+        matches!(self.new_permission, GroupMemberPermission::Member)
+    }
+
+    /// 变更的是否是当前登录的账号。
+    ///
+    /// 需要已缓存当前账号的 QQ 号，参见 [`ClientExt::self_uin`](chocho_client::ClientExt::self_uin)。
+    pub fn is_self(&self) -> bool {
+        self.client.self_uin() == Some(self.target_uin)
+    }
+}
+
+/// 群管理员变更事件的处理器。
+///
+/// 使用 [`AdminChangeAdapter`] 包装后即可作为 ricq 的事件处理器。
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_trait::async_trait;
+/// use chocho::prelude::*;
+/// use chocho::event::{AdminChangeAdapter, AdminChangeEvent, AdminChangeHandler};
+/// use chocho_msg::{elem::At, msg};
+///
+/// struct AdminWatcher;
+///
+/// #[async_trait]
+/// impl AdminChangeHandler for AdminWatcher {
+///     async fn handle_group_admin_change(&self, event: AdminChangeEvent) {
+///         let text = if event.is_promoted() {
+///             "恭喜成为管理员！"
+///         } else {
+///             "已被取消管理员。"
+///         };
+///         let _ = event
+///             .client
+///             .group(event.group)
+///             .send(msg![At::new(event.target_uin), " ", text])
+///             .await;
+///     }
+/// }
+///
+/// #[chocho::main(handler = AdminChangeAdapter::new(AdminWatcher))]
+/// async fn main(client: RQClient) {}
+/// ```
+#[async_trait]
+pub trait AdminChangeHandler: Send + Sync {
+    /// 处理群管理员变更事件。
+    async fn handle_group_admin_change(&self, event: AdminChangeEvent);
+
+    /// 处理其它事件，默认忽略。
+    async fn handle_other(&self, _event: QEvent) {}
+}
+
+/// 将 [`AdminChangeHandler`] 包装为 ricq 的事件处理器。
+///
+/// 管理员变更事件交给 [`handle_group_admin_change`](AdminChangeHandler::handle_group_admin_change)，
+/// 其它事件交给 [`handle_other`](AdminChangeHandler::handle_other)。
+pub struct AdminChangeAdapter<H> {
+    inner: H,
+}
+
+impl<H> AdminChangeAdapter<H> {
+    /// 包装群管理员变更事件的处理器。
+    pub fn new(inner: H) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<H> Handler for AdminChangeAdapter<H>
+where
+    H: AdminChangeHandler,
+{
+    async fn handle(&self, event: QEvent) {
+        match AdminChangeEvent::from_event(&event) {
+            Some(admin_change) => self.inner.handle_group_admin_change(admin_change).await,
+            None => self.inner.handle_other(event).await,
+        }
+    }
+}
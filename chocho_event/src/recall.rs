@@ -0,0 +1,180 @@
+//! 消息撤回事件与防撤回记录。
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use ricq::{handler::QEvent, Client};
+
+use crate::{
+    message::{IncomingMessage, MessageSource},
+    middleware::{MessageMiddleware, Next},
+};
+
+/// 消息撤回事件。
+///
+/// 统一了群消息撤回和好友消息撤回。群临时会话的消息撤回不会推送。
+#[derive(Debug, Clone)]
+pub struct RecallEvent {
+    /// 收到事件的客户端。
+    pub client: Arc<Client>,
+    /// 被撤回消息的来源。
+    pub source: MessageSource,
+    /// 被撤回消息的发送者。
+    pub author: i64,
+    /// 撤回消息的操作者。好友消息撤回时与发送者相同。
+    pub operator: i64,
+    /// 被撤回消息的序号。
+    pub seq: i32,
+    /// 撤回时间。
+    pub time: i64,
+}
+
+impl RecallEvent {
+    /// 从 ricq 事件中提取撤回事件，非撤回事件返回 `None`。
+    pub fn from_event(event: &QEvent) -> Option<Self> {
+        match event {
+            QEvent::GroupMessageRecall(e) => Some(Self {
+                client: e.client.clone(),
+                source: MessageSource::Group(e.inner.group_code),
+                author: e.inner.author_uin,
+                operator: e.inner.operator_uin,
+                seq: e.inner.msg_seq,
+                time: e.inner.time as i64,
+            }),
+            QEvent::FriendMessageRecall(e) => Some(Self {
+                client: e.client.clone(),
+                source: MessageSource::Friend,
+                author: e.inner.friend_uin,
+                operator: e.inner.friend_uin,
+                seq: e.inner.msg_seq,
+                time: e.inner.time as i64,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 消息的标识：来源、发送者和序号。
+type MessageKey = (MessageSource, i64, i32);
+
+/// 防撤回记录器。
+///
+/// 在内存中缓存最近收到的 `capacity` 条消息，消息被撤回时可以取回原内容。超出容量时丢弃最早的消息。
+///
+/// `RecallLogger` 实现了 [`MessageMiddleware`]，注册为中间件后会自动记录所有消息；
+/// 克隆得到的记录器共享同一个缓存，可以在事件处理器中使用。
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_trait::async_trait;
+/// use chocho::event::{Dispatcher, RecallEvent, RecallLogger};
+/// use chocho::ricq::handler::{Handler, QEvent};
+///
+/// struct AntiRecall {
+///     logger: RecallLogger,
+/// }
+///
+/// #[async_trait]
+/// impl Handler for AntiRecall {
+///     async fn handle(&self, event: QEvent) {
+///         let Some(recall) = RecallEvent::from_event(&event) else {
+///             return;
+///         };
+///         if let Some(message) = self.logger.take(&recall) {
+///             tracing::info!("{} 撤回了消息：{}", recall.author, message.message);
+///         }
+///     }
+/// }
+///
+/// let logger = RecallLogger::new(1000);
+/// let handler = Dispatcher::new(AntiRecall { logger: logger.clone() }).middleware(logger);
+/// ```
+#[derive(Clone)]
+pub struct RecallLogger {
+    inner: Arc<Mutex<RecallCache>>,
+}
+
+struct RecallCache {
+    capacity: usize,
+    order: VecDeque<MessageKey>,
+    messages: HashMap<MessageKey, IncomingMessage>,
+}
+
+impl RecallLogger {
+    /// 创建记录器，最多缓存 `capacity` 条消息。
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RecallCache {
+                capacity,
+                order: VecDeque::new(),
+                messages: HashMap::new(),
+            })),
+        }
+    }
+
+    /// 记录一条消息。
+    pub fn record(&self, message: &IncomingMessage) {
+        let Some(&seq) = message.seqs.first() else {
+            return;
+        };
+        let key = (message.source, message.sender, seq);
+        let mut cache = self.inner.lock().expect("Failed locking recall cache");
+        if cache.capacity == 0 {
+            return;
+        }
+        if cache.messages.insert(key, message.clone()).is_none() {
+            cache.order.push_back(key);
+        }
+        while cache.order.len() > cache.capacity {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.messages.remove(&oldest);
+            }
+        }
+    }
+
+    /// 查找被撤回的消息。消息已被丢弃或没有记录时返回 `None`。
+    pub fn get(&self, recall: &RecallEvent) -> Option<IncomingMessage> {
+        let key = (recall.source, recall.author, recall.seq);
+        self.inner
+            .lock()
+            .expect("Failed locking recall cache")
+            .messages
+            .get(&key)
+            .cloned()
+    }
+
+    /// 取出被撤回的消息，并从缓存中删除。
+    pub fn take(&self, recall: &RecallEvent) -> Option<IncomingMessage> {
+        let key = (recall.source, recall.author, recall.seq);
+        let mut cache = self.inner.lock().expect("Failed locking recall cache");
+        let message = cache.messages.remove(&key)?;
+        cache.order.retain(|k| *k != key);
+        Some(message)
+    }
+
+    /// 当前缓存的消息数量。
+    pub fn len(&self) -> usize {
+        self.inner
+            .lock()
+            .expect("Failed locking recall cache")
+            .messages
+            .len()
+    }
+
+    /// 缓存是否为空。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for RecallLogger {
+    async fn handle(&self, message: &IncomingMessage, next: Next<'_>) {
+        self.record(message);
+        next.run().await
+    }
+}
@@ -0,0 +1,221 @@
+//! 按会话与用户的消息处理开关。
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chocho_client::{state, utils::atomic_write};
+use ricq::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::message::{IncomingMessage, MessageSource};
+
+/// 会话作用域。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum Scope {
+    /// 群，包含群号。
+    Group(i64),
+    /// 好友（包括群临时会话），包含 QQ 号。
+    Friend(i64),
+}
+
+impl Scope {
+    /// 消息所属的会话作用域。
+    pub fn of(message: &IncomingMessage) -> Self {
+        match message.source {
+            MessageSource::Group(code) => Scope::Group(code),
+            MessageSource::Friend | MessageSource::GroupTemp(_) => Scope::Friend(message.sender),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ScopeState {
+    muted: HashSet<Scope>,
+    blacklist: HashSet<i64>,
+    #[serde(skip)]
+    persist: Option<Persist>,
+    /// 状态的版本，每次修改加一。
+    #[serde(skip)]
+    version: u64,
+}
+
+/// 持久化的目标文件。
+struct Persist {
+    path: PathBuf,
+    /// 已写入文件的最新版本。
+    written: Arc<Mutex<u64>>,
+}
+
+impl ScopeState {
+    /// 记录一次修改。持久化时返回需要写入的快照，由调用者在锁外保存。
+    fn modified(&mut self) -> Option<Snapshot> {
+        self.version += 1;
+        let persist = self.persist.as_ref()?;
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => Some(Snapshot {
+                path: persist.path.clone(),
+                written: persist.written.clone(),
+                version: self.version,
+                json,
+            }),
+            Err(e) => {
+                tracing::warn!("保存消息处理开关失败: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// 待写入文件的状态。
+struct Snapshot {
+    path: PathBuf,
+    written: Arc<Mutex<u64>>,
+    version: u64,
+    json: String,
+}
+
+impl Snapshot {
+    /// 保存快照。在 tokio 运行时中时交给阻塞线程写入，不阻塞异步任务。
+    fn save(self) {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || self.write());
+            }
+            Err(_) => self.write(),
+        }
+    }
+
+    fn write(self) {
+        let mut written = self.written.lock().expect("Failed locking Persist");
+        // 更新的状态已经写入，多个快照的写入顺序可能与修改顺序不同
+        if *written >= self.version {
+            return;
+        }
+        match atomic_write(&self.path, &self.json) {
+            Ok(()) => *written = self.version,
+            Err(e) => tracing::warn!("保存消息处理开关失败: {}", e),
+        }
+    }
+}
+
+fn with_state<T>(client: &Arc<Client>, f: impl FnOnce(&mut ScopeState) -> T) -> T {
+    let state = state::get_or_insert_with(client, Mutex::<ScopeState>::default);
+    let mut state = state.lock().expect("Failed locking ScopeState");
+    f(&mut state)
+}
+
+/// 修改状态，`f` 返回 `true` 表示状态有变化，此时在锁外保存状态。
+fn update(client: &Arc<Client>, f: impl FnOnce(&mut ScopeState) -> bool) {
+    let snapshot = with_state(
+        client,
+        |state| if f(state) { state.modified() } else { None },
+    );
+    if let Some(snapshot) = snapshot {
+        snapshot.save();
+    }
+}
+
+/// 消息是否应被跳过，不再交给事件处理器。
+pub(crate) fn is_blocked(message: &IncomingMessage) -> bool {
+    let Some(state) = state::get::<Mutex<ScopeState>>(&message.client) else {
+        return false;
+    };
+    let state = state.lock().expect("Failed locking ScopeState");
+    state.blacklist.contains(&message.sender) || state.muted.contains(&Scope::of(message))
+}
+
+/// 消息处理开关的扩展方法。
+///
+/// 被静默的会话与被拉黑的用户发来的消息，不会交给事件处理器。
+/// 此功能依赖 [`Dispatcher`](crate::Dispatcher) 分发事件，`#[chocho::main]` 会自动启用它，
+/// 并将开关状态保存在数据文件夹中。
+///
+/// # Examples
+///
+/// ```no_run
+/// use chocho::prelude::*;
+/// use chocho::event::{Scope, ScopeExt};
+///
+/// #[chocho::main]
+/// async fn main(client: RQClient) {
+///     client.mute_scope(Scope::Group(12345678));
+///     client.blacklist_user(87654321);
+///     assert!(client.is_scope_muted(Scope::Group(12345678)));
+/// }
+/// ```
+pub trait ScopeExt {
+    /// 静默会话，不再处理其中的消息。
+    fn mute_scope(&self, scope: Scope);
+
+    /// 取消静默会话。
+    fn unmute_scope(&self, scope: Scope);
+
+    /// 会话是否被静默。
+    fn is_scope_muted(&self, scope: Scope) -> bool;
+
+    /// 拉黑用户，不再处理其发送的消息。
+    fn blacklist_user(&self, uin: i64);
+
+    /// 取消拉黑用户。
+    fn unblacklist_user(&self, uin: i64);
+
+    /// 用户是否被拉黑。
+    fn is_user_blacklisted(&self, uin: i64) -> bool;
+
+    /// 将开关状态持久化到文件。
+    ///
+    /// 如果文件已存在，会先读取其中的状态；之后每次修改开关都会写回文件。
+    /// 在 tokio 运行时中修改开关时，文件在后台线程中写入，先写入临时文件再替换原文件。
+    fn persist_scopes(&self, path: impl AsRef<Path>) -> std::io::Result<()>;
+}
+
+impl ScopeExt for Arc<Client> {
+    fn mute_scope(&self, scope: Scope) {
+        update(self, |state| state.muted.insert(scope))
+    }
+
+    fn unmute_scope(&self, scope: Scope) {
+        update(self, |state| state.muted.remove(&scope))
+    }
+
+    fn is_scope_muted(&self, scope: Scope) -> bool {
+        with_state(self, |state| state.muted.contains(&scope))
+    }
+
+    fn blacklist_user(&self, uin: i64) {
+        update(self, |state| state.blacklist.insert(uin))
+    }
+
+    fn unblacklist_user(&self, uin: i64) {
+        update(self, |state| state.blacklist.remove(&uin))
+    }
+
+    fn is_user_blacklisted(&self, uin: i64) -> bool {
+        with_state(self, |state| state.blacklist.contains(&uin))
+    }
+
+    fn persist_scopes(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let mut loaded = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            ScopeState::default()
+        };
+        loaded.persist = Some(Persist {
+            path: path.to_path_buf(),
+            written: Default::default(),
+        });
+        update(self, |state| {
+            loaded.muted.extend(state.muted.drain());
+            loaded.blacklist.extend(state.blacklist.drain());
+            loaded.version = state.version;
+            *state = loaded;
+            true
+        });
+        Ok(())
+    }
+}
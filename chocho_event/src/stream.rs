@@ -0,0 +1,138 @@
+//! 以异步流的形式接收事件。
+
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use futures_core::Stream;
+use ricq::handler::{Handler, QEvent};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::Event;
+
+/// 事件队列已满时的处理方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// 等待队列有空位。
+    ///
+    /// 不会丢失事件，但在事件被取走之前，ricq 不会处理后续的网络包，消费过慢时可能导致心跳超时。
+    #[default]
+    Wait,
+    /// 丢弃新到达的事件，并打印警告。
+    DropNewest,
+}
+
+/// 将事件推入队列的事件处理器，配合 [`EventStream`] 使用。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+/// use chocho::event::{Event, OverflowPolicy, StreamHandler};
+/// use chocho::ricq::handler::QEvent;
+/// use chocho::{login_with_credential, Credential, LoginOptions, QSignClient};
+///
+/// # async fn _f(qsign_client: Arc<QSignClient>) -> anyhow::Result<()> {
+/// let (handler, mut events) = StreamHandler::new(256);
+/// let handler = handler.overflow(OverflowPolicy::DropNewest);
+/// let (client, alive) = login_with_credential(
+///     Credential::QrCode { uin: 123456789 },
+///     "./bots",
+///     handler,
+///     qsign_client,
+///     LoginOptions::new(),
+/// )
+/// .await?;
+/// tokio::spawn(alive.auto_reconnect());
+///
+/// while let Some(event) = events.recv().await {
+///     if let Event::Ricq(QEvent::GroupMessage(e)) = event {
+///         println!("{}: {}", e.inner.from_uin, chocho::Message::from(e.inner.elements));
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamHandler {
+    sender: mpsc::Sender<Event>,
+    policy: OverflowPolicy,
+    dropped: Arc<AtomicU64>,
+}
+
+impl StreamHandler {
+    /// 创建事件处理器和对应的事件流，队列最多容纳 `capacity` 个事件。
+    ///
+    /// # Panics
+    ///
+    /// `capacity` 为 0 时 panic。
+    pub fn new(capacity: usize) -> (Self, EventStream) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let handler = Self {
+            sender,
+            policy: OverflowPolicy::default(),
+            dropped: dropped.clone(),
+        };
+        (handler, EventStream { receiver, dropped })
+    }
+
+    /// 设置队列已满时的处理方式，默认为 [`OverflowPolicy::Wait`]。
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+#[async_trait]
+impl Handler for StreamHandler {
+    async fn handle(&self, event: QEvent) {
+        let event = Event::from(event);
+        match self.policy {
+            // 事件流已被丢弃时，忽略事件
+            OverflowPolicy::Wait => {
+                let _ = self.sender.send(event).await;
+            }
+            OverflowPolicy::DropNewest => {
+                if let Err(TrySendError::Full(_)) = self.sender.try_send(event) {
+                    let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    tracing::warn!("事件队列已满，丢弃新事件（累计丢弃 {} 个）", dropped);
+                }
+            }
+        }
+    }
+}
+
+/// 事件流，由 [`StreamHandler::new`] 创建。
+///
+/// 实现了 [`Stream`]，可以配合 `futures` 等库的组合子使用；也可以直接调用 [`recv`](EventStream::recv)。
+/// 事件处理器被丢弃（例如客户端停止）后，流在取完剩余的事件后结束。
+pub struct EventStream {
+    receiver: mpsc::Receiver<Event>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl EventStream {
+    /// 接收下一个事件，流结束时返回 `None`。
+    pub async fn recv(&mut self) -> Option<Event> {
+        self.receiver.recv().await
+    }
+
+    /// 因队列已满而被丢弃的事件数。
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.receiver.poll_recv(cx)
+    }
+}
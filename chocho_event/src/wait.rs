@@ -0,0 +1,126 @@
+//! 条件等待消息。
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use chocho_client::state;
+use ricq::Client;
+use tokio::sync::oneshot;
+
+use crate::message::IncomingMessage;
+
+type Filter = Box<dyn Fn(&IncomingMessage) -> bool + Send>;
+
+struct Waiter {
+    id: u64,
+    filter: Filter,
+    sender: oneshot::Sender<IncomingMessage>,
+}
+
+/// 客户端的所有等待者。
+#[derive(Default)]
+struct Waiters(Mutex<Vec<Waiter>>);
+
+impl Waiters {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Waiter>> {
+        self.0.lock().expect("Failed locking Waiters")
+    }
+}
+
+/// 等待结束（包括超时和被取消）时移除等待者。
+struct WaiterGuard {
+    waiters: Arc<Waiters>,
+    id: u64,
+}
+
+impl Drop for WaiterGuard {
+    fn drop(&mut self) {
+        self.waiters.lock().retain(|waiter| waiter.id != self.id);
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 将消息交给等待者，如果消息被某个等待者接收，返回 `None`。
+pub(crate) fn feed(message: IncomingMessage) -> Option<IncomingMessage> {
+    let Some(waiters) = state::get::<Waiters>(&message.client) else {
+        return Some(message);
+    };
+    let mut waiters = waiters.lock();
+    waiters.retain(|waiter| !waiter.sender.is_closed());
+    let Some(index) = waiters.iter().position(|waiter| (waiter.filter)(&message)) else {
+        return Some(message);
+    };
+    let waiter = waiters.remove(index);
+    waiter.sender.send(message).err()
+}
+
+/// 等待消息的扩展方法。
+#[async_trait::async_trait]
+pub trait WaitExt {
+    /// 等待下一条满足条件的消息。
+    ///
+    /// 在超时之前收到满足 `filter` 的消息时返回该消息，否则返回 `None`。
+    /// 被等待接收的消息不会再交给事件处理器。
+    ///
+    /// 此方法依赖 [`Dispatcher`](crate::Dispatcher) 分发事件，`#[chocho::main]` 会自动启用它。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use chocho::prelude::*;
+    /// use chocho::event::IncomingMessage;
+    ///
+    /// async fn ask_name(msg: &IncomingMessage) -> RQResult<()> {
+    ///     let friend = msg.client.friend(msg.sender);
+    ///     friend.send("请问你叫什么名字？".to_string()).await?;
+    ///     let reply = msg
+    ///         .client
+    ///         .wait_for_message(
+    ///             {
+    ///                 let msg = msg.clone();
+    ///                 move |reply| reply.same_session(&msg)
+    ///             },
+    ///             Duration::from_secs(60),
+    ///         )
+    ///         .await;
+    ///     match reply {
+    ///         Some(reply) => friend.send(format!("你好，{}！", reply.message)).await?,
+    ///         None => friend.send("等待超时".to_string()).await?,
+    ///     };
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn wait_for_message<F>(&self, filter: F, timeout: Duration) -> Option<IncomingMessage>
+    where
+        F: Fn(&IncomingMessage) -> bool + Send + 'static;
+}
+
+#[async_trait::async_trait]
+impl WaitExt for Arc<Client> {
+    async fn wait_for_message<F>(&self, filter: F, timeout: Duration) -> Option<IncomingMessage>
+    where
+        F: Fn(&IncomingMessage) -> bool + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let waiters = state::get_or_insert_with(self, Waiters::default);
+        waiters.lock().push(Waiter {
+            id,
+            filter: Box::new(filter),
+            sender,
+        });
+        let _guard = WaiterGuard { waiters, id };
+
+        tokio::time::timeout(timeout, receiver)
+            .await
+            .ok()
+            .and_then(Result::ok)
+    }
+}
@@ -0,0 +1,50 @@
+//! 自动处理好友请求、加群请求和入群邀请。
+use async_trait::async_trait;
+use chocho::prelude::*;
+use chocho::ricq::{
+    client::event::{FriendRequestEvent, JoinGroupRequestEvent, SelfInvitedEvent},
+    handler::PartlyHandler,
+};
+
+struct Handler;
+
+#[async_trait]
+impl PartlyHandler for Handler {
+    async fn handle_friend_request(
+        &self,
+        FriendRequestEvent { client, inner }: FriendRequestEvent,
+    ) {
+        tracing::info!(
+            "{}({}) 请求添加好友: {}",
+            inner.req_nick,
+            inner.req_uin,
+            inner.message
+        );
+        if let Err(e) = client.approve_friend_request(&inner).await {
+            tracing::error!("处理好友请求失败: {}", e);
+        }
+    }
+    async fn handle_group_request(
+        &self,
+        JoinGroupRequestEvent { client, inner }: JoinGroupRequestEvent,
+    ) {
+        let result = if inner.suspicious {
+            client
+                .reject_join_group_request(&inner, "风险账号", false)
+                .await
+        } else {
+            client.approve_join_group_request(&inner).await
+        };
+        if let Err(e) = result {
+            tracing::error!("处理加群请求失败: {}", e);
+        }
+    }
+    async fn handle_self_invited(&self, SelfInvitedEvent { client, inner }: SelfInvitedEvent) {
+        if let Err(e) = client.accept_group_invitation(&inner).await {
+            tracing::error!("处理入群邀请失败: {}", e);
+        }
+    }
+}
+
+#[chocho::main(handler = Handler)]
+async fn main(_: RQClient) {}
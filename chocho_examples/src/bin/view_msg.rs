@@ -13,12 +13,12 @@ impl PartlyHandler for Handler {
     async fn handle_group_message(&self, ev: GroupMessageEvent) {
         let msg: Message = ev.inner.elements.into();
         println!("Group Message");
-        println!("{:?}", msg.into_elems().collect::<Vec<_>>());
+        println!("{}", msg.debug_pretty());
     }
     async fn handle_friend_message(&self, ev: FriendMessageEvent) {
         let msg: Message = ev.inner.elements.into();
         println!("Friend Message");
-        println!("{:?}", msg.into_elems().collect::<Vec<_>>());
+        println!("{}", msg.debug_pretty());
     }
 }
 
@@ -27,8 +27,8 @@ impl PartlyHandler for Handler {
 
 #[chocho::main(handler = Handler)]
 async fn main(client: RQClient) {
-    let account_info = client.account_info.read().await;
-    println!("{:?}", account_info);
+    let account = client.account().await;
+    println!("{:?}", account);
 
     chocho::finalizer(|| async {
         tracing::info!("正在退出...");
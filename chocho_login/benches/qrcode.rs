@@ -0,0 +1,62 @@
+//! 二维码识别的基准测试。
+//!
+//! 对比每帧新建 [`QrcodeDecoder`] 与复用同一个识别器识别连续多帧的开销。
+
+use std::io::Cursor;
+
+use chocho_login::qrcode::QrcodeDecoder;
+use criterion::{criterion_group, criterion_main, Criterion};
+use image::{GrayImage, ImageOutputFormat, Luma};
+use qrcode::{Color, QrCode};
+
+/// 每个模块的像素数。
+const SCALE: u32 = 4;
+/// 四周空白区域的模块数。
+const QUIET_ZONE: u32 = 4;
+/// 每轮识别的帧数。
+const FRAMES: usize = 8;
+
+/// 生成内容为 `content` 的二维码 PNG 图片。
+fn qrcode_png(content: &str) -> Vec<u8> {
+    let code = QrCode::new(content).unwrap();
+    let modules = code.width() as u32;
+    let colors = code.to_colors();
+    let size = (modules + QUIET_ZONE * 2) * SCALE;
+    let image = GrayImage::from_fn(size, size, |x, y| {
+        let (x, y) = (x / SCALE, y / SCALE);
+        let dark = (QUIET_ZONE..QUIET_ZONE + modules).contains(&x)
+            && (QUIET_ZONE..QUIET_ZONE + modules).contains(&y)
+            && colors[((y - QUIET_ZONE) * modules + x - QUIET_ZONE) as usize] == Color::Dark;
+        Luma([if dark { 0 } else { 255 }])
+    });
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageOutputFormat::Png).unwrap();
+    png.into_inner()
+}
+
+fn decode(c: &mut Criterion) {
+    let frames: Vec<_> = (0..FRAMES)
+        .map(|i| qrcode_png(&format!("https://txz.qq.com/p?k=chocho-{}&f=1", i)))
+        .collect();
+
+    let mut group = c.benchmark_group("qrcode_decode");
+    group.bench_function("new_decoder_per_frame", |b| {
+        b.iter(|| {
+            for frame in &frames {
+                QrcodeDecoder::new().decode(frame).unwrap();
+            }
+        })
+    });
+    group.bench_function("reused_decoder", |b| {
+        let mut decoder = QrcodeDecoder::new();
+        b.iter(|| {
+            for frame in &frames {
+                decoder.decode(frame).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,191 @@
+//! 滑块验证。
+//!
+//! 密码登录遇到滑块验证时，chocho 会调用 [`CaptchaSolver`] 获取 ticket。
+//! 默认使用 [`StdinCaptchaSolver`] 从标准输入读取 ticket，也可以通过
+//! [`LoginOptions::captcha_solver`](crate::LoginOptions::captcha_solver) 指定其他方式。
+//!
+//! 启用 `captcha-server` 特性后，可以使用 [`LocalServerSolver`] 在本地启动 HTTP 服务器，
+//! 接收 [TxCaptchaHelper](https://github.com/mzdluo123/TxCaptchaHelper) 等工具提交的 ticket。
+//!
+//! 异步函数也实现了 [`CaptchaSolver`]，可以用来调用外部的求解服务：
+//!
+//! ```
+//! use chocho_login::LoginOptions;
+//!
+//! async fn solve(url: String) -> anyhow::Result<String> {
+//!     // 调用外部求解服务……
+//!     Ok(format!("ticket for {}", url))
+//! }
+//!
+//! let options = LoginOptions::new().captcha_solver(solve);
+//! ```
+
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+/// 滑块验证求解器。
+#[async_trait::async_trait]
+pub trait CaptchaSolver: Send + Sync {
+    /// 完成滑块验证，返回 ticket。
+    ///
+    /// `url` 是滑块验证的链接。
+    async fn solve(&self, url: &str) -> Result<String>;
+}
+
+#[async_trait::async_trait]
+impl<F, Fut> CaptchaSolver for F
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String>> + Send,
+{
+    async fn solve(&self, url: &str) -> Result<String> {
+        self(url.to_string()).await
+    }
+}
+
+/// 从标准输入读取 ticket。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdinCaptchaSolver;
+
+#[async_trait::async_trait]
+impl CaptchaSolver for StdinCaptchaSolver {
+    async fn solve(&self, url: &str) -> Result<String> {
+        tracing::info!("滑块 url: {}", url);
+        tracing::info!("请输入 ticket:");
+        let mut reader = FramedRead::new(tokio::io::stdin(), LinesCodec::new());
+        let ticket = reader
+            .next()
+            .await
+            .transpose()?
+            .ok_or_else(|| anyhow!("标准输入已关闭"))?;
+        Ok(ticket.trim().to_string())
+    }
+}
+
+#[cfg(feature = "captcha-server")]
+pub use server::LocalServerSolver;
+
+#[cfg(feature = "captcha-server")]
+mod server {
+    use std::io::ErrorKind;
+
+    use anyhow::Result;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::CaptchaSolver;
+
+    /// 在本地启动 HTTP 服务器接收 ticket。
+    ///
+    /// 完成滑块验证后，以 `GET /?ticket=<ticket>` 或 `POST /`（请求体为 ticket）的方式提交 ticket。
+    /// 指定的端口被占用时，会改用系统分配的端口，实际地址会打印在日志中。
+    #[derive(Debug, Clone, Copy)]
+    pub struct LocalServerSolver {
+        port: u16,
+    }
+
+    impl Default for LocalServerSolver {
+        fn default() -> Self {
+            Self { port: 8848 }
+        }
+    }
+
+    impl LocalServerSolver {
+        /// 创建求解器，监听指定端口。
+        pub fn new(port: u16) -> Self {
+            Self { port }
+        }
+
+        async fn bind(&self) -> Result<TcpListener> {
+            match TcpListener::bind(("127.0.0.1", self.port)).await {
+                Ok(listener) => Ok(listener),
+                Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                    tracing::warn!("端口 {} 已被占用，改用随机端口", self.port);
+                    Ok(TcpListener::bind(("127.0.0.1", 0)).await?)
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CaptchaSolver for LocalServerSolver {
+        async fn solve(&self, url: &str) -> Result<String> {
+            let listener = self.bind().await?;
+            let addr = listener.local_addr()?;
+            tracing::info!("滑块 url: {}", url);
+            tracing::info!(
+                "完成验证后，请将 ticket 提交到 http://{}/?ticket=<ticket>",
+                addr
+            );
+            loop {
+                let (stream, _) = listener.accept().await?;
+                match receive_ticket(stream).await {
+                    Ok(Some(ticket)) => return Ok(ticket),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("处理 ticket 请求失败: {}", e),
+                }
+            }
+        }
+    }
+
+    async fn receive_ticket(mut stream: TcpStream) -> Result<Option<String>> {
+        let mut buf = vec![0; 8192];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let ticket = parse_ticket(&request);
+        let response = match ticket {
+            Some(_) => "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nConnection: close\r\n\r\nticket 已收到",
+            None => "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain; charset=utf-8\r\nConnection: close\r\n\r\n未找到 ticket",
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(ticket)
+    }
+
+    /// 从 HTTP 请求中解析 ticket。
+    fn parse_ticket(request: &str) -> Option<String> {
+        let path = request.lines().next()?.split(' ').nth(1)?;
+        let from_query = path
+            .split_once('?')
+            .and_then(|(_, query)| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("ticket="))
+            })
+            .map(percent_decode);
+        let from_body = request.split_once("\r\n\r\n").map(|(_, body)| {
+            let body = body.trim();
+            percent_decode(body.strip_prefix("ticket=").unwrap_or(body))
+        });
+        from_query.or(from_body).filter(|ticket| !ticket.is_empty())
+    }
+
+    fn percent_decode(s: &str) -> String {
+        let bytes = s.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+                .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok())
+                .flatten()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match (escaped, bytes[i]) {
+                (Some(b), _) => {
+                    out.push(b);
+                    i += 3;
+                    continue;
+                }
+                (None, b'+') => out.push(b' '),
+                (None, b) => out.push(b),
+            }
+            i += 1;
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+}
@@ -0,0 +1,181 @@
+//! 连接服务器。
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use futures_util::future::BoxFuture;
+use ricq::client::{Client, Connector, DefaultConnector, NetworkStatus};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+/// 与服务器的连接。
+pub(crate) struct Connection {
+    /// 客户端的接收任务。
+    pub(crate) alive: JoinHandle<()>,
+    /// 连接的服务器地址。连接器的连接类型不是 TCP 时为 `None`。
+    pub(crate) server: Option<SocketAddr>,
+}
+
+/// 类型擦除后的连接器：连接服务器，并启动客户端的接收任务。
+pub(crate) type BoxedConnector =
+    Arc<dyn Fn(Arc<Client>) -> BoxFuture<'static, tokio::io::Result<Connection>> + Send + Sync>;
+
+/// 擦除连接器的类型，以便保存在 [`LoginOptions`](crate::LoginOptions) 和 [`AliveHandle`](crate::AliveHandle) 中。
+pub(crate) fn boxed<C, S>(connector: C) -> BoxedConnector
+where
+    C: Connector<S> + Send + Sync + 'static,
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let connector = Arc::new(connector);
+    Arc::new(move |client: Arc<Client>| {
+        let connector = connector.clone();
+        Box::pin(async move {
+            let stream = connector.connect(&client).await?;
+            let alive = tokio::spawn(async move { client.start(stream).await });
+            Ok(Connection {
+                alive,
+                server: None,
+            })
+        })
+    })
+}
+
+/// 擦除 TCP 连接器的类型，并记录连接的服务器地址。
+pub(crate) fn boxed_tcp<C>(connector: C) -> BoxedConnector
+where
+    C: Connector<TcpStream> + Send + Sync + 'static,
+{
+    let connector = Arc::new(connector);
+    Arc::new(move |client: Arc<Client>| {
+        let connector = connector.clone();
+        Box::pin(async move {
+            let stream = connector.connect(&client).await?;
+            let server = stream.peer_addr().ok();
+            let alive = tokio::spawn(async move { client.start(stream).await });
+            Ok(Connection { alive, server })
+        })
+    })
+}
+
+/// 默认连接器，直接连接最快的服务器。
+pub(crate) fn default_connector() -> BoxedConnector {
+    boxed_tcp(DefaultConnector)
+}
+
+/// 连接列表中单个服务器的最长时间。
+const SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 依次尝试列表中的服务器，直到连接成功。
+///
+/// 下一次连接（例如断线重连）时，从上一次连接成功的服务器开始尝试。
+pub(crate) struct ServerList {
+    servers: Vec<SocketAddr>,
+    start: AtomicUsize,
+}
+
+impl ServerList {
+    pub(crate) fn new(servers: Vec<SocketAddr>) -> Self {
+        Self {
+            servers,
+            start: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector<TcpStream> for ServerList {
+    async fn connect(&self, _client: &Client) -> tokio::io::Result<TcpStream> {
+        let start = self.start.load(Ordering::SeqCst);
+        let mut last_error = None;
+        for i in 0..self.servers.len() {
+            let index = (start + i) % self.servers.len();
+            let addr = self.servers[index];
+            let error = match tokio::time::timeout(SERVER_TIMEOUT, TcpStream::connect(addr)).await {
+                Ok(Ok(stream)) => {
+                    tracing::info!("已连接到服务器 {}", addr);
+                    self.start.store(index, Ordering::SeqCst);
+                    return Ok(stream);
+                }
+                Ok(Err(e)) => e,
+                Err(_) => tokio::io::Error::new(tokio::io::ErrorKind::TimedOut, "连接超时"),
+            };
+            tracing::warn!("连接服务器 {} 失败：{}，尝试下一个服务器", addr, error);
+            last_error = Some(error);
+        }
+        Err(last_error.unwrap_or_else(|| {
+            tokio::io::Error::new(tokio::io::ErrorKind::NotFound, "服务器列表为空")
+        }))
+    }
+}
+
+/// 等待接收任务启动的最长时间。
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 为连接器加上超时限制。
+pub(crate) fn with_timeout(connector: BoxedConnector, timeout: Duration) -> BoxedConnector {
+    Arc::new(move |client: Arc<Client>| {
+        let connect = connector(client);
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, connect).await {
+                Ok(result) => result,
+                Err(_) => Err(tokio::io::Error::new(
+                    tokio::io::ErrorKind::TimedOut,
+                    format!("连接超时（{} 秒）", timeout.as_secs_f32()),
+                )),
+            }
+        })
+    })
+}
+
+/// 连接服务器，返回与服务器的连接。
+///
+/// 连接失败，或连接后客户端未能进入在线状态时，返回包含服务器地址的错误。
+pub(crate) async fn connect(
+    connector: &BoxedConnector,
+    client: &Arc<Client>,
+) -> Result<Connection> {
+    let connection = match connector(client.clone()).await {
+        Ok(connection) => connection,
+        Err(e) => bail!("连接服务器失败：{}。{}", e, diagnose(client).await),
+    };
+
+    // 等待接收任务启动，确保连上了
+    let alive = &connection.alive;
+    let started = tokio::time::timeout(STARTUP_TIMEOUT, async {
+        while client.get_status() != NetworkStatus::Running as u8 && !alive.is_finished() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await;
+    if started.is_err() || alive.is_finished() {
+        alive.abort();
+        bail!(
+            "已建立连接，但客户端未能进入在线状态（状态码 {}），连接可能被服务器关闭。{}",
+            client.get_status(),
+            diagnose(client).await
+        );
+    }
+    Ok(connection)
+}
+
+/// 生成连接失败时的诊断信息。
+async fn diagnose(client: &Client) -> String {
+    let addrs = client.get_address_list().await;
+    let addrs = addrs
+        .iter()
+        .map(|addr| addr.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "候选服务器：[{}]。请检查网络连接、代理与防火墙设置，或通过 `LoginOptions::connect_timeout` 延长超时时间",
+        addrs
+    )
+}
@@ -0,0 +1,672 @@
+//! 统一登录入口。
+//!
+//! [`login_with_credential`] 是所有登录方式的主入口，[`login_with_password`](crate::login_with_password)
+//! 和 [`login_with_qrcode`](crate::login_with_qrcode) 都是它的便捷封装。
+
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use bytes::Bytes;
+use ricq::{
+    client::{Connector, Token},
+    handler::Handler,
+    qsign::QSignClient,
+    version::Version,
+    Client, Device, Protocol,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+
+use crate::captcha::CaptchaSolver;
+use crate::connector::{
+    boxed, boxed_tcp, default_connector, with_timeout, BoxedConnector, ServerList,
+};
+use crate::device_lock::{DeviceLockStrategy, WebVerifyHandler};
+use crate::event::{emit, LoginEvent};
+use crate::heartbeat::HeartbeatConfig;
+use crate::layout::{DataLayout, DefaultLayout};
+use crate::login::{login_impl, token_login, LoginContext};
+use crate::password::password_login_with_options;
+use crate::qrcode::{qrcode_login_with_options, supports_qrcode};
+use crate::token::{FileTokenStore, NoTokenStore, TokenStore};
+use crate::AliveHandle;
+
+/// 登录凭据。
+pub enum Credential {
+    /// 账号密码。
+    Password {
+        /// QQ 号。
+        uin: i64,
+        /// 密码。
+        password: String,
+    },
+    /// 二维码。
+    QrCode {
+        /// QQ 号。
+        uin: i64,
+    },
+    /// 已有的 token。
+    ///
+    /// 直接使用此 token 登录，不会先尝试 [`TokenStore`] 中保存的 token。
+    /// 其它凭据总是先尝试保存的 token，失效时才使用凭据登录。
+    Token {
+        /// QQ 号。
+        uin: i64,
+        /// 登录 token。
+        token: Token,
+    },
+}
+
+impl Credential {
+    /// 凭据对应的 QQ 号。
+    pub fn uin(&self) -> i64 {
+        match self {
+            Credential::Password { uin, .. }
+            | Credential::QrCode { uin }
+            | Credential::Token { uin, .. } => *uin,
+        }
+    }
+}
+
+type ShowQrCode = Box<dyn FnMut(Bytes) -> Result<()> + Send>;
+type SmsHandler = Box<dyn FnMut(Option<String>) -> Result<String> + Send>;
+
+/// 登录选项。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_login::{LoginOptions, RQProtocol};
+///
+/// let options = LoginOptions::new()
+///     .protocol(RQProtocol::AndroidWatch)
+///     .show_qrcode(|qrcode| {
+///         println!("收到二维码，共 {} 字节", qrcode.len());
+///         Ok(())
+///     });
+/// ```
+pub struct LoginOptions {
+    pub(crate) protocol: Option<Protocol>,
+    pub(crate) show_qrcode: Option<ShowQrCode>,
+    pub(crate) sms_handler: Option<SmsHandler>,
+    pub(crate) device_lock_strategy: Option<DeviceLockStrategy>,
+    pub(crate) web_verify_handler: Option<WebVerifyHandler>,
+    pub(crate) token_store: Option<Arc<dyn TokenStore>>,
+    pub(crate) data_layout: Option<Arc<dyn DataLayout>>,
+    pub(crate) events: Option<mpsc::Sender<LoginEvent>>,
+    pub(crate) captcha_solver: Option<Box<dyn CaptchaSolver>>,
+    pub(crate) captcha_timeout: Duration,
+    pub(crate) connector: Option<BoxedConnector>,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) heartbeat: Option<HeartbeatConfig>,
+    pub(crate) token_save_interval: Duration,
+    pub(crate) version: Option<Version>,
+    pub(crate) qrcode_poll_interval: Duration,
+    pub(crate) qrcode_timeout: Option<Duration>,
+    pub(crate) qrcode_max_refreshes: Option<usize>,
+    pub(crate) qrcode_refresh: Option<mpsc::Receiver<()>>,
+    pub(crate) validate_device: bool,
+    pub(crate) device: Option<Device>,
+}
+
+impl Default for LoginOptions {
+    fn default() -> Self {
+        Self {
+            protocol: None,
+            show_qrcode: None,
+            sms_handler: None,
+            device_lock_strategy: None,
+            web_verify_handler: None,
+            token_store: None,
+            data_layout: None,
+            events: None,
+            captcha_solver: None,
+            captcha_timeout: Duration::from_secs(300),
+            connector: None,
+            connect_timeout: Duration::from_secs(30),
+            heartbeat: None,
+            token_save_interval: Duration::from_secs(30 * 60),
+            version: None,
+            qrcode_poll_interval: Duration::from_secs(5),
+            qrcode_timeout: None,
+            qrcode_max_refreshes: Some(5),
+            qrcode_refresh: None,
+            validate_device: true,
+            device: None,
+        }
+    }
+}
+
+impl LoginOptions {
+    /// 创建默认的登录选项。
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 指定客户端协议。
+    ///
+    /// 不指定时，密码与 token 登录使用 iPad 协议，二维码登录使用 Android 手表协议。
+    /// 二维码登录只支持部分协议，指定不支持的协议时登录会返回错误，参见 [`supports_qrcode`](crate::qrcode::supports_qrcode)。
+    pub fn protocol(mut self, protocol: impl Into<Protocol>) -> Self {
+        self.protocol = Some(protocol.into());
+        self
+    }
+
+    /// 指定展示二维码的回调函数，仅用于二维码登录。
+    ///
+    /// 不指定时，在控制台打印二维码（需要 `show-qrcode` 特性）。
+    pub fn show_qrcode(
+        mut self,
+        show_qrcode: impl FnMut(Bytes) -> Result<()> + Send + 'static,
+    ) -> Self {
+        self.show_qrcode = Some(Box::new(show_qrcode));
+        self
+    }
+
+    /// 指定获取短信验证码的回调函数。
+    ///
+    /// 遇到设备锁且使用短信验证时，会请求发送短信验证码，并调用此回调获取用户输入的验证码。
+    /// 回调的参数是接收验证码的手机号。验证码错误时会再次调用，最多尝试 3 次。
+    /// 回调在阻塞线程中调用，可以直接读取标准输入。
+    ///
+    /// 参见 [`device_lock_strategy`](Self::device_lock_strategy)。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_login::LoginOptions;
+    ///
+    /// let options = LoginOptions::new().sms_handler(|phone| {
+    ///     println!("请输入发送到 {} 的验证码：", phone.unwrap_or_default());
+    ///     let mut code = String::new();
+    ///     std::io::stdin().read_line(&mut code)?;
+    ///     Ok(code)
+    /// });
+    /// ```
+    pub fn sms_handler(
+        mut self,
+        sms_handler: impl FnMut(Option<String>) -> Result<String> + Send + 'static,
+    ) -> Self {
+        self.sms_handler = Some(Box::new(sms_handler));
+        self
+    }
+
+    /// 指定遇到设备锁时的处理方式，对密码登录和二维码登录均有效。
+    ///
+    /// 不指定时，若设置了 [`sms_handler`](Self::sms_handler) 且账号绑定了手机号，使用短信验证，否则直接返回错误。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_login::{DeviceLockStrategy, LoginOptions};
+    ///
+    /// let options = LoginOptions::new()
+    ///     .device_lock_strategy(DeviceLockStrategy::WebVerify)
+    ///     .web_verify_handler(|url| {
+    ///         println!("请前往 {} 完成验证，完成后按回车继续", url);
+    ///         std::io::stdin().read_line(&mut String::new())?;
+    ///         Ok(())
+    ///     });
+    /// ```
+    pub fn device_lock_strategy(mut self, strategy: DeviceLockStrategy) -> Self {
+        self.device_lock_strategy = Some(strategy);
+        self
+    }
+
+    /// 指定网页验证设备锁的回调函数。
+    ///
+    /// 回调的参数是验证链接，应在用户完成验证后返回。回调在阻塞线程中调用，可以直接读取标准输入。
+    /// 不指定时，在控制台打印验证链接，并等待用户按下回车。
+    pub fn web_verify_handler(
+        mut self,
+        web_verify_handler: impl FnMut(String) -> Result<()> + Send + 'static,
+    ) -> Self {
+        self.web_verify_handler = Some(Box::new(web_verify_handler));
+        self
+    }
+
+    /// 指定 token 的存储后端。
+    ///
+    /// 不指定时，使用 [`FileTokenStore`] 将 token 保存在数据文件夹中。
+    pub fn token_store(mut self, token_store: impl TokenStore + 'static) -> Self {
+        self.token_store = Some(Arc::new(token_store));
+        self
+    }
+
+    /// 指定数据目录结构。
+    ///
+    /// 不指定时，使用 [`DefaultLayout`]，即 `{data_folder}/{uin}/device.json` 和 `{data_folder}/{uin}/token.json`。
+    /// 未指定 [`token_store`](Self::token_store) 时，默认的文件存储也会使用此目录结构。参见 [`layout`](crate::layout)。
+    pub fn data_layout(mut self, data_layout: impl DataLayout + 'static) -> Self {
+        self.data_layout = Some(Arc::new(data_layout));
+        self
+    }
+
+    /// 订阅登录进度事件。
+    ///
+    /// 登录过程中的状态变化（获取二维码、等待扫码、需要验证等）会以 [`LoginEvent`] 的形式发送到 `events`。
+    /// 接收端关闭后，事件会被丢弃，不影响登录。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_login::{event::LoginEvent, LoginOptions};
+    ///
+    /// # async fn _f() {
+    /// let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+    /// let options = LoginOptions::new().events(sender);
+    /// tokio::spawn(async move {
+    ///     while let Some(event) = receiver.recv().await {
+    ///         match event {
+    ///             LoginEvent::WaitingForScan => println!("请扫码"),
+    ///             LoginEvent::WaitingForConfirm => println!("请在手机上确认登录"),
+    ///             LoginEvent::Success { uin } => println!("{} 登录成功", uin),
+    ///             _ => {}
+    ///         }
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn events(mut self, events: mpsc::Sender<LoginEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 指定滑块验证的求解器，仅用于密码登录。
+    ///
+    /// 不指定时，从标准输入读取 ticket。参见 [`captcha`](crate::captcha)。
+    pub fn captcha_solver(mut self, captcha_solver: impl CaptchaSolver + 'static) -> Self {
+        self.captcha_solver = Some(Box::new(captcha_solver));
+        self
+    }
+
+    /// 指定滑块验证的超时时间，默认为 5 分钟。
+    pub fn captcha_timeout(mut self, captcha_timeout: Duration) -> Self {
+        self.captcha_timeout = captcha_timeout;
+        self
+    }
+
+    /// 指定轮询二维码状态的间隔，仅用于二维码登录。默认为 5 秒。
+    pub fn qrcode_poll_interval(mut self, interval: Duration) -> Self {
+        self.qrcode_poll_interval = interval;
+        self
+    }
+
+    /// 指定二维码登录的最长等待时间，仅用于二维码登录。
+    ///
+    /// 超过该时间仍未完成扫码确认时，登录返回错误。不指定时不限制等待时间。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use chocho_login::LoginOptions;
+    ///
+    /// // 30 秒内扫不上就退出
+    /// let options = LoginOptions::new().qrcode_timeout(Duration::from_secs(30));
+    /// ```
+    pub fn qrcode_timeout(mut self, timeout: Duration) -> Self {
+        self.qrcode_timeout = Some(timeout);
+        self
+    }
+
+    /// 指定二维码过期后连续重新获取的最大次数，仅用于二维码登录。
+    ///
+    /// 超过次数后登录返回错误。默认为 5 次，传入 `None` 表示不限制。
+    pub fn qrcode_max_refreshes(mut self, max_refreshes: impl Into<Option<usize>>) -> Self {
+        self.qrcode_max_refreshes = max_refreshes.into();
+        self
+    }
+
+    /// 指定主动刷新二维码的信号，仅用于二维码登录。
+    ///
+    /// 每收到一个信号，立即重新获取二维码并回调新的图像。主动刷新不计入
+    /// [`qrcode_max_refreshes`](Self::qrcode_max_refreshes) 的次数。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_login::LoginOptions;
+    ///
+    /// let (refresh, receiver) = tokio::sync::mpsc::channel(1);
+    /// let options = LoginOptions::new().qrcode_refresh(receiver);
+    /// // 在界面上点击「刷新」时：
+    /// let _ = refresh.try_send(());
+    /// ```
+    pub fn qrcode_refresh(mut self, refresh: mpsc::Receiver<()>) -> Self {
+        self.qrcode_refresh = Some(refresh);
+        self
+    }
+
+    /// 是否在登录前校验 `device.json`，默认为 `true`。
+    ///
+    /// 校验规则参见 [`device::validate`](crate::device::validate)，校验失败时仅打印警告，不影响登录。
+    /// 使用特殊设备信息时，可以关闭校验以避免误报。
+    pub fn validate_device(mut self, validate_device: bool) -> Self {
+        self.validate_device = validate_device;
+        self
+    }
+
+    /// 指定设备信息，不再读取或写入 `device.json`。
+    ///
+    /// 设备信息需要由调用者持久化，每次登录应使用相同的设备信息，否则可能触发设备锁验证。
+    /// 登录后服务器分配的 qimei 不会写回，可以通过 `client.engine` 读取后自行保存。
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// 指定连接服务器的方式。
+    ///
+    /// 不指定时，使用 [`DefaultConnector`](ricq::client::DefaultConnector) 直连最快的服务器。
+    /// 断线重连时会复用同一个连接器。
+    ///
+    /// # Examples
+    ///
+    /// 借助 [`tokio-socks`](https://docs.rs/tokio-socks) 通过 SOCKS5 代理连接：
+    ///
+    /// ```ignore
+    /// use async_trait::async_trait;
+    /// use chocho_login::LoginOptions;
+    /// use ricq::client::{Client, Connector};
+    /// use tokio_socks::tcp::Socks5Stream;
+    ///
+    /// struct Socks5Connector {
+    ///     proxy: String,
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Connector<Socks5Stream<tokio::net::TcpStream>> for Socks5Connector {
+    ///     async fn connect(
+    ///         &self,
+    ///         client: &Client,
+    ///     ) -> tokio::io::Result<Socks5Stream<tokio::net::TcpStream>> {
+    ///         let addrs = client.get_address_list().await;
+    ///         let addr = addrs.first().ok_or(tokio::io::ErrorKind::NotFound)?;
+    ///         Socks5Stream::connect(self.proxy.as_str(), *addr)
+    ///             .await
+    ///             .map_err(|e| tokio::io::Error::new(tokio::io::ErrorKind::Other, e))
+    ///     }
+    /// }
+    ///
+    /// let options = LoginOptions::new().connector(Socks5Connector {
+    ///     proxy: "127.0.0.1:1080".to_string(),
+    /// });
+    /// ```
+    pub fn connector<C, S>(mut self, connector: C) -> Self
+    where
+        C: Connector<S> + Send + Sync + 'static,
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        self.connector = Some(boxed(connector));
+        self
+    }
+
+    /// 指定候选服务器列表，代替自动选择的服务器。
+    ///
+    /// 连接时按顺序尝试列表中的服务器，单个服务器 5 秒内未连接成功时尝试下一个；断线重连时从上一次连接成功的服务器开始尝试。
+    /// 当前连接的服务器可以通过 [`ConnectionMonitor::server`](crate::ConnectionMonitor::server) 查看。
+    ///
+    /// 此选项会覆盖 [`connector`](LoginOptions::connector)，列表中的服务器全部连接失败时登录失败。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_login::LoginOptions;
+    ///
+    /// let options = LoginOptions::new().servers([
+    ///     "120.232.18.27:8080".parse().unwrap(),
+    ///     "157.255.13.77:14000".parse().unwrap(),
+    /// ]);
+    /// ```
+    pub fn servers(mut self, servers: impl IntoIterator<Item = SocketAddr>) -> Self {
+        let servers = ServerList::new(servers.into_iter().collect());
+        self.connector = Some(boxed_tcp(servers));
+        self
+    }
+
+    /// 指定连接服务器的超时时间，默认为 30 秒。
+    ///
+    /// 登录与断线重连时都会使用此超时时间。
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// 指定心跳检测配置，参见 [`HeartbeatConfig`]。
+    ///
+    /// 不指定时只使用 ricq 内置的心跳，连接断开后才会重连。
+    pub fn heartbeat(mut self, heartbeat: HeartbeatConfig) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    /// 指定定期保存 token 的间隔，默认为 30 分钟，设为 0 时不定期保存。
+    ///
+    /// 登录和重连成功后总会保存 token。会话期间 token 可能被服务器刷新，定期保存可以让进程意外退出后，
+    /// 使用最新的 token 重新登录。
+    pub fn token_save_interval(mut self, interval: Duration) -> Self {
+        self.token_save_interval = interval;
+        self
+    }
+
+    /// 指定客户端版本，覆盖协议对应的默认版本。
+    ///
+    /// 旧版本被风控时，可以手动指定更新的版本，而不必等待 ricq 更新。
+    /// 指定版本后，[`protocol`](LoginOptions::protocol) 只用于选择登录流程，客户端使用的协议以 `version` 中的为准。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_login::LoginOptions;
+    /// use ricq::{version::get_version, Protocol};
+    ///
+    /// let mut version = get_version(Protocol::IPad);
+    /// version.build_ver = "8.9.50.611";
+    /// let options = LoginOptions::new().version(version);
+    /// ```
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+}
+
+fn default_show_qrcode() -> ShowQrCode {
+    Box::new(|qrcode| {
+        #[cfg(feature = "show-qrcode")]
+        {
+            println!("{}", crate::qrcode::qrcode_text(&qrcode)?);
+            Ok(())
+        }
+        #[cfg(not(feature = "show-qrcode"))]
+        {
+            let _ = qrcode;
+            anyhow::bail!("未指定二维码展示方式，请通过 `LoginOptions::show_qrcode` 设置")
+        }
+    })
+}
+
+/// 从登录选项中取出登录流程共用的部分。
+pub(crate) fn login_context(data_folder: &Path, options: &mut LoginOptions) -> LoginContext {
+    let layout = options
+        .data_layout
+        .take()
+        .unwrap_or_else(|| Arc::new(DefaultLayout::new(data_folder)));
+    let token_store = options
+        .token_store
+        .take()
+        .unwrap_or_else(|| Arc::new(FileTokenStore::with_layout(layout.clone())));
+    LoginContext {
+        layout,
+        token_store,
+        connector: with_timeout(
+            options.connector.take().unwrap_or_else(default_connector),
+            options.connect_timeout,
+        ),
+        validate_device: options.validate_device,
+        device: options.device.take(),
+        heartbeat: options.heartbeat,
+        token_save_interval: options.token_save_interval,
+        version: options.version,
+        use_stored_token: true,
+    }
+}
+
+/// 使用指定凭据登录。
+///
+/// # Arguments
+///
+/// * `credential` - 登录凭据。
+/// * `data_folder` - 数据文件夹。指定了 [`LoginOptions::data_layout`] 时不使用。
+/// * `handler` - 事件处理器。
+/// * `qsign_client` - qsign 客户端。
+/// * `options` - 登录选项。
+///
+/// # Returns
+///
+/// 返回一个元组`(Arc<Client>, AliveHandle)`，代表客户端实例和 Keep Alive 的句柄。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{time::Duration, sync::Arc};
+/// use chocho_login::{login_with_credential, Credential, LoginOptions, QSignClient};
+/// use ricq::handler::DefaultHandler;
+/// use anyhow::Result;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let qsign_client = Arc::new(QSignClient::new(
+///         "http://localhost:5000".to_string(),
+///         "114514".to_string(),
+///         Duration::from_secs(60),
+///     )?);
+///     let (client, alive) = login_with_credential(
+///         Credential::QrCode { uin: 123456789 },
+///         "./data",
+///         DefaultHandler,
+///         qsign_client,
+///         LoginOptions::new(),
+///     ).await?;
+///     alive.auto_reconnect().await?;
+/// }
+/// ```
+pub async fn login_with_credential(
+    credential: Credential,
+    data_folder: impl AsRef<Path>,
+    handler: impl Handler + 'static + Send,
+    qsign_client: Arc<QSignClient>,
+    mut options: LoginOptions,
+) -> Result<(Arc<Client>, AliveHandle)> {
+    let events = options.events.clone();
+    let mut context = login_context(data_folder.as_ref(), &mut options);
+    let (client, alive) = match credential {
+        Credential::Password { uin, password } => {
+            let protocol = options.protocol.take().unwrap_or(Protocol::IPad);
+            login_impl(
+                uin,
+                protocol,
+                qsign_client,
+                handler,
+                context,
+                move |client| async move {
+                    password_login_with_options(&client, uin, &password, &mut options).await
+                },
+            )
+            .await
+        }
+        Credential::QrCode { uin } => {
+            let protocol = options.protocol.take().unwrap_or(Protocol::AndroidWatch);
+            if !supports_qrcode(&protocol) {
+                anyhow::bail!(
+                    "协议 {:?} 不支持二维码登录，请使用 Android 手表或 macOS 协议",
+                    protocol
+                );
+            }
+            let show_qrcode = options
+                .show_qrcode
+                .take()
+                .unwrap_or_else(default_show_qrcode);
+            login_impl(
+                uin,
+                protocol,
+                qsign_client,
+                handler,
+                context,
+                move |client| async move {
+                    qrcode_login_with_options(&client, uin, show_qrcode, &mut options).await
+                },
+            )
+            .await
+        }
+        Credential::Token { uin, token } => {
+            let protocol = options.protocol.take().unwrap_or(Protocol::IPad);
+            // 显式指定的 token 优先于保存的 token
+            context.use_stored_token = false;
+            login_impl(
+                uin,
+                protocol,
+                qsign_client,
+                handler,
+                context,
+                move |client| async move { token_login(&client, token).await },
+            )
+            .await
+        }
+    }?;
+    emit(
+        events.as_ref(),
+        LoginEvent::Success {
+            uin: client.uin().await,
+        },
+    )
+    .await;
+    Ok((client, alive))
+}
+
+/// 使用已有的设备信息登录，不读写数据文件夹。
+///
+/// 与 [`login_with_credential`] 相同，但设备信息由 `device` 指定，参见 [`LoginOptions::device`]。
+/// 未指定 [`LoginOptions::token_store`] 时不保存 token（[`NoTokenStore`]），每次启动都需要使用凭据登录；
+/// 指定后 token 登录、定期保存等功能正常工作。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{time::Duration, sync::Arc};
+/// use chocho_login::{device, login_with_device, Credential, LoginOptions, QSignClient};
+/// use ricq::handler::DefaultHandler;
+///
+/// # async fn _f(device_json: String) -> anyhow::Result<()> {
+/// let qsign_client = Arc::new(QSignClient::new(
+///     "http://localhost:5000".to_string(),
+///     "114514".to_string(),
+///     Duration::from_secs(60),
+/// )?);
+/// // 例如从数据库中读取的设备信息
+/// let device = device::from_json(&device_json, &device::random_from_uin(123456789))?;
+/// let (client, alive) = login_with_device(
+///     Credential::QrCode { uin: 123456789 },
+///     device,
+///     DefaultHandler,
+///     qsign_client,
+///     LoginOptions::new(),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn login_with_device(
+    credential: Credential,
+    device: Device,
+    handler: impl Handler + 'static + Send,
+    qsign_client: Arc<QSignClient>,
+    mut options: LoginOptions,
+) -> Result<(Arc<Client>, AliveHandle)> {
+    options.device = Some(device);
+    if options.token_store.is_none() {
+        options.token_store = Some(Arc::new(NoTokenStore));
+    }
+    // 设备信息和 token 都不经过文件，数据文件夹不会被使用
+    login_with_credential(credential, "", handler, qsign_client, options).await
+}
@@ -52,12 +52,13 @@
 //!
 
 use anyhow::{anyhow, bail, Result};
-use rand::SeedableRng;
+use rand::{RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use ricq::{device::OSVersion, Device};
-use ricq_core::protocol::qimei::Qimei;
 use serde_json::{Map, Value};
 
+pub use ricq_core::protocol::qimei::Qimei;
+
 macro_rules! parse_batch {
     ($version:ty, $json:ident, $fallback:ident, $($key:expr => $name:ident,)*) => {
         Device {
@@ -118,6 +119,65 @@ macro_rules! parse {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// 版本 1 格式中的字节数组可以使用有符号（mirai 的默认格式）或无符号的字节，超出范围的值会导致解析失败：
+///
+/// ```
+/// use chocho_login::device::{from_json, random_from_uin};
+///
+/// let fallback = random_from_uin(123456789);
+/// let version = r#""version": {"incremental": [53], "release": [49, 48], "codename": [82, 69, 76], "sdk": 29}"#;
+///
+/// let json = format!(r#"{{"imsiMd5": [-1, 0, 127, -128, 255], {}}}"#, version);
+/// let device = from_json(&json, &fallback).unwrap();
+/// assert_eq!(device.imsi_md5, [0xff, 0x00, 0x7f, 0x80, 0xff]);
+/// assert_eq!(device.version.release, "10");
+///
+/// let json = format!(r#"{{"imsiMd5": [256], {}}}"#, version);
+/// let err = from_json(&json, &fallback).unwrap_err();
+/// assert!(err.to_string().contains("超出范围"));
+///
+/// let json = format!(r#"{{"imsiMd5": [-129], {}}}"#, version);
+/// assert!(from_json(&json, &fallback).is_err());
+///
+/// let json = format!(r#"{{"imsiMd5": [1.5], {}}}"#, version);
+/// assert!(from_json(&json, &fallback).is_err());
+/// ```
+///
+/// 支持 mirai 新版的 `deviceInfoVersion` 3 格式，未知字段会被忽略，缺失字段使用 `fallback` 中的值：
+///
+/// ```
+/// use chocho_login::device::{from_json, random_from_uin};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let json = r#"{
+///     "deviceInfoVersion": 3,
+///     "data": {
+///         "display": "ROG Phone 3",
+///         "product": "ASUS_I003DD",
+///         "imsiMd5": "3e20e2c552e4a01c43cd7c802310b778",
+///         "ipAddress": [10, 0, 1, 3],
+///         "version": {
+///             "incremental": "5891938",
+///             "release": "10",
+///             "codename": "REL",
+///             "sdk": "29"
+///         },
+///         "unknownField": "ignored"
+///     },
+///     "extra": {}
+/// }"#;
+///
+/// let fallback = random_from_uin(123456789);
+/// let device = from_json(json, &fallback)?;
+/// assert_eq!(device.display, "ROG Phone 3");
+/// assert_eq!(hex::encode(&device.imsi_md5), "3e20e2c552e4a01c43cd7c802310b778");
+/// assert_eq!(device.ip_address, [10, 0, 1, 3]);
+/// assert_eq!(device.version.sdk, 29);
+/// assert_eq!(device.model, fallback.model);
+/// # Ok(())
+/// # }
+/// ```
 pub fn from_json(json: &str, fallback: &Device) -> Result<Device> {
     let json: Value = serde_json::from_str(json)?;
     let json = json
@@ -141,34 +201,423 @@ pub fn from_json(json: &str, fallback: &Device) -> Result<Device> {
                 .ok_or_else(|| anyhow!("未找到 `data` 字段"))?;
             Ok(parse!(V2, json, fallback))
         }
+        3 => {
+            // 版本3：与版本2相同，但字节数组字段也可能以数组形式出现，数字字段可能以字符串形式出现
+            let json = json
+                .get("data")
+                .and_then(|v| v.as_object())
+                .ok_or_else(|| anyhow!("未找到 `data` 字段"))?;
+            Ok(parse!(V3, json, fallback))
+        }
         _ => bail!("未知的 `deviceInfoVersion` 值: {}", version),
     }
 }
 
+/// 读取设备信息中缓存的 qimei。
+///
+/// 登录时会复用缓存的 qimei，避免频繁重新申请触发风控；登录成功后，服务器返回的最新 qimei 会写回 `device.json`。
+/// 缓存的 qimei 格式不正确时视为已失效，返回 `None`，登录时会重新申请。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_login::device::{get_qimei, random_from_uin, set_qimei, Qimei};
+///
+/// let mut device = random_from_uin(123456789);
+/// assert!(get_qimei(&device).is_none());
+///
+/// set_qimei(&mut device, Some(Qimei {
+///     q16: "0123456789abcdef".to_string(),
+///     q36: "0123456789abcdef0123456789abcdef0123".to_string(),
+/// }));
+/// assert_eq!(get_qimei(&device).unwrap().q16, "0123456789abcdef");
+///
+/// // 格式不正确的 qimei 视为已失效
+/// set_qimei(&mut device, Some(Qimei { q16: "expired".to_string(), q36: String::new() }));
+/// assert!(get_qimei(&device).is_none());
+/// ```
+pub fn get_qimei(device: &Device) -> Option<&Qimei> {
+    device.qimei.as_ref().filter(|qimei| is_valid_qimei(qimei))
+}
+
+/// 设置设备信息中缓存的 qimei。传入 `None` 时清除缓存，下次登录时重新申请。
+pub fn set_qimei(device: &mut Device, qimei: Option<Qimei>) {
+    device.qimei = qimei;
+}
+
+/// qimei 的格式是否正确：`q16` 为 16 位、`q36` 为 36 位字母或数字。
+pub(crate) fn is_valid_qimei(qimei: &Qimei) -> bool {
+    let is_valid =
+        |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_alphanumeric());
+    is_valid(&qimei.q16, 16) && is_valid(&qimei.q36, 36)
+}
+
 /// 以 QQ 号为种子生成随机的设备信息。
 ///
 /// 使用 `rand_chacha` 作为随机数生成器，因此可以保证相同的 QQ 号生成的设备信息相同。
 ///
+/// 生成结果与 ricq 0.1.20 的 `Device::random_with_rng` 逐位一致，并且由 chocho 固定下来，
+/// 只使用随机数生成器的原始输出，不依赖 ricq 或 `rand` 的分布实现，因此升级依赖不会改变同一 QQ 号生成的设备信息。
+///
 /// # Examples
 ///
 /// ```
 /// let device = chocho_login::device::random_from_uin(123456789);
 /// assert_eq!(device.display, "RICQ.110281.001");
+/// assert_eq!(device.finger_print, "xiaomi/iarim/sagit:10/eomam.200122.001/6912206:user/release-keys");
+/// assert_eq!(device.boot_id, "dc2cbb52-5b7f-6845-4260-36ed8527f77b");
+/// assert_eq!(device.proc_version, "Linux version 4.19.71-okfMoHsm (android-build@google.com)");
+/// assert_eq!(device.imei, "946238919605855");
+/// assert_eq!(hex::encode(&device.imsi_md5), "5262ab82093988ee5bd9d8315c229ec7");
+/// assert_eq!(device.android_id, "c0f47d603b5650a3");
 /// ```
 pub fn random_from_uin(uin: i64) -> Device {
-    let mut seed = ChaCha8Rng::seed_from_u64(uin as u64);
-    Device::random_with_rng(&mut seed)
+    let mut rng = ChaCha8Rng::seed_from_u64(uin as u64);
+    generate_device(&mut rng)
+}
+
+macro_rules! device_overrides {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty,)*) => {
+        /// 设备信息中需要固定的字段，用于 [`random_with_overrides`]。
+        ///
+        /// 每个字段对应 [`Device`] 的同名字段，为 `None` 的字段使用随机生成的值。
+        #[derive(Debug, Clone, Default)]
+        pub struct DeviceOverrides {
+            $($(#[$doc])* pub $name: Option<$ty>,)*
+        }
+
+        impl DeviceOverrides {
+            fn apply(self, device: &mut Device) {
+                $(if let Some(value) = self.$name {
+                    device.$name = value;
+                })*
+            }
+        }
+    };
+}
+
+device_overrides! {
+    /// 系统版本号（`display`）。
+    display: String,
+    /// 产品名称。
+    product: String,
+    /// 设备名称。
+    device: String,
+    /// 主板名称。
+    board: String,
+    /// 设备型号。
+    model: String,
+    /// 系统指纹。
+    finger_print: String,
+    /// 启动 ID。
+    boot_id: String,
+    /// 内核版本。
+    proc_version: String,
+    /// IMEI。
+    imei: String,
+    /// 品牌。
+    brand: String,
+    /// 引导程序。
+    bootloader: String,
+    /// 基带版本。
+    base_band: String,
+    /// 系统版本。
+    version: OSVersion,
+    /// SIM 卡信息。
+    sim_info: String,
+    /// 系统类型。
+    os_type: String,
+    /// MAC 地址。
+    mac_address: String,
+    /// IP 地址。
+    ip_address: Vec<u8>,
+    /// WiFi BSSID。
+    wifi_bssid: String,
+    /// WiFi SSID。
+    wifi_ssid: String,
+    /// IMSI 的 md5。
+    imsi_md5: Vec<u8>,
+    /// Android ID。
+    android_id: String,
+    /// APN。
+    apn: String,
+    /// 厂商名称。
+    vendor_name: String,
+    /// 厂商系统名称。
+    vendor_os_name: String,
+}
+
+/// 以 QQ 号为种子生成随机的设备信息，并用 `overrides` 中指定的字段覆盖。
+///
+/// 未指定的字段与 [`random_from_uin`] 生成的值相同，因此相同的 QQ 号和相同的 `overrides` 总是生成相同的设备信息。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_login::device::{random_from_uin, random_with_overrides, DeviceOverrides};
+///
+/// let device = random_with_overrides(123456789, DeviceOverrides {
+///     model: Some("Pixel 7".to_string()),
+///     brand: Some("Google".to_string()),
+///     ..Default::default()
+/// });
+/// assert_eq!(device.model, "Pixel 7");
+/// assert_eq!(device.brand, "Google");
+/// assert_eq!(device.imei, random_from_uin(123456789).imei);
+/// ```
+pub fn random_with_overrides(uin: i64, overrides: DeviceOverrides) -> Device {
+    let mut device = random_from_uin(uin);
+    overrides.apply(&mut device);
+    device
+}
+
+/// 生成设备信息。
+///
+/// 与 ricq 0.1.20 的 `Device::random_with_rng` 逐位一致（包括随机数的抽取顺序），
+/// 保证从 ricq 生成设备信息迁移过来的账号，在没有 `device.json` 时仍能得到相同的设备信息。
+///
+/// **修改此函数会导致已有账号的设备信息改变，请勿随意修改。**
+fn generate_device(rng: &mut impl RngCore) -> Device {
+    Device {
+        display: format!("RICQ.{}.001", random_range(rng, 100000, 999999)),
+        product: "iarim".into(),
+        device: "sagit".into(),
+        board: "eomam".into(),
+        model: "MI 6".into(),
+        finger_print: format!(
+            "xiaomi/iarim/sagit:10/eomam.200122.001/{}:user/release-keys",
+            random_range(rng, 1000000, 9999999)
+        ),
+        boot_id: random_uuid(rng),
+        proc_version: format!(
+            "Linux version 4.19.71-{} (android-build@google.com)",
+            random_alphanumeric(rng, 8)
+        ),
+        imei: random_imei(rng),
+        brand: "Xiaomi".into(),
+        bootloader: "U-boot".into(),
+        base_band: "".into(),
+        version: OSVersion {
+            incremental: "5891938".into(),
+            release: "10".into(),
+            codename: "REL".into(),
+            sdk: 29,
+        },
+        sim_info: "T-Mobile".into(),
+        os_type: "android".into(),
+        mac_address: "00:50:56:C0:00:08".into(),
+        ip_address: vec![10, 0, 1, 3],
+        wifi_bssid: "00:50:56:C0:00:08".into(),
+        wifi_ssid: "<unknown ssid>".into(),
+        imsi_md5: md5::compute(random_bytes::<16>(rng)).to_vec(),
+        android_id: hex::encode(random_bytes::<8>(rng)),
+        apn: "wifi".into(),
+        vendor_name: "MIUI".into(),
+        vendor_os_name: "gmobile".into(),
+        qimei: None,
+    }
+}
+
+// 以下函数复刻了 rand 0.8.5 中对应分布的采样算法，只依赖随机数生成器的原始输出，
+// 因此升级 rand 不会改变生成的结果。
+
+/// 生成 `[low, high)` 范围内的随机整数，等同于 rand 0.8 的 `rng.gen_range(low..high)`（`i32`）。
+fn random_range(rng: &mut impl RngCore, low: i32, high: i32) -> i32 {
+    let range = high.wrapping_sub(low) as u32;
+    let zone = (range << range.leading_zeros()).wrapping_sub(1);
+    loop {
+        let v = rng.next_u32() as u64 * range as u64;
+        let (hi, lo) = ((v >> 32) as u32, v as u32);
+        if lo <= zone {
+            return low.wrapping_add(hi as i32);
+        }
+    }
+}
+
+/// 等同于 rand 0.8 的 `rng.gen::<[u8; N]>()`，每个字节消耗一个 `u32`。
+fn random_bytes<const N: usize>(rng: &mut impl RngCore) -> [u8; N] {
+    let mut bytes = [0; N];
+    for byte in &mut bytes {
+        *byte = rng.next_u32() as u8;
+    }
+    bytes
+}
+
+/// 等同于 rand 0.8 的 `Alphanumeric` 分布。
+fn random_alphanumeric(rng: &mut impl RngCore, len: usize) -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    (0..len)
+        .map(|_| loop {
+            let index = rng.next_u32() >> (32 - 6);
+            if (index as usize) < CHARSET.len() {
+                break CHARSET[index as usize] as char;
+            }
+        })
+        .collect()
+}
+
+fn random_uuid(rng: &mut impl RngCore) -> String {
+    let bytes = hex::encode(md5::compute(random_bytes::<16>(rng)).0);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &bytes[0..8],
+        &bytes[8..12],
+        &bytes[12..16],
+        &bytes[16..20],
+        &bytes[20..32]
+    )
+}
+
+/// 生成 15 位 IMEI，与 ricq（以及 MiraiGo）的算法相同。
+///
+/// 此算法的校验位是所有数位之和的补数，而不是标准的 Luhn 校验位。
+fn random_imei(rng: &mut impl RngCore) -> String {
+    let mut sum = 0;
+    let mut imei = String::with_capacity(15);
+    for i in 0..14 {
+        let mut digit = random_range(rng, 0, 10);
+        if i % 2 == 0 {
+            digit *= 2;
+            if digit >= 10 {
+                digit = digit % 10 + 1;
+            }
+        }
+        sum += digit;
+        imei.push_str(&digit.to_string());
+    }
+    imei.push_str(&(sum * 9 % 10).to_string());
+    imei
+}
+
+/// 设备信息校验错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// 字段名，与 `device.json` 中的键名相同。
+    pub field: &'static str,
+    /// 错误描述。
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` {}", self.field, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// 校验设备信息的关键字段。
+///
+/// 字段格式不正确的设备信息可能导致登录被风控。此函数检查所有规则，并返回发现的全部问题。
+/// 规则基于常见的 Android 设备，特殊设备可能无法通过校验，此时可以忽略校验结果。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_login::device::{random_from_uin, validate};
+///
+/// let mut device = random_from_uin(123456789);
+/// assert!(validate(&device).is_ok());
+///
+/// device.imei = "12345".to_string();
+/// device.mac_address = "00-50-56-C0-00-08".to_string();
+/// let errors = validate(&device).unwrap_err();
+/// let fields: Vec<_> = errors.iter().map(|e| e.field).collect();
+/// assert_eq!(fields, ["imei", "macAddress"]);
+/// ```
+pub fn validate(device: &Device) -> std::result::Result<(), Vec<ValidationError>> {
+    let mut errors = vec![];
+    let mut check = |ok: bool, field: &'static str, message: &str| {
+        if !ok {
+            errors.push(ValidationError {
+                field,
+                message: message.to_string(),
+            });
+        }
+    };
+
+    check(
+        is_valid_imei(&device.imei),
+        "imei",
+        "应为 15 位数字，且校验位正确",
+    );
+    check(
+        is_valid_mac(&device.mac_address),
+        "macAddress",
+        "应为 `XX:XX:XX:XX:XX:XX` 格式",
+    );
+    check(
+        is_valid_mac(&device.wifi_bssid),
+        "wifiBSSID",
+        "应为 `XX:XX:XX:XX:XX:XX` 格式",
+    );
+    check(
+        device.android_id.len() == 16 && device.android_id.bytes().all(|b| b.is_ascii_hexdigit()),
+        "androidId",
+        "应为 16 位十六进制字符",
+    );
+    check(device.imsi_md5.len() == 16, "imsiMd5", "应为 16 字节");
+    check(
+        device.ip_address.len() == 4,
+        "ipAddress",
+        "应为 4 字节的 IPv4 地址",
+    );
+    check(device.version.sdk > 0, "version.sdk", "应为正整数");
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn is_valid_imei(imei: &str) -> bool {
+    let Some(digits) = imei
+        .chars()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()
+    else {
+        return false;
+    };
+    if digits.len() != 15 {
+        return false;
+    }
+    // ricq 与 MiraiGo 生成的 IMEI 以所有数位之和为校验，其余按标准的 Luhn 校验
+    if digits.iter().sum::<u32>() % 10 == 0 {
+        return true;
+    }
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let d = d * 2;
+                d / 10 + d % 10
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+fn is_valid_mac(mac: &str) -> bool {
+    let parts: Vec<&str> = mac.split(':').collect();
+    parts.len() == 6
+        && parts
+            .iter()
+            .all(|part| part.len() == 2 && part.bytes().all(|b| b.is_ascii_hexdigit()))
 }
 
 macro_rules! dump_batch {
-    ($json:ident, $device:ident, $($key:expr => $name:ident,)*) => {
-        $($json.insert($key.to_string(), V2::dump(&$device.$name));)*
+    ($version:ty, $json:ident, $device:ident, $($key:expr => $name:ident,)*) => {
+        $($json.insert($key.to_string(), <$version>::dump(&$device.$name));)*
     };
 }
 
 macro_rules! dump {
-    ($json:ident, $device:ident) => {
-        dump_batch!($json, $device,
+    ($version:ty, $json:ident, $device:ident) => {
+        dump_batch!($version, $json, $device,
             "display" => display,
             "product" => product,
             "device" => device,
@@ -193,17 +642,20 @@ macro_rules! dump {
             "apn" => apn,
             "vendorName" => vendor_name,
             "vendorOsName" => vendor_os_name,
+            "qimei" => qimei,
         )
     }
 }
 
 /// 将设备信息写入 `device.json`。
+///
+/// 始终写入版本2，以便旧版本的 chocho 与其它框架读取；版本3只在读取时支持。
 pub(crate) fn to_json(device: &Device) -> Result<String> {
     let mut json = Map::new();
     json.insert("deviceInfoVersion".into(), Value::Number(2.into()));
     json.insert("data".into(), {
         let mut json = Map::new();
-        dump!(json, device);
+        dump!(V2, json, device);
         json.into()
     });
     Ok(serde_json::to_string_pretty(&json)?)
@@ -226,22 +678,28 @@ impl Parse<String> for V1 {
                 if let Some(s) = v.as_str() {
                     return Ok(s.to_string());
                 }
-                let bytes = v
-                    .as_array()
-                    .ok_or_else(|| anyhow!("`{}` 格式错误", key))?
-                    .iter()
-                    .map(|b| b.as_i64())
-                    .collect::<Option<Vec<i64>>>()
-                    .ok_or_else(|| anyhow!("`{}` 格式错误", key))?
-                    .iter()
-                    .map(|b| b.to_le_bytes()[0])
-                    .collect::<Vec<u8>>();
-                Ok(String::from_utf8(bytes)?)
+                Ok(String::from_utf8(parse_byte_array(v, key)?)?)
             })
             .unwrap_or_else(|| Ok(fallback()))
     }
 }
 
+/// 解析字节数组。
+///
+/// mirai 导出的字节是有符号的（-128 到 127），也兼容无符号的写法（0 到 255），超出范围时返回错误。
+fn parse_byte_array(v: &Value, key: &str) -> Result<Vec<u8>> {
+    v.as_array()
+        .ok_or_else(|| anyhow!("`{}` 格式错误", key))?
+        .iter()
+        .map(|b| match b.as_i64() {
+            Some(b @ -128..=-1) => Ok(b as i8 as u8),
+            Some(b @ 0..=255) => Ok(b as u8),
+            Some(b) => bail!("`{}` 格式错误：字节 {} 超出范围", key, b),
+            None => bail!("`{}` 格式错误：{} 不是整数", key, b),
+        })
+        .collect()
+}
+
 impl Parse<Vec<u8>> for V1 {
     fn parse(
         json: &Map<String, Value>,
@@ -249,19 +707,7 @@ impl Parse<Vec<u8>> for V1 {
         fallback: impl FnOnce() -> Vec<u8>,
     ) -> Result<Vec<u8>> {
         json.get(key)
-            .map(|v| -> Result<Vec<u8>> {
-                let bytes = v
-                    .as_array()
-                    .ok_or_else(|| anyhow!("`{}` 格式错误", key))?
-                    .iter()
-                    .map(|b| b.as_i64())
-                    .collect::<Option<Vec<i64>>>()
-                    .ok_or_else(|| anyhow!("`{}` 格式错误", key))?
-                    .iter()
-                    .map(|b| b.to_le_bytes()[0])
-                    .collect::<Vec<u8>>();
-                Ok(bytes)
-            })
+            .map(|v| -> Result<Vec<u8>> { parse_byte_array(v, key) })
             .unwrap_or_else(|| Ok(fallback()))
     }
 }
@@ -399,7 +845,7 @@ impl Parse<Option<Qimei>> for V2 {
         _fallback: impl FnOnce() -> Option<Qimei>,
     ) -> Result<Option<Qimei>> {
         match json.get(key) {
-            None => Ok(None),
+            None | Some(Value::Null) => Ok(None),
             Some(v) => {
                 let qimei = v.as_object().ok_or_else(|| anyhow!("`{}` 格式错误", key))?;
                 let q16 = <V2 as Parse<String>>::parse(qimei, "q16", || "".to_string())?;
@@ -415,6 +861,77 @@ impl Parse<Option<Qimei>> for V2 {
     }
 }
 
+struct V3;
+
+impl Parse<String> for V3 {
+    fn parse(
+        json: &Map<String, Value>,
+        key: &str,
+        fallback: impl FnOnce() -> String,
+    ) -> Result<String> {
+        match json.get(key) {
+            Some(Value::Array(_)) => V1::parse(json, key, fallback),
+            _ => V2::parse(json, key, fallback),
+        }
+    }
+}
+
+impl Parse<Vec<u8>> for V3 {
+    fn parse(
+        json: &Map<String, Value>,
+        key: &str,
+        fallback: impl FnOnce() -> Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        match json.get(key) {
+            Some(Value::Array(_)) => V1::parse(json, key, fallback),
+            _ => V2::parse(json, key, fallback),
+        }
+    }
+}
+
+impl Parse<u32> for V3 {
+    fn parse(json: &Map<String, Value>, key: &str, fallback: impl FnOnce() -> u32) -> Result<u32> {
+        match json.get(key) {
+            Some(Value::String(s)) => Ok(s.parse()?),
+            _ => V2::parse(json, key, fallback),
+        }
+    }
+}
+
+impl Parse<OSVersion> for V3 {
+    fn parse(
+        json: &Map<String, Value>,
+        key: &str,
+        fallback: impl FnOnce() -> OSVersion,
+    ) -> Result<OSVersion> {
+        let version = json
+            .get(key)
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| anyhow!("`{}` 格式错误", key))?;
+        let fallback = fallback();
+        let incremental = V3::parse(version, "incremental", || fallback.incremental)?;
+        let release = V3::parse(version, "release", || fallback.release)?;
+        let codename = V3::parse(version, "codename", || fallback.codename)?;
+        let sdk = V3::parse(version, "sdk", || fallback.sdk)?;
+        Ok(OSVersion {
+            incremental,
+            release,
+            codename,
+            sdk,
+        })
+    }
+}
+
+impl Parse<Option<Qimei>> for V3 {
+    fn parse(
+        json: &Map<String, Value>,
+        key: &str,
+        fallback: impl FnOnce() -> Option<Qimei>,
+    ) -> Result<Option<Qimei>> {
+        V2::parse(json, key, fallback)
+    }
+}
+
 trait Dump<T> {
     fn dump(value: &T) -> Value;
 }
@@ -0,0 +1,177 @@
+//! 设备锁处理。
+//!
+//! 密码登录和二维码登录遇到设备锁时，都按 [`DeviceLockStrategy`] 处理。
+
+use anyhow::{bail, Result};
+use futures_util::StreamExt;
+use ricq::{LoginDeviceLocked, LoginResponse};
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+use crate::credential::LoginOptions;
+use crate::event::{emit, LoginEvent};
+
+/// 遇到设备锁时的处理方式。
+///
+/// 通过 [`LoginOptions::device_lock_strategy`](crate::LoginOptions::device_lock_strategy) 指定。
+/// 不指定时，若设置了短信验证码回调且账号绑定了手机号，使用 [`Sms`](Self::Sms)，否则使用 [`Abort`](Self::Abort)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceLockStrategy {
+    /// 网页验证。
+    ///
+    /// 将验证链接交给 [`LoginOptions::web_verify_handler`](crate::LoginOptions::web_verify_handler)，
+    /// 等待用户完成验证后重新登录。
+    WebVerify,
+    /// 短信验证。
+    ///
+    /// 请求发送短信验证码，并通过 [`LoginOptions::sms_handler`](crate::LoginOptions::sms_handler) 获取验证码。
+    Sms,
+    /// 直接返回错误。
+    Abort,
+}
+
+pub(crate) type WebVerifyHandler = Box<dyn FnMut(String) -> Result<()> + Send>;
+
+/// 短信验证码的最大尝试次数。
+const MAX_SMS_ATTEMPTS: usize = 3;
+
+/// 设备锁的处理进度。
+#[derive(Default)]
+pub(crate) struct DeviceLockState {
+    /// 已请求发送短信验证码时，记录接收验证码的手机号
+    sms_sent: Option<Option<String>>,
+    sms_attempts: usize,
+}
+
+impl DeviceLockState {
+    /// 是否已经发送了短信验证码。
+    pub(crate) fn sms_sent(&self) -> bool {
+        self.sms_sent.is_some()
+    }
+}
+
+/// 处理设备锁。
+///
+/// 返回 `Some(resp)` 表示得到了新的登录响应；返回 `None` 表示网页验证已完成，调用方需要重新发起登录。
+pub(crate) async fn handle_device_locked(
+    client: &ricq::Client,
+    locked: LoginDeviceLocked,
+    options: &mut LoginOptions,
+    state: &mut DeviceLockState,
+) -> Result<Option<LoginResponse>> {
+    let LoginDeviceLocked {
+        sms_phone,
+        verify_url,
+        message,
+        ..
+    } = locked;
+
+    if state.sms_sent() {
+        return resubmit_sms_code(client, options, state).await.map(Some);
+    }
+
+    emit(
+        options.events.as_ref(),
+        LoginEvent::DeviceLocked {
+            url: verify_url.clone(),
+            phone: sms_phone.clone(),
+        },
+    )
+    .await;
+
+    let strategy = options.device_lock_strategy.unwrap_or(
+        if options.sms_handler.is_some() && sms_phone.is_some() {
+            DeviceLockStrategy::Sms
+        } else {
+            DeviceLockStrategy::Abort
+        },
+    );
+    match strategy {
+        DeviceLockStrategy::Sms => {
+            if options.sms_handler.is_none() {
+                bail!("设备锁：未指定短信验证码回调，请通过 `LoginOptions::sms_handler` 设置");
+            }
+            let Some(phone) = sms_phone else {
+                bail!("设备锁：账号未绑定手机号，无法使用短信验证");
+            };
+            tracing::info!("设备锁：向 {} 发送短信验证码", phone);
+            state.sms_sent = Some(Some(phone.clone()));
+            let resp = client.request_sms().await?;
+            emit(
+                options.events.as_ref(),
+                LoginEvent::SmsSent { phone: Some(phone) },
+            )
+            .await;
+            Ok(Some(resp))
+        }
+        DeviceLockStrategy::WebVerify => {
+            let Some(url) = verify_url else {
+                bail!("设备锁：{}\n未获取到验证链接", message.unwrap_or_default());
+            };
+            if options.web_verify_handler.is_some() {
+                call_blocking(&mut options.web_verify_handler, url).await?;
+            } else {
+                wait_web_verify(&url).await?;
+            }
+            tracing::info!("设备锁：网页验证完成，重新登录");
+            Ok(None)
+        }
+        DeviceLockStrategy::Abort => bail!(
+            "设备锁：{}\n请前往 {} 解锁",
+            message.unwrap_or_default(),
+            verify_url.unwrap_or_default()
+        ),
+    }
+}
+
+/// 向用户询问短信验证码并提交。
+///
+/// 验证码错误时，服务器返回未知状态，调用方应再次调用此方法要求重新输入。
+pub(crate) async fn resubmit_sms_code(
+    client: &ricq::Client,
+    options: &mut LoginOptions,
+    state: &mut DeviceLockState,
+) -> Result<LoginResponse> {
+    if options.sms_handler.is_none() {
+        bail!("设备锁：未指定短信验证码回调，请通过 `LoginOptions::sms_handler` 设置");
+    }
+    if state.sms_attempts >= MAX_SMS_ATTEMPTS {
+        bail!("短信验证码错误次数过多，请稍后再试");
+    }
+    state.sms_attempts += 1;
+    let code = call_blocking(&mut options.sms_handler, state.sms_sent.clone().flatten()).await?;
+    Ok(client.submit_sms_code(code.trim()).await?)
+}
+
+/// 在阻塞线程中调用同步回调，回调读取标准输入等阻塞操作时不会卡住异步运行时。
+///
+/// 调用期间回调从 `slot` 中取出，调用结束后放回。
+async fn call_blocking<F, A, R>(slot: &mut Option<Box<F>>, arg: A) -> Result<R>
+where
+    F: FnMut(A) -> Result<R> + Send + ?Sized + 'static,
+    A: Send + 'static,
+    R: Send + 'static,
+{
+    let Some(mut handler) = slot.take() else {
+        bail!("回调在上一次调用中 panic，已不可用");
+    };
+    let (handler, result) = tokio::task::spawn_blocking(move || {
+        let result = handler(arg);
+        (handler, result)
+    })
+    .await?;
+    *slot = Some(handler);
+    result
+}
+
+/// 在控制台打印验证链接，等待用户按下回车。
+async fn wait_web_verify(url: &str) -> Result<()> {
+    tracing::info!("设备锁：请前往 {} 完成验证，完成后按回车继续", url);
+    let mut reader = FramedRead::new(tokio::io::stdin(), LinesCodec::new());
+    match reader.next().await {
+        Some(line) => {
+            line?;
+            Ok(())
+        }
+        None => bail!("设备锁：等待网页验证时标准输入已关闭"),
+    }
+}
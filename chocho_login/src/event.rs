@@ -0,0 +1,52 @@
+//! 登录进度事件。
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+/// 登录进度事件。
+///
+/// 通过 [`LoginOptions::events`](crate::LoginOptions::events) 订阅。
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum LoginEvent {
+    /// 获取到二维码图片（PNG 格式）。二维码过期后重新获取时也会发送此事件。
+    QrCodeFetched {
+        /// 二维码图片。
+        image: Bytes,
+    },
+    /// 二维码已过期。
+    QrCodeExpired,
+    /// 等待扫描二维码。
+    WaitingForScan,
+    /// 二维码已扫描，等待确认。
+    WaitingForConfirm,
+    /// 需要完成滑块验证。
+    NeedCaptcha {
+        /// 滑块验证链接。
+        url: String,
+    },
+    /// 遇到设备锁。
+    DeviceLocked {
+        /// 解锁链接。
+        url: Option<String>,
+        /// 绑定的手机号。
+        phone: Option<String>,
+    },
+    /// 已发送短信验证码。
+    SmsSent {
+        /// 接收验证码的手机号。
+        phone: Option<String>,
+    },
+    /// 登录成功。
+    Success {
+        /// 登录的 QQ 号。
+        uin: i64,
+    },
+}
+
+/// 发送登录进度事件。接收端已关闭时忽略。
+pub(crate) async fn emit(events: Option<&mpsc::Sender<LoginEvent>>, event: LoginEvent) {
+    if let Some(events) = events {
+        let _ = events.send(event).await;
+    }
+}
@@ -0,0 +1,16 @@
+//! 文件读写辅助函数。
+
+use std::path::Path;
+
+use anyhow::Result;
+
+/// 原子地写入文件。
+///
+/// 在阻塞线程中调用 [`chocho_client::utils::atomic_write`]，不阻塞异步任务。
+pub(crate) async fn atomic_write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = path.as_ref().to_path_buf();
+    let contents = contents.as_ref().to_vec();
+    tokio::task::spawn_blocking(move || chocho_client::utils::atomic_write(path, contents))
+        .await??;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! 心跳检测。
+
+use std::time::Duration;
+
+use ricq::Client;
+
+use crate::status::ConnectionState;
+
+/// 心跳检测配置。
+///
+/// ricq 登录后会以固定的 30 秒间隔发送心跳，但心跳失败时不会断开连接，只有 TCP 连接断开后才会触发重连。
+/// 在某些网络环境下，连接可能已经失效但长时间没有断开，造成假性掉线。
+///
+/// 设置 [`LoginOptions::heartbeat`](crate::LoginOptions::heartbeat) 后，chocho 会按配置的间隔额外发送心跳，
+/// 连续失败次数达到阈值时主动断开连接，由 [`AliveHandle`](crate::AliveHandle) 进行重连。
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use chocho_login::{HeartbeatConfig, LoginOptions};
+///
+/// let options = LoginOptions::new().heartbeat(HeartbeatConfig {
+///     interval: Duration::from_secs(60),
+///     max_failures: 5,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatConfig {
+    /// 心跳间隔，默认为 30 秒。
+    pub interval: Duration,
+    /// 单次心跳的超时时间，默认为 10 秒。
+    pub timeout: Duration,
+    /// 连续失败多少次后判定掉线，默认为 3 次。
+    pub max_failures: u32,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            max_failures: 3,
+        }
+    }
+}
+
+/// 持续发送心跳，直到连续失败次数达到阈值。心跳成功时记录在 `state` 中。
+pub(crate) async fn watch(client: &Client, config: &HeartbeatConfig, state: &ConnectionState) {
+    let mut failures = 0;
+    loop {
+        tokio::time::sleep(config.interval).await;
+        match tokio::time::timeout(config.timeout, client.heartbeat()).await {
+            Ok(Ok(())) => {
+                failures = 0;
+                state.heartbeat_succeeded();
+            }
+            Ok(Err(e)) => {
+                failures += 1;
+                tracing::warn!("心跳失败（{}/{}）：{}", failures, config.max_failures, e);
+            }
+            Err(_) => {
+                failures += 1;
+                tracing::warn!("心跳超时（{}/{}）", failures, config.max_failures);
+            }
+        }
+        if failures >= config.max_failures.max(1) {
+            return;
+        }
+    }
+}
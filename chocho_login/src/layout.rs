@@ -0,0 +1,98 @@
+//! 数据目录结构。
+//!
+//! 默认情况下，每个账号的数据保存在 `{data_folder}/{uin}/` 下，包括 `device.json`、`token.json` 和 `scopes.json`。
+//! 实现 [`DataLayout`] 并通过 [`LoginOptions::data_layout`](crate::LoginOptions::data_layout)
+//! 传入，可以适配其它的目录约定。
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use chocho_login::{layout::DataLayout, LoginOptions};
+//!
+//! /// 将所有账号的文件放在同一个目录中，以 QQ 号作为文件名前缀。
+//! struct FlatLayout(PathBuf);
+//!
+//! impl DataLayout for FlatLayout {
+//!     fn device_path(&self, uin: i64) -> PathBuf {
+//!         self.0.join(format!("{}.device.json", uin))
+//!     }
+//!
+//!     fn token_path(&self, uin: i64) -> PathBuf {
+//!         self.0.join(format!("{}.token.json", uin))
+//!     }
+//! }
+//!
+//! let options = LoginOptions::new().data_layout(FlatLayout("./accounts".into()));
+//! ```
+
+use std::path::PathBuf;
+
+/// 数据目录结构，决定各个数据文件的路径。
+pub trait DataLayout: Send + Sync {
+    /// 账号的 `device.json` 路径。
+    fn device_path(&self, uin: i64) -> PathBuf;
+
+    /// 账号的 token 文件路径，用于 [`FileTokenStore`](crate::token::FileTokenStore)。
+    fn token_path(&self, uin: i64) -> PathBuf;
+
+    /// 账号的消息处理开关文件路径，参见 `chocho_event::ScopeExt::persist_scopes`。
+    ///
+    /// 默认与 `device.json` 位于同一目录，文件名为 `scopes.json`。
+    fn scopes_path(&self, uin: i64) -> PathBuf {
+        self.device_path(uin).with_file_name("scopes.json")
+    }
+
+    /// 账号专用的数据目录，目录中只包含此账号的文件。
+    ///
+    /// 导出[会话快照](crate::session)时，存在账号目录则打包整个目录，否则只打包 `device.json`、
+    /// token 文件和消息处理开关文件。默认返回 `None`。
+    fn account_dir(&self, uin: i64) -> Option<PathBuf> {
+        let _ = uin;
+        None
+    }
+}
+
+/// 默认的数据目录结构。
+///
+/// 每个账号的文件保存在账号目录 `{data_folder}/{uin}/` 下。
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use chocho_login::layout::{DataLayout, DefaultLayout};
+///
+/// let layout = DefaultLayout::new("./data");
+/// assert_eq!(layout.device_path(123456789), Path::new("./data/123456789/device.json"));
+/// assert_eq!(layout.token_path(123456789), Path::new("./data/123456789/token.json"));
+/// assert_eq!(layout.scopes_path(123456789), Path::new("./data/123456789/scopes.json"));
+/// assert_eq!(layout.account_dir(123456789).unwrap(), Path::new("./data/123456789"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DefaultLayout {
+    data_folder: PathBuf,
+}
+
+impl DefaultLayout {
+    /// 创建默认的数据目录结构。
+    pub fn new(data_folder: impl Into<PathBuf>) -> Self {
+        Self {
+            data_folder: data_folder.into(),
+        }
+    }
+}
+
+impl DataLayout for DefaultLayout {
+    fn device_path(&self, uin: i64) -> PathBuf {
+        self.data_folder.join(uin.to_string()).join("device.json")
+    }
+
+    fn token_path(&self, uin: i64) -> PathBuf {
+        self.data_folder.join(uin.to_string()).join("token.json")
+    }
+
+    fn account_dir(&self, uin: i64) -> Option<PathBuf> {
+        Some(self.data_folder.join(uin.to_string()))
+    }
+}
@@ -27,26 +27,58 @@
 //! ## Feature flags
 //!
 //! - `show-qrcode`: 在控制台显示二维码。
+//! - `captcha-server`: 启动本地 HTTP 服务器接收滑块验证的 ticket。
+//! - `qsign-failover`: 启动本地代理，在多个 qsign 服务器之间故障转移，参见 [`MultiQSignClient`]。
 //! - `interactive`: 交互式登录。
+//! - `secure-store`: 加密和压缩保存的 token 文件，参见 [`FileTokenStore`](token::FileTokenStore)。
 
 #![deny(missing_docs)]
 #![feature(never_type)]
 #![feature(try_blocks)]
 
 use anyhow::Result;
-use login::reconnect;
-use ricq::{handler::Handler, Client};
-use std::{path::PathBuf, sync::Arc};
+use connector::{BoxedConnector, Connection};
+use heartbeat::HeartbeatConfig;
+use login::{reconnect, save_token};
+use ricq::{client::NetworkStatus, handler::Handler, Client};
+use status::ConnectionState;
+use std::{sync::Arc, time::Duration};
+use token::TokenStore;
 
 use tokio::task::JoinHandle;
 
+pub mod captcha;
+mod connector;
+mod credential;
 pub mod device;
+mod device_lock;
+pub mod event;
+mod fs;
+mod heartbeat;
+pub mod layout;
 mod login;
+mod manager;
+mod offline;
 pub mod password;
+mod protocol;
 pub mod qrcode;
+mod qsign;
+#[cfg(feature = "secure-store")]
+mod secure;
+pub mod session;
+mod status;
+pub mod token;
 
+pub use crate::credential::{login_with_credential, login_with_device, Credential, LoginOptions};
+pub use crate::device_lock::DeviceLockStrategy;
+pub use crate::heartbeat::HeartbeatConfig;
+pub use crate::manager::AccountManager;
+pub use crate::offline::OfflineReason;
 pub use crate::password::login_with_password;
+pub use crate::protocol::{ParseProtocolError, ProtocolName};
 pub use crate::qrcode::login_with_qrcode;
+pub use crate::qsign::MultiQSignClient;
+pub use crate::status::{ConnectionMonitor, ConnectionStats, ConnectionStatus};
 pub use ricq::qsign::QSignClient;
 
 /// 协议。
@@ -65,25 +97,120 @@ pub use ricq::Protocol as RQProtocol;
 /// `AliveHandle` 结构体提供了登录保持的功能，包括等待连接断开、断线重连和自动断线重连。
 pub struct AliveHandle {
     client: Arc<ricq::Client>,
-    account_data_folder: PathBuf,
+    uin: i64,
+    token_store: Arc<dyn TokenStore>,
+    connector: BoxedConnector,
     alive: Option<JoinHandle<()>>,
+    on_offline: Option<Box<dyn Fn(OfflineReason) + Send + Sync>>,
+    state: Arc<ConnectionState>,
+    heartbeat: Option<HeartbeatConfig>,
+    token_save_interval: Duration,
 }
 
 impl AliveHandle {
     pub(crate) fn new(
         client: Arc<ricq::Client>,
-        account_data_folder: PathBuf,
-        alive: JoinHandle<()>,
+        uin: i64,
+        token_store: Arc<dyn TokenStore>,
+        connector: BoxedConnector,
+        connection: Connection,
     ) -> Self {
         Self {
             client,
-            account_data_folder,
-            alive: Some(alive),
+            uin,
+            token_store,
+            connector,
+            alive: Some(connection.alive),
+            on_offline: None,
+            state: Arc::new(ConnectionState::new(connection.server)),
+            heartbeat: None,
+            token_save_interval: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn with_heartbeat(mut self, heartbeat: Option<HeartbeatConfig>) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    pub(crate) fn with_token_save_interval(mut self, interval: Duration) -> Self {
+        self.token_save_interval = interval;
+        self
+    }
+
+    /// 注册掉线时的回调。
+    ///
+    /// 连接断开后、尝试重连前，以及主动下线时，会以掉线原因调用此回调。
+    /// 被挤下线或被服务器强制下线时不会自动重连，可以在回调中通知运营者。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho_login::OfflineReason;
+    ///
+    /// # async fn _f(alive: chocho_login::AliveHandle) -> anyhow::Result<()> {
+    /// let alive = alive.on_offline(|reason| match reason {
+    ///     OfflineReason::AnotherLogin => eprintln!("账号在其他设备登录，已下线"),
+    ///     OfflineReason::KickedByServer => eprintln!("被服务器强制下线"),
+    ///     _ => {}
+    /// });
+    /// alive.auto_reconnect().await?;
+    /// # }
+    /// ```
+    pub fn on_offline(
+        mut self,
+        on_offline: impl Fn(OfflineReason) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_offline = Some(Box::new(on_offline));
+        self
+    }
+
+    /// 获取底层的客户端。
+    ///
+    /// 返回的是客户端的 `Arc` 克隆，与 [`login`] 返回的客户端是同一个，可以多次调用。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn _f(alive: chocho_login::AliveHandle) -> anyhow::Result<()> {
+    /// let client = alive.client();
+    /// tokio::spawn(async move {
+    ///     client.send_group_message(123456789, Default::default()).await
+    /// });
+    /// alive.auto_reconnect().await?;
+    /// # }
+    /// ```
+    pub fn client(&self) -> Arc<Client> {
+        self.client.clone()
+    }
+
+    /// 当前的连接状态。
+    pub fn status(&self) -> ConnectionStatus {
+        self.monitor().status()
+    }
+
+    /// 连接统计信息，包括重连次数、最近一次连接成功和心跳成功的时间。
+    pub fn stats(&self) -> ConnectionStats {
+        self.monitor().stats()
+    }
+
+    /// 获取连接状态的监视器，参见 [`ConnectionMonitor`]。
+    pub fn monitor(&self) -> ConnectionMonitor {
+        ConnectionMonitor::new(self.client.clone(), self.state.clone())
+    }
+
+    fn notify_offline(&self, reason: OfflineReason) {
+        tracing::info!("客户端掉线，原因：{:?}", reason);
+        if let Some(on_offline) = &self.on_offline {
+            on_offline(reason);
         }
     }
 
     /// 等待，直到连接断开。
     ///
+    /// 登录时设置了 [`HeartbeatConfig`] 的，心跳连续失败达到阈值时会主动断开连接。
+    /// 等待期间会按 [`LoginOptions::token_save_interval`] 定期保存最新的 token。
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -94,12 +221,48 @@ impl AliveHandle {
     /// # }
     /// ```
     pub async fn alive(&mut self) -> Result<()> {
-        if let Some(alive) = self.alive.take() {
-            alive.await?;
+        let Some(mut alive) = self.alive.take() else {
+            return Ok(());
+        };
+        let heartbeat = async {
+            match self.heartbeat {
+                Some(config) => {
+                    heartbeat::watch(&self.client, &config, &self.state).await;
+                    config
+                }
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            result = &mut alive => result?,
+            config = heartbeat => {
+                tracing::error!("心跳连续失败 {} 次，主动断开连接", config.max_failures);
+                self.client.stop(NetworkStatus::NetworkOffline);
+                alive.await?;
+            }
+            _ = self.save_token_periodically() => {}
         }
         Ok(())
     }
 
+    /// 定期保存 token，间隔为 0 时不保存。此 future 不会结束。
+    async fn save_token_periodically(&self) {
+        if self.token_save_interval.is_zero() {
+            return std::future::pending().await;
+        }
+        let mut interval = tokio::time::interval(self.token_save_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // 第一次 tick 立即完成，此时刚刚登录或重连，已经保存过 token
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match save_token(&self.client, self.uin, &*self.token_store).await {
+                Ok(()) => tracing::debug!("已定期保存 token"),
+                Err(e) => tracing::warn!("定期保存 token 失败：{}", e),
+            }
+        }
+    }
+
     /// 断线重连。
     ///
     /// # Examples
@@ -115,9 +278,15 @@ impl AliveHandle {
     /// # }
     pub async fn reconnect(&mut self) -> Result<()> {
         if self.alive.is_none() {
+            self.notify_offline(OfflineReason::from_status(self.client.get_status()));
             // 断线重连
-            let handle = reconnect(&self.client, &self.account_data_folder).await?;
-            self.alive = Some(handle);
+            self.state.set_reconnecting(true);
+            let result =
+                reconnect(&self.client, self.uin, &*self.token_store, &self.connector).await;
+            self.state.set_reconnecting(false);
+            let connection = result?;
+            self.alive = Some(connection.alive);
+            self.state.reconnected(connection.server);
         }
         Ok(())
     }
@@ -154,6 +323,113 @@ impl AliveHandle {
             self.reconnect().await?;
         }
     }
+
+    /// 在后台任务中自动断线重连，返回可以停止任务的 [`AliveTask`]。
+    ///
+    /// 与 [`auto_reconnect`](AliveHandle::auto_reconnect) 相同，但不阻塞当前任务，适合嵌入到已有的事件循环中。
+    /// 掉线时以掉线原因调用 `on_offline`（在 [`on_offline`](AliveHandle::on_offline) 注册的回调之后），
+    /// 重连成功后调用 `on_reconnect`。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn _f(alive: chocho_login::AliveHandle) -> anyhow::Result<()> {
+    /// let task = alive.spawn_with_callbacks(
+    ///     |reason| eprintln!("掉线：{:?}", reason),
+    ///     || eprintln!("重连成功"),
+    /// );
+    /// // ... 运行应用的其它部分
+    /// task.abort();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_with_callbacks(
+        mut self,
+        on_offline: impl Fn(OfflineReason) + Send + Sync + 'static,
+        on_reconnect: impl Fn() + Send + Sync + 'static,
+    ) -> AliveTask {
+        let previous = self.on_offline.take();
+        self.on_offline = Some(Box::new(move |reason| {
+            if let Some(previous) = &previous {
+                previous(reason);
+            }
+            on_offline(reason);
+        }));
+        let client = self.client.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                self.alive().await?;
+                self.reconnect().await?;
+                on_reconnect();
+            }
+        });
+        AliveTask { client, handle }
+    }
+
+    /// 主动下线，停止心跳。
+    ///
+    /// 下线前会保存最新的 token，以便下次启动时直接登录。
+    /// 在已断线的状态下调用也是安全的。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn _f(alive: chocho_login::AliveHandle) -> anyhow::Result<()> {
+    /// alive.shutdown().await?;
+    /// println!("已下线");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn shutdown(mut self) -> Result<()> {
+        let saved = save_token(&self.client, self.uin, &*self.token_store).await;
+        self.client.stop(NetworkStatus::NetworkOffline);
+        if let Some(alive) = self.alive.take() {
+            if let Err(e) = alive.await {
+                tracing::warn!("心跳任务异常退出: {}", e);
+            }
+        }
+        self.notify_offline(OfflineReason::Manual);
+        saved
+    }
+}
+
+/// 后台运行的自动断线重连任务，由 [`AliveHandle::spawn_with_callbacks`] 创建。
+///
+/// 丢弃 `AliveTask` 不会停止任务，需要停止时请调用 [`abort`](AliveTask::abort)。
+pub struct AliveTask {
+    client: Arc<ricq::Client>,
+    handle: JoinHandle<Result<!>>,
+}
+
+impl AliveTask {
+    /// 获取底层的客户端。
+    pub fn client(&self) -> Arc<Client> {
+        self.client.clone()
+    }
+
+    /// 重连任务是否已经结束。
+    ///
+    /// 重连失败或任务被停止后返回 `true`。
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// 停止重连任务，并断开客户端的连接。
+    ///
+    /// 与 [`AliveHandle::shutdown`] 不同，此方法不会保存 token。
+    pub fn abort(&self) {
+        self.handle.abort();
+        self.client.stop(NetworkStatus::NetworkOffline);
+    }
+
+    /// 等待重连任务结束，返回导致任务结束的错误。
+    pub async fn join(self) -> Result<!> {
+        match self.handle.await {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => anyhow::bail!("重连任务已停止"),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
 /// 登录方式。
@@ -167,6 +443,46 @@ pub enum LoginMethod {
     },
     /// 二维码登录。
     QrCode,
+    /// 使用已有的 token 登录。
+    ///
+    /// `token` 为 JSON 格式，与数据文件夹中保存的 `token.json` 相同。
+    /// 在 `#[chocho::main]` 中可以通过 `login_method` 属性指定。
+    /// 此 token 优先于数据文件夹中保存的 token，参见 [`Credential::Token`]。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho_login::LoginMethod;
+    ///
+    /// # fn _f() -> Result<(), std::env::VarError> {
+    /// let method = LoginMethod::Token {
+    ///     token: std::env::var("CHOCHO_TOKEN")?,
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    Token {
+        /// JSON 格式的 token。
+        token: String,
+    },
+}
+
+impl LoginMethod {
+    /// 转换为登录凭据和对应的登录选项。
+    pub(crate) fn into_credential(self, uin: i64) -> Result<(Credential, LoginOptions)> {
+        Ok(match self {
+            LoginMethod::Password { protocol, password } => (
+                Credential::Password { uin, password },
+                LoginOptions::new().protocol(protocol),
+            ),
+            LoginMethod::QrCode => (Credential::QrCode { uin }, LoginOptions::new()),
+            LoginMethod::Token { token } => {
+                let token = serde_json::from_str(&token)
+                    .map_err(|e| anyhow::anyhow!("token 无效，无法解析：{}", e))?;
+                (Credential::Token { uin, token }, LoginOptions::new())
+            }
+        })
+    }
 }
 
 #[cfg(feature = "interactive")]
@@ -207,6 +523,7 @@ pub async fn login(
             .message("请选择登录方式：")
             .choice("密码登录")
             .choice("二维码登录")
+            .choice("Token 登录")
             .build();
         let login_method = requestty::prompt_one(login_method)?
             .as_list_item()
@@ -249,26 +566,41 @@ pub async fn login(
                 // 二维码登录
                 LoginMethod::QrCode
             }
+            2 => {
+                // Token 登录
+                let token = Question::input("token")
+                    .message("请输入 token（JSON 格式）")
+                    .build();
+                let token = requestty::prompt_one(token)?.try_into_string().unwrap();
+                LoginMethod::Token { token }
+            }
             _ => unreachable!(),
         }
     };
 
-    match login_method {
-        LoginMethod::Password { protocol, password } => {
-            login_with_password(uin, &password, protocol, data_folder, qsign_client, handler).await
-        }
-        LoginMethod::QrCode => {
-            login_with_qrcode(
-                uin,
-                |img| {
-                    println!("{}", qrcode::qrcode_text(&img)?);
-                    Ok(())
-                },
-                data_folder,
-                qsign_client,
-                handler,
-            )
-            .await
-        }
-    }
+    let (credential, options) = match login_method {
+        LoginMethod::Password { protocol, password } => (
+            Credential::Password { uin, password },
+            LoginOptions::new()
+                .protocol(protocol)
+                .sms_handler(|sms_phone| {
+                    let sms_code = Question::input("sms_code")
+                        .message(format!(
+                            "请输入发送到 {} 的短信验证码",
+                            sms_phone.unwrap_or_default()
+                        ))
+                        .build();
+                    Ok(requestty::prompt_one(sms_code)?.try_into_string().unwrap())
+                }),
+        ),
+        LoginMethod::QrCode => (
+            Credential::QrCode { uin },
+            LoginOptions::new().show_qrcode(|img| {
+                println!("{}", qrcode::qrcode_text(&img)?);
+                Ok(())
+            }),
+        ),
+        method @ LoginMethod::Token { .. } => method.into_credential(uin)?,
+    };
+    login_with_credential(credential, data_folder, handler, qsign_client, options).await
 }
@@ -1,60 +1,110 @@
 //! 登录。
 
 use std::future::Future;
-use std::{path::Path, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::{bail, Result};
 use ricq::handler::Handler;
 use ricq::qsign::QSignClient;
 use ricq::Protocol;
 use ricq::{
-    client::{Client, Connector, DefaultConnector, NetworkStatus, Token},
+    client::{Client, NetworkStatus, Token},
     ext::{common::after_login, reconnect::fast_login},
-    version::get_version,
-    Device, LoginResponse, LoginSuccess,
+    version::{get_version, Version},
+    Device, LoginResponse, LoginSuccess, RQError,
 };
-use tokio::task::JoinHandle;
 
+use crate::connector::{connect, BoxedConnector, Connection};
+use crate::device;
+use crate::fs::atomic_write;
+use crate::heartbeat::HeartbeatConfig;
+use crate::layout::DataLayout;
+use crate::token::TokenStore;
 use crate::AliveHandle;
 
+/// 登录过程中与登录方式无关的配置。
+pub(crate) struct LoginContext {
+    pub(crate) layout: Arc<dyn DataLayout>,
+    pub(crate) token_store: Arc<dyn TokenStore>,
+    pub(crate) connector: BoxedConnector,
+    pub(crate) validate_device: bool,
+    pub(crate) heartbeat: Option<HeartbeatConfig>,
+    pub(crate) token_save_interval: Duration,
+    pub(crate) version: Option<Version>,
+    pub(crate) device: Option<Device>,
+    /// 是否先尝试使用保存的 token 登录。
+    pub(crate) use_stored_token: bool,
+}
+
 pub(crate) async fn login_impl<Fut>(
     uin: i64,
     protocol: Protocol,
-    data_folder: impl AsRef<Path>,
     qsign_client: Arc<QSignClient>,
     handler: impl Handler + 'static + Send,
+    context: LoginContext,
     login_with_credential: impl FnOnce(Arc<ricq::Client>) -> Fut,
 ) -> Result<(Arc<Client>, AliveHandle)>
 where
     Fut: Future<Output = Result<()>>,
 {
-    // 创建数据文件夹
-    let account_data_folder = data_folder.as_ref().join(uin.to_string());
-    tokio::fs::create_dir_all(&account_data_folder).await?;
+    let LoginContext {
+        layout,
+        token_store,
+        connector,
+        validate_device,
+        heartbeat,
+        token_save_interval,
+        version,
+        device,
+        use_stored_token,
+    } = context;
 
-    let device = load_device_json(uin, &account_data_folder).await?;
-    let (client, alive) = prepare_client(device, protocol, qsign_client, handler).await?;
+    // 指定了设备信息时不读写 `device.json`
+    let (mut device, device_json) = match device {
+        Some(device) => (device, None),
+        None => {
+            let device_json = layout.device_path(uin);
+            let device = load_device_json(uin, device_json.clone()).await?;
+            (device, Some(device_json))
+        }
+    };
+    if device.qimei.is_some() && device::get_qimei(&device).is_none() {
+        tracing::warn!("device.json 中缓存的 qimei 已失效，将重新申请");
+        device::set_qimei(&mut device, None);
+    }
+    if validate_device {
+        if let Err(errors) = device::validate(&device) {
+            for error in errors {
+                tracing::warn!("device.json 校验失败：{}", error);
+            }
+        }
+    }
+    let version = version.unwrap_or_else(|| get_version(protocol));
+    let (client, connection) =
+        prepare_client(device.clone(), version, qsign_client, handler, &connector).await?;
 
     // 尝试 token 登录
-    if !try_token_login(&client, &account_data_folder).await? {
+    if !use_stored_token || !try_token_login(&client, uin, &*token_store).await? {
         login_with_credential(client.clone()).await?;
     }
 
     // 注册客户端，启动心跳。
     after_login(&client).await;
-    save_token(&client, &account_data_folder).await?;
+    save_token(&client, uin, &*token_store).await?;
+    if let Some(device_json) = device_json {
+        if let Err(e) = save_qimei(&client, device, device_json).await {
+            tracing::warn!("保存 qimei 失败：{}", e);
+        }
+    }
 
-    let alive = AliveHandle::new(client.clone(), account_data_folder, alive);
+    let alive = AliveHandle::new(client.clone(), uin, token_store, connector, connection)
+        .with_heartbeat(heartbeat)
+        .with_token_save_interval(token_save_interval);
     Ok((client, alive))
 }
 
 /// 加载 `device.json`。
-async fn load_device_json(uin: i64, data_folder: impl AsRef<Path>) -> Result<Device> {
-    use crate::device;
-
-    // 获取 `device.json` 的路径
-    let device_json = data_folder.as_ref().join("device.json");
-
+async fn load_device_json(uin: i64, device_json: PathBuf) -> Result<Device> {
     // 解析设备信息
     let device = if device_json.exists() {
         // 尝试读取已有的 `device.json`
@@ -62,85 +112,112 @@ async fn load_device_json(uin: i64, data_folder: impl AsRef<Path>) -> Result<Dev
         device::from_json(&json, &device::random_from_uin(uin))?
     } else {
         // 否则，生成一个新的 `device.json` 并保存到文件中
+        if let Some(parent) = device_json.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
         let device = device::random_from_uin(uin);
         let json = device::to_json(&device)?;
-        tokio::fs::write(device_json, json).await?;
+        atomic_write(device_json, json).await?;
         device
     };
 
     Ok(device)
 }
 
+/// 服务器返回的 qimei 与缓存不同时，写回 `device.json`。
+async fn save_qimei(client: &ricq::Client, mut device: Device, device_json: PathBuf) -> Result<()> {
+    let qimei = client.engine.read().await.transport.device.qimei.clone();
+    let Some(qimei) = qimei.filter(device::is_valid_qimei) else {
+        return Ok(());
+    };
+    if let Some(cached) = &device.qimei {
+        if cached.q16 == qimei.q16 && cached.q36 == qimei.q36 {
+            return Ok(());
+        }
+    }
+    tracing::debug!("qimei 已更新：{}", qimei.q16);
+    device::set_qimei(&mut device, Some(qimei));
+    atomic_write(device_json, device::to_json(&device)?).await
+}
+
 /// 创建客户端，准备登录。
 async fn prepare_client(
     device: Device,
-    protocol: Protocol,
+    version: Version,
     qsign_client: Arc<QSignClient>,
     handler: impl Handler + 'static + Send,
-) -> tokio::io::Result<(Arc<ricq::Client>, JoinHandle<()>)> {
-    let client = Arc::new(ricq::Client::new(
-        device,
-        get_version(protocol),
-        qsign_client,
-        handler,
-    ));
-    let alive = tokio::spawn({
-        let client = client.clone();
-        // 连接最快的服务器
-        let stream = DefaultConnector.connect(&client).await?;
-        async move { client.start(stream).await }
-    });
-
-    tokio::task::yield_now().await; // 等一下，确保连上了
-    Ok((client, alive))
+    connector: &BoxedConnector,
+) -> Result<(Arc<ricq::Client>, Connection)> {
+    let client = Arc::new(ricq::Client::new(device, version, qsign_client, handler));
+    let connection = connect(connector, &client).await?;
+    Ok((client, connection))
 }
 
 /// 尝试使用 token 登录。
+///
+/// 服务器明确拒绝 token 时删除 token，返回 `Ok(false)`，回退到凭据登录。
+/// 其它错误时保留 token，返回以原始错误为源的错误：网络错误或超时可以稍后重试，
+/// 其余错误不一定说明 token 已失效，不应删除 token。
 async fn try_token_login(
     client: &ricq::Client,
-    account_data_folder: impl AsRef<Path>,
+    uin: i64,
+    token_store: &dyn TokenStore,
 ) -> Result<bool> {
-    let token_path = account_data_folder.as_ref().join("token.json");
-
-    if !token_path.exists() {
+    let Some(token) = token_store.load(uin).await? else {
         return Ok(false);
-    }
+    };
     tracing::info!("发现上一次登录的 token，尝试使用 token 登录");
-    let token = tokio::fs::read_to_string(&token_path).await?;
-    let token: Token = serde_json::from_str(&token)?;
     match client.token_login(token).await {
-        Ok(login_resp) => {
-            if let LoginResponse::Success(LoginSuccess {
-                ref account_info, ..
-            }) = login_resp
-            {
-                tracing::info!("登录成功: {:?}", account_info);
-                return Ok(true);
-            }
-            bail!("登录失败: {:?}", login_resp)
+        Ok(LoginResponse::Success(LoginSuccess { account_info, .. })) => {
+            tracing::info!("登录成功: {:?}", account_info);
+            Ok(true)
         }
-        Err(_) => {
-            tracing::info!("token 登录失败，将删除 token");
-            tokio::fs::remove_file(token_path).await?;
+        Ok(LoginResponse::UnknownStatus(status)) => {
+            tracing::info!("token 已失效（{:?}），将删除 token", status);
+            token_store.remove(uin).await?;
             Ok(false)
         }
+        Ok(login_resp) => bail!("登录失败: {:?}", login_resp),
+        Err(e @ (RQError::Network | RQError::Timeout | RQError::IO(_))) => {
+            Err(anyhow::Error::new(e).context("token 登录时发生网络错误，已保留 token，请稍后重试"))
+        }
+        Err(e) => Err(anyhow::Error::new(e).context("token 登录失败，已保留 token")),
+    }
+}
+
+/// 使用给定的 token 登录。
+pub(crate) async fn token_login(client: &ricq::Client, token: Token) -> Result<()> {
+    let login_resp = client
+        .token_login(token)
+        .await
+        .map_err(|e| anyhow::Error::new(e).context("token 登录失败"))?;
+    match login_resp {
+        LoginResponse::Success(LoginSuccess { account_info, .. }) => {
+            tracing::info!("登录成功: {:?}", account_info);
+            Ok(())
+        }
+        LoginResponse::UnknownStatus(status) => bail!("token 无效或已过期: {:?}", status),
+        login_resp => bail!("登录失败: {:?}", login_resp),
     }
 }
 
 /// 保存 Token，用于断线重连。
-async fn save_token(client: &ricq::Client, account_data_folder: impl AsRef<Path>) -> Result<()> {
+pub(crate) async fn save_token(
+    client: &ricq::Client,
+    uin: i64,
+    token_store: &dyn TokenStore,
+) -> Result<()> {
     let token = client.gen_token().await;
-    let token = serde_json::to_string(&token)?;
-    let token_path = account_data_folder.as_ref().join("token.json");
-    tokio::fs::write(token_path, token).await?;
-    Ok(())
+    token_store.save(uin, &token).await
 }
 
 /// 断线重连。
 pub(crate) async fn reconnect(
     client: &Arc<ricq::Client>,
-    account_data_folder: &Path,
-) -> Result<JoinHandle<()>> {
+    uin: i64,
+    token_store: &dyn TokenStore,
+    connector: &BoxedConnector,
+) -> Result<Connection> {
     let mut retry_count = 10;
     loop {
         match try {
@@ -152,24 +229,15 @@ pub(crate) async fn reconnect(
             tracing::error!("客户端连接中断，将在 10 秒后重连");
             tokio::time::sleep(std::time::Duration::from_secs(10)).await;
 
-            let alive = tokio::spawn({
-                let client = client.clone();
-                // 连接最快的服务器
-                let stream = DefaultConnector.connect(&client).await?;
-                async move { client.start(stream).await }
-            });
-            tokio::task::yield_now().await; // 等一下，确保连上了
+            // 复用登录时的连接器
+            let connection = connect(connector, client).await?;
 
             // 启动接收后，再发送登录请求，否则报错 NetworkError
-            let token_path = account_data_folder.join("token.json");
-            if !token_path.exists() {
-                bail!("重连失败：无法找到上次登录的 token");
-            }
-            let token = tokio::fs::read_to_string(token_path).await?;
-            let token = match serde_json::from_str(&token) {
-                Ok(token) => token,
+            let token = match token_store.load(uin).await {
+                Ok(Some(token)) => token,
+                Ok(None) => bail!("重连失败：无法找到上次登录的 token"),
                 Err(err) => {
-                    bail!("重连失败：无法解析上次登录的 token: {}", err)
+                    bail!("重连失败：无法读取上次登录的 token: {}", err)
                 }
             };
             fast_login(client, &ricq::ext::reconnect::Credential::Token(token))
@@ -183,7 +251,7 @@ pub(crate) async fn reconnect(
 
             tracing::info!("客户端重连成功");
 
-            alive
+            connection
         } {
             Ok(t) => return Ok(t),
             Err(e) => {
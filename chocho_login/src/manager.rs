@@ -0,0 +1,138 @@
+//! 多账号管理。
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use ricq::{handler::Handler, qsign::QSignClient, Client};
+use tokio::task::JoinSet;
+
+use crate::{login_with_credential, AliveHandle, Credential, LoginMethod, LoginOptions};
+
+type HandlerFactory<H> = Box<dyn Fn(i64) -> H + Send + Sync>;
+
+/// 多账号管理器。
+///
+/// 在同一进程内登录和管理多个账号，每个账号持有独立的客户端和 [`AliveHandle`]。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{time::Duration, sync::Arc};
+/// use chocho_login::{AccountManager, LoginMethod, QSignClient};
+/// use ricq::handler::DefaultHandler;
+///
+/// # async fn _f() -> anyhow::Result<()> {
+/// let qsign_client = Arc::new(QSignClient::new(
+///     "http://localhost:5000".to_string(),
+///     "114514".to_string(),
+///     Duration::from_secs(60),
+/// )?);
+/// let mut manager = AccountManager::new("./data", qsign_client, |_uin| DefaultHandler);
+/// manager.add_account(123456789, LoginMethod::QrCode).await?;
+/// manager.add_account(987654321, LoginMethod::QrCode).await?;
+/// manager.auto_reconnect_all().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AccountManager<H> {
+    data_folder: PathBuf,
+    qsign_client: Arc<QSignClient>,
+    handler: HandlerFactory<H>,
+    accounts: HashMap<i64, (Arc<Client>, AliveHandle)>,
+}
+
+impl<H> AccountManager<H>
+where
+    H: Handler + Send + 'static,
+{
+    /// 创建多账号管理器。
+    ///
+    /// # Arguments
+    ///
+    /// * `data_folder` - 数据文件夹，所有账号共用。
+    /// * `qsign_client` - qsign 客户端，所有账号共用。
+    /// * `handler` - 根据 QQ 号创建事件处理器的函数。
+    pub fn new(
+        data_folder: impl AsRef<Path>,
+        qsign_client: Arc<QSignClient>,
+        handler: impl Fn(i64) -> H + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            data_folder: data_folder.as_ref().to_path_buf(),
+            qsign_client,
+            handler: Box::new(handler),
+            accounts: HashMap::new(),
+        }
+    }
+
+    /// 登录并添加账号。
+    pub async fn add_account(&mut self, uin: i64, method: LoginMethod) -> Result<Arc<Client>> {
+        let (credential, options) = method.into_credential(uin)?;
+        self.add_account_with_options(credential, options).await
+    }
+
+    /// 使用指定的凭据和登录选项登录并添加账号。
+    pub async fn add_account_with_options(
+        &mut self,
+        credential: Credential,
+        options: LoginOptions,
+    ) -> Result<Arc<Client>> {
+        let uin = credential.uin();
+        if self.accounts.contains_key(&uin) {
+            bail!("账号 {} 已登录", uin);
+        }
+        let (client, alive) = login_with_credential(
+            credential,
+            &self.data_folder,
+            (self.handler)(uin),
+            self.qsign_client.clone(),
+            options,
+        )
+        .await?;
+        self.accounts.insert(uin, (client.clone(), alive));
+        Ok(client)
+    }
+
+    /// 下线并移除账号。
+    pub async fn remove_account(&mut self, uin: i64) -> Result<()> {
+        match self.accounts.remove(&uin) {
+            Some((_, alive)) => alive.shutdown().await,
+            None => bail!("账号 {} 未登录", uin),
+        }
+    }
+
+    /// 获取账号的客户端。
+    pub fn client(&self, uin: i64) -> Option<Arc<Client>> {
+        self.accounts.get(&uin).map(|(client, _)| client.clone())
+    }
+
+    /// 所有已登录的账号。
+    pub fn uins(&self) -> impl Iterator<Item = i64> + '_ {
+        self.accounts.keys().copied()
+    }
+
+    /// 对所有账号开始自动断线重连。
+    ///
+    /// 某个账号重连失败时，记录错误并放弃该账号，不影响其它账号。所有账号都停止后返回。
+    pub async fn auto_reconnect_all(self) {
+        let mut tasks = JoinSet::new();
+        for (uin, (_, alive)) in self.accounts {
+            tasks.spawn(async move {
+                match alive.auto_reconnect().await {
+                    Ok(never) => never,
+                    Err(e) => (uin, e),
+                }
+            });
+        }
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok((uin, e)) => tracing::error!("账号 {} 已停止运行: {}", uin, e),
+                Err(e) => tracing::error!("账号的重连任务异常退出: {}", e),
+            }
+        }
+    }
+}
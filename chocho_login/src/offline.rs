@@ -0,0 +1,41 @@
+//! 掉线原因。
+
+use ricq::client::NetworkStatus;
+
+/// 掉线原因。
+///
+/// 通过 [`AliveHandle::on_offline`](crate::AliveHandle::on_offline) 获取。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OfflineReason {
+    /// 网络连接中断，将自动重连。
+    NetworkError,
+    /// 被服务器强制下线。
+    KickedByServer,
+    /// 账号在其他设备登录。
+    AnotherLogin,
+    /// 主动下线。
+    Manual,
+    /// 其它原因，包含 ricq 的原始状态码。
+    Unknown(u8),
+}
+
+impl OfflineReason {
+    /// 根据客户端的网络状态判断掉线原因。
+    pub fn from_status(status: u8) -> Self {
+        match status {
+            s if s == NetworkStatus::NetworkOffline as u8 => OfflineReason::NetworkError,
+            s if s == NetworkStatus::MsfOffline as u8 => OfflineReason::KickedByServer,
+            s if s == NetworkStatus::KickedOffline as u8 => OfflineReason::AnotherLogin,
+            s if s == NetworkStatus::Stop as u8 || s == NetworkStatus::Drop as u8 => {
+                OfflineReason::Manual
+            }
+            s => OfflineReason::Unknown(s),
+        }
+    }
+
+    /// 是否会自动重连。
+    pub fn is_network_error(&self) -> bool {
+        matches!(self, OfflineReason::NetworkError)
+    }
+}
@@ -1,21 +1,21 @@
 //! 密码登录。
 
-use std::{path::Path, sync::Arc};
+use std::{path::Path, sync::Arc, time::Duration};
 
 use anyhow::{bail, Result};
-use futures_util::StreamExt;
 use ricq::qsign::QSignClient;
-use ricq::{
-    handler::Handler, Client, LoginDeviceLocked, LoginNeedCaptcha, LoginResponse, LoginSuccess,
-    Protocol,
-};
-use tokio_util::codec::{FramedRead, LinesCodec};
+use ricq::{handler::Handler, Client, LoginNeedCaptcha, LoginResponse, LoginSuccess, Protocol};
 
-use crate::login::login_impl;
+use crate::captcha::{CaptchaSolver, StdinCaptchaSolver};
+use crate::credential::{login_with_credential, Credential, LoginOptions};
+use crate::device_lock::{handle_device_locked, resubmit_sms_code, DeviceLockState};
+use crate::event::{emit, LoginEvent};
 use crate::AliveHandle;
 
 /// 使用密码登录。
 ///
+/// 如需指定连接器（例如通过代理连接）等更多选项，请使用 [`login_with_credential`] 和 [`LoginOptions`]。
+///
 /// # Arguments
 ///
 /// * `uin` - QQ 号。
@@ -62,13 +62,15 @@ pub async fn login_with_password(
     qsign_client: Arc<QSignClient>,
     handler: impl Handler + 'static + Send,
 ) -> Result<(Arc<Client>, AliveHandle)> {
-    login_impl(
-        uin,
-        protocol,
+    login_with_credential(
+        Credential::Password {
+            uin,
+            password: password.to_string(),
+        },
         data_folder,
-        qsign_client,
         handler,
-        move |client| async move { password_login(&client, uin, password).await },
+        qsign_client,
+        LoginOptions::new().protocol(protocol),
     )
     .await
 }
@@ -108,12 +110,17 @@ pub async fn login_with_password(
 /// # }
 /// ```
 pub async fn password_login(client: &ricq::Client, uin: i64, password: &str) -> Result<()> {
-    let resp = client.password_login(uin, password).await?;
-    handle_password_login_resp(client, resp).await?;
-    Ok(())
+    password_login_with_options(client, uin, password, &mut LoginOptions::new()).await
 }
 
-async fn handle_password_login_resp(client: &ricq::Client, mut resp: LoginResponse) -> Result<()> {
+pub(crate) async fn password_login_with_options(
+    client: &ricq::Client,
+    uin: i64,
+    password: &str,
+    options: &mut LoginOptions,
+) -> Result<()> {
+    let mut resp = client.password_login(uin, password).await?;
+    let mut lock_state = DeviceLockState::default();
     loop {
         match resp {
             LoginResponse::Success(LoginSuccess {
@@ -122,25 +129,28 @@ async fn handle_password_login_resp(client: &ricq::Client, mut resp: LoginRespon
                 tracing::info!("登录成功: {:?}", account_info);
                 break;
             }
-            LoginResponse::DeviceLocked(LoginDeviceLocked {
-                // ref sms_phone,
-                verify_url,
-                message,
-                ..
-            }) => {
-                bail!(
-                    "设备锁：{}\n请前往 {} 解锁",
-                    message.unwrap_or_default(),
-                    verify_url.unwrap_or_default()
-                );
-                //也可以走短信验证
-                // resp = client.request_sms().await.expect("failed to request sms");
+            LoginResponse::DeviceLocked(locked) => {
+                resp = match handle_device_locked(client, locked, options, &mut lock_state).await? {
+                    Some(resp) => resp,
+                    // 网页验证完成，重新登录
+                    None => client.password_login(uin, password).await?,
+                };
             }
             LoginResponse::NeedCaptcha(LoginNeedCaptcha { ref verify_url, .. }) => {
-                tracing::info!("滑块 url: {}", verify_url.as_deref().unwrap_or("")); // TODO: 接入 TxCaptchaHelper
-                tracing::info!("请输入 ticket:");
-                let mut reader = FramedRead::new(tokio::io::stdin(), LinesCodec::new());
-                let ticket = reader.next().await.transpose().unwrap().unwrap();
+                emit(
+                    options.events.as_ref(),
+                    LoginEvent::NeedCaptcha {
+                        url: verify_url.clone().unwrap_or_default(),
+                    },
+                )
+                .await;
+                let url = verify_url.as_deref().unwrap_or_default();
+                let ticket = match &options.captcha_solver {
+                    Some(solver) => solve_captcha(&**solver, url, options.captcha_timeout).await?,
+                    None => {
+                        solve_captcha(&StdinCaptchaSolver, url, options.captcha_timeout).await?
+                    }
+                };
                 resp = client.submit_ticket(&ticket).await?;
             }
             LoginResponse::DeviceLockLogin { .. } => {
@@ -150,6 +160,11 @@ async fn handle_password_login_resp(client: &ricq::Client, mut resp: LoginRespon
             LoginResponse::TooManySMSRequest => {
                 bail!("短信验证码请求过于频繁，请稍后再试")
             }
+            unknown if lock_state.sms_sent() && options.sms_handler.is_some() => {
+                // 验证码错误时，服务器返回未知状态，要求重新输入
+                tracing::warn!("短信验证失败: {:?}", unknown);
+                resp = resubmit_sms_code(client, options, &mut lock_state).await?;
+            }
             unknown => {
                 bail!("登录失败: {:?}", unknown)
             }
@@ -158,3 +173,11 @@ async fn handle_password_login_resp(client: &ricq::Client, mut resp: LoginRespon
 
     Ok(())
 }
+
+/// 在限定时间内完成滑块验证。
+async fn solve_captcha(solver: &dyn CaptchaSolver, url: &str, timeout: Duration) -> Result<String> {
+    match tokio::time::timeout(timeout, solver.solve(url)).await {
+        Ok(ticket) => Ok(ticket?.trim().to_string()),
+        Err(_) => bail!("滑块验证超时"),
+    }
+}
@@ -0,0 +1,74 @@
+//! 从字符串解析客户端协议。
+
+use std::{fmt::Display, str::FromStr};
+
+use ricq::Protocol;
+
+/// 可以从字符串解析的客户端协议，用于从配置文件或环境变量读取协议。
+///
+/// 解析时不区分大小写，`-` 与 `_` 等价。
+///
+/// | 字符串 | 协议 |
+/// | --- | --- |
+/// | `ipad` | `Protocol::IPad` |
+/// | `android_phone`、`android`、`phone` | `Protocol::AndroidPhone` |
+/// | `android_watch`、`watch` | `Protocol::AndroidWatch` |
+/// | `macos`、`mac` | `Protocol::MacOS` |
+/// | `qidian` | `Protocol::QiDian` |
+///
+/// # Examples
+///
+/// ```
+/// use chocho_login::{LoginOptions, ProtocolName, RQProtocol};
+///
+/// let protocol: ProtocolName = "Android-Watch".parse().unwrap();
+/// assert!(matches!(RQProtocol::from(protocol), RQProtocol::AndroidWatch));
+///
+/// let options = LoginOptions::new().protocol("ipad".parse::<ProtocolName>().unwrap());
+///
+/// let err = "windows".parse::<ProtocolName>().unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "未知的协议 `windows`，可选值为 ipad、android_phone、android_watch、macos、qidian"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProtocolName(pub Protocol);
+
+impl FromStr for ProtocolName {
+    type Err = ParseProtocolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let protocol = match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "ipad" => Protocol::IPad,
+            "android_phone" | "androidphone" | "android" | "phone" => Protocol::AndroidPhone,
+            "android_watch" | "androidwatch" | "watch" => Protocol::AndroidWatch,
+            "macos" | "mac" => Protocol::MacOS,
+            "qidian" => Protocol::QiDian,
+            _ => return Err(ParseProtocolError(s.to_string())),
+        };
+        Ok(ProtocolName(protocol))
+    }
+}
+
+impl From<ProtocolName> for Protocol {
+    fn from(protocol: ProtocolName) -> Self {
+        protocol.0
+    }
+}
+
+/// 协议解析错误。
+#[derive(Debug, Clone)]
+pub struct ParseProtocolError(String);
+
+impl Display for ParseProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "未知的协议 `{}`，可选值为 ipad、android_phone、android_watch、macos、qidian",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseProtocolError {}
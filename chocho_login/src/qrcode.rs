@@ -1,16 +1,61 @@
 //! 二维码登录。
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use bytes::Bytes;
 use ricq::qsign::QSignClient;
 use ricq::{handler::Handler, Client, LoginResponse, LoginSuccess, Protocol};
+use tokio::sync::mpsc;
 
+use crate::credential::{login_context, login_with_credential, LoginOptions};
+use crate::device_lock::{handle_device_locked, resubmit_sms_code, DeviceLockState};
+use crate::event::{emit, LoginEvent};
 use crate::login::login_impl;
 use crate::AliveHandle;
 
+/// 协议是否支持二维码登录。
+///
+/// 只有 Android 手表协议和 macOS 协议可以扫码登录，手机、iPad 等协议需要使用密码登录。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_login::{qrcode::supports_qrcode, RQProtocol};
+///
+/// assert!(supports_qrcode(&RQProtocol::AndroidWatch));
+/// assert!(supports_qrcode(&RQProtocol::MacOS));
+/// assert!(!supports_qrcode(&RQProtocol::AndroidPhone));
+/// ```
+pub fn supports_qrcode(protocol: &Protocol) -> bool {
+    matches!(protocol, Protocol::AndroidWatch | Protocol::MacOS)
+}
+
 /// 使用二维码登录。
 ///
+/// 使用 Android 手表协议，手表协议不能发送部分类型的消息。如需使用其它支持扫码的协议（参见 [`supports_qrcode`]），
+/// 或指定连接器（例如通过代理连接）等更多选项，请使用 [`login_with_credential`] 和 [`LoginOptions`]：
+///
+/// ```no_run
+/// use std::{time::Duration, sync::Arc};
+/// use chocho_login::{login_with_credential, Credential, LoginOptions, QSignClient, RQProtocol};
+/// use ricq::handler::DefaultHandler;
+///
+/// # async fn _f(qsign_client: Arc<QSignClient>) -> anyhow::Result<()> {
+/// let (client, alive) = login_with_credential(
+///     Credential::QrCode { uin: 123456789 },
+///     "./data",
+///     DefaultHandler,
+///     qsign_client,
+///     LoginOptions::new().protocol(RQProtocol::MacOS),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
 /// # Arguments
 ///
 /// * `uin` - QQ号
@@ -52,13 +97,18 @@ pub async fn login_with_qrcode(
     qsign_client: Arc<QSignClient>,
     handler: impl Handler + 'static + Send,
 ) -> Result<(Arc<Client>, AliveHandle)> {
+    // 不经过 `LoginOptions::show_qrcode`，回调不需要满足 `Send + 'static`
+    let mut options = LoginOptions::new();
+    let context = login_context(data_folder.as_ref(), &mut options);
     login_impl(
         uin,
         Protocol::AndroidWatch,
-        data_folder,
         qsign_client,
         handler,
-        move |client| async move { qrcode_login(&client, uin, show_qrcode).await },
+        context,
+        move |client| async move {
+            qrcode_login_with_options(&client, uin, show_qrcode, &mut options).await
+        },
     )
     .await
 }
@@ -67,6 +117,8 @@ pub async fn login_with_qrcode(
 ///
 /// 此方法用于已有客户端实例的情况。
 ///
+/// `refresh` 用于主动刷新二维码：每收到一个信号，立即重新获取二维码并回调新的图像。
+///
 /// # Examples
 ///
 /// ```no_run
@@ -94,10 +146,11 @@ pub async fn login_with_qrcode(
 ///     async move { client.start(stream).await }
 /// });
 /// tokio::task::yield_now().await;
+/// let (refresh, receiver) = tokio::sync::mpsc::channel(1);
 /// qrcode_login(&client, 123456789, |qrcode| {
 ///     println!("{}", qrcode_text(&qrcode)?);
 ///     Ok(())
-/// }).await?;
+/// }, Some(receiver)).await?;
 /// after_login(&client).await;
 /// # Ok(())
 /// # }
@@ -105,39 +158,96 @@ pub async fn login_with_qrcode(
 pub async fn qrcode_login(
     client: &ricq::Client,
     uin: i64,
-    mut show_qrcode: impl FnMut(Bytes) -> Result<()>,
+    show_qrcode: impl FnMut(Bytes) -> Result<()>,
+    refresh: Option<mpsc::Receiver<()>>,
 ) -> Result<()> {
-    use std::time::Duration;
-
-    use ricq::{QRCodeConfirmed, QRCodeImageFetch, QRCodeState};
+    let mut options = LoginOptions::new();
+    options.qrcode_refresh = refresh;
+    qrcode_login_with_options(client, uin, show_qrcode, &mut options).await
+}
 
+pub(crate) async fn qrcode_login_with_options(
+    client: &ricq::Client,
+    uin: i64,
+    show_qrcode: impl FnMut(Bytes) -> Result<()>,
+    options: &mut LoginOptions,
+) -> Result<()> {
     tracing::info!("使用二维码登录，uin={}", uin);
 
+    let timeout = options.qrcode_timeout;
+    let login = poll_qrcode(client, uin, show_qrcode, options);
+    match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, login).await {
+            Ok(result) => result,
+            Err(_) => bail!("二维码登录超时：{} 秒内未完成扫码", timeout.as_secs()),
+        },
+        None => login.await,
+    }
+}
+
+async fn poll_qrcode(
+    client: &ricq::Client,
+    uin: i64,
+    mut show_qrcode: impl FnMut(Bytes) -> Result<()>,
+    options: &mut LoginOptions,
+) -> Result<()> {
+    use ricq::{QRCodeConfirmed, QRCodeImageFetch, QRCodeState};
+
+    let events = options.events.clone();
+    let events = events.as_ref();
+    let mut refresh = options.qrcode_refresh.take();
     let mut resp = client.fetch_qrcode().await?;
 
     let mut image_sig = bytes::Bytes::new();
+    // 连续重新获取二维码的次数
+    let mut refreshes = 0;
     loop {
         match resp {
             QRCodeState::ImageFetch(QRCodeImageFetch {
                 image_data,
                 ref sig,
             }) => {
+                emit(
+                    events,
+                    LoginEvent::QrCodeFetched {
+                        image: image_data.clone(),
+                    },
+                )
+                .await;
                 show_qrcode(image_data)?;
                 image_sig = sig.clone();
             }
             QRCodeState::WaitingForScan => {
-                tracing::debug!("等待二维码扫描")
+                tracing::debug!("等待二维码扫描");
+                emit(events, LoginEvent::WaitingForScan).await;
             }
             QRCodeState::WaitingForConfirm => {
-                tracing::debug!("二维码已扫描，等待确认")
+                tracing::debug!("二维码已扫描，等待确认");
+                emit(events, LoginEvent::WaitingForConfirm).await;
+                refreshes = 0;
             }
             QRCodeState::Timeout => {
+                emit(events, LoginEvent::QrCodeExpired).await;
+                if options
+                    .qrcode_max_refreshes
+                    .map_or(false, |max| refreshes >= max)
+                {
+                    bail!("二维码已超时，且连续重新获取 {} 次后仍未扫码", refreshes);
+                }
+                refreshes += 1;
                 tracing::info!("二维码已超时，重新获取");
                 if let QRCodeState::ImageFetch(QRCodeImageFetch {
                     image_data,
                     ref sig,
-                }) = client.fetch_qrcode().await.expect("failed to fetch qrcode")
+                }) = client.fetch_qrcode().await?
                 {
+                    emit(
+                        events,
+                        LoginEvent::QrCodeFetched {
+                            image: image_data.clone(),
+                        },
+                    )
+                    .await;
                     show_qrcode(image_data)?;
                     image_sig = sig.clone();
                 }
@@ -149,43 +259,274 @@ pub async fn qrcode_login(
                 ..
             }) => {
                 tracing::info!("二维码已确认");
-                let mut login_resp = client.qrcode_login(tmp_pwd, tmp_no_pic_sig, tgt_qr).await?;
-                if let LoginResponse::DeviceLockLogin { .. } = login_resp {
-                    login_resp = client.device_lock_login().await?;
-                }
-                if let LoginResponse::Success(LoginSuccess {
-                    ref account_info, ..
-                }) = login_resp
-                {
-                    tracing::info!("登录成功: {:?}", account_info);
+                let login_resp = client.qrcode_login(tmp_pwd, tmp_no_pic_sig, tgt_qr).await?;
+                if finish_login(client, login_resp, options).await? {
                     let real_uin = client.uin().await;
                     if real_uin != uin {
                         tracing::warn!("预期登录账号 {}，但实际登陆账号为 {}", uin, real_uin);
                     }
                     break;
                 }
-                bail!("登录失败: {:?}", login_resp)
+                // 网页验证完成，需要重新扫码
+                tracing::info!("设备锁验证完成，请重新扫描二维码");
+                resp = client.fetch_qrcode().await?;
+                continue;
             }
             QRCodeState::Canceled => bail!("二维码已取消"),
         }
-        tokio::time::sleep(Duration::from_secs(5)).await;
-        resp = client.query_qrcode_result(&image_sig).await?;
+
+        // 只在两次轮询之间等待刷新信号，不会打断正在进行的轮询请求
+        let refreshed = tokio::select! {
+            _ = tokio::time::sleep(options.qrcode_poll_interval) => false,
+            _ = wait_refresh(&mut refresh) => true,
+        };
+        resp = if refreshed {
+            tracing::info!("主动刷新二维码");
+            client.fetch_qrcode().await?
+        } else {
+            client.query_qrcode_result(&image_sig).await?
+        };
     }
 
     Ok(())
 }
 
+/// 等待主动刷新二维码的信号。
+///
+/// 连续的多个信号合并为一次刷新。没有信号来源，或发送端全部关闭时，永远不会返回。
+async fn wait_refresh(refresh: &mut Option<mpsc::Receiver<()>>) {
+    let Some(receiver) = refresh else {
+        return std::future::pending().await;
+    };
+    if receiver.recv().await.is_none() {
+        *refresh = None;
+        return std::future::pending().await;
+    }
+    while receiver.try_recv().is_ok() {}
+}
+
+/// 完成扫码后的登录流程。
+///
+/// 登录成功时返回 `true`；通过网页验证解除设备锁后返回 `false`，此时需要重新扫码。
+async fn finish_login(
+    client: &ricq::Client,
+    mut resp: LoginResponse,
+    options: &mut LoginOptions,
+) -> Result<bool> {
+    let mut lock_state = DeviceLockState::default();
+    loop {
+        match resp {
+            LoginResponse::Success(LoginSuccess {
+                ref account_info, ..
+            }) => {
+                tracing::info!("登录成功: {:?}", account_info);
+                return Ok(true);
+            }
+            LoginResponse::DeviceLockLogin { .. } => {
+                resp = client.device_lock_login().await?;
+            }
+            LoginResponse::DeviceLocked(locked) => {
+                match handle_device_locked(client, locked, options, &mut lock_state).await? {
+                    Some(next) => resp = next,
+                    None => return Ok(false),
+                }
+            }
+            unknown if lock_state.sms_sent() && options.sms_handler.is_some() => {
+                // 验证码错误时，服务器返回未知状态，要求重新输入
+                tracing::warn!("短信验证失败: {:?}", unknown);
+                resp = resubmit_sms_code(client, options, &mut lock_state).await?;
+            }
+            unknown => bail!("登录失败: {:?}", unknown),
+        }
+    }
+}
+
+/// 可复用的二维码识别器。
+///
+/// 识别器会缓存灰度图的缓冲区，二值化也直接在这个缓冲区上进行。连续识别多帧图片（例如摄像头扫码）时，
+/// 只要图片尺寸不变，就不会为灰度图重新分配内存。
+///
+/// `QrcodeDecoder` 实现了 `Send` 和 `Sync`，但 [`decode`](QrcodeDecoder::decode) 需要 `&mut self`，
+/// 多个线程同时识别时，应为每个线程分别创建识别器，而不是共享同一个识别器。
+///
+/// 与每次新建识别器的性能对比见 `chocho_login/benches/qrcode.rs`（`cargo bench -p chocho_login`）。
+///
+/// # Examples
+///
+/// ```no_run
+/// use chocho_login::qrcode::QrcodeDecoder;
+///
+/// # fn _f(frames: Vec<Vec<u8>>) {
+/// let mut decoder = QrcodeDecoder::new();
+/// for frame in frames {
+///     if let Ok(content) = decoder.decode(&frame) {
+///         println!("识别到二维码：{}", content);
+///         break;
+///     }
+/// }
+/// # }
+/// ```
+#[cfg(feature = "show-qrcode")]
+#[derive(Debug, Clone, Default)]
+pub struct QrcodeDecoder {
+    luma: Vec<u8>,
+}
+
+#[cfg(feature = "show-qrcode")]
+impl QrcodeDecoder {
+    /// 创建识别器。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 识别图片中的二维码，返回二维码的内容。
+    ///
+    /// 图片中没有二维码或有多个二维码时返回错误。
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<String> {
+        use image::{DynamicImage, GenericImageView, Pixel};
+
+        let image = image::load_from_memory(bytes)?;
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        self.luma.clear();
+        match &image {
+            DynamicImage::ImageLuma8(luma) => self.luma.extend_from_slice(luma.as_raw()),
+            DynamicImage::ImageRgb8(rgb) => self.luma.extend(rgb.pixels().map(|p| p.to_luma()[0])),
+            DynamicImage::ImageRgba8(rgba) => {
+                self.luma.extend(rgba.pixels().map(|p| p.to_luma()[0]))
+            }
+            other => self
+                .luma
+                .extend(other.pixels().map(|(_, _, p)| p.to_luma()[0])),
+        }
+
+        let mut prepared = rqrr::PreparedImage::prepare(LumaBuffer {
+            width,
+            height,
+            pixels: &mut self.luma,
+        });
+        let grids = prepared.detect_grids();
+        if grids.len() != 1 {
+            bail!("无法识别二维码");
+        }
+        let (_, content) = grids[0].decode()?;
+        Ok(content)
+    }
+}
+
+/// 借用 [`QrcodeDecoder`] 缓冲区的灰度图，rqrr 在其上原地二值化。
+#[cfg(feature = "show-qrcode")]
+struct LumaBuffer<'a> {
+    width: usize,
+    height: usize,
+    pixels: &'a mut [u8],
+}
+
+#[cfg(feature = "show-qrcode")]
+impl rqrr::ImageBuffer for LumaBuffer<'_> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, val: u8) {
+        self.pixels[y * self.width + x] = val;
+    }
+}
+
+/// 识别二维码图片，重新生成二维码。
+#[cfg(feature = "show-qrcode")]
+fn decode_qrcode(qrcode: &[u8]) -> anyhow::Result<qrcode::QrCode> {
+    let content = QrcodeDecoder::new().decode(qrcode)?;
+    Ok(qrcode::QrCode::new(content)?)
+}
+
 /// 将二维码图片转换为文本形式。
+///
+/// 使用 Unicode 半块字符，每行文本表示两行像素。若终端不支持半块字符，可以使用 [`qrcode_ascii`]。
 #[cfg(feature = "show-qrcode")]
 pub fn qrcode_text(qrcode: &[u8]) -> anyhow::Result<String> {
-    let qrcode = image::load_from_memory(qrcode)?.to_luma8();
-    let mut qrcode = rqrr::PreparedImage::prepare(qrcode);
-    let grids = qrcode.detect_grids();
-    if grids.len() != 1 {
-        bail!("无法识别二维码");
-    }
-    let (_, content) = grids[0].decode()?;
-    let qrcode = qrcode::QrCode::new(content)?;
+    let qrcode = decode_qrcode(qrcode)?;
     let qrcode = qrcode.render::<qrcode::render::unicode::Dense1x2>().build();
     Ok(qrcode)
 }
+
+/// 将二维码图片转换为 ASCII 文本形式。
+///
+/// 只使用 `#` 和空格，适用于不支持 Unicode 半块字符的终端。
+#[cfg(feature = "show-qrcode")]
+pub fn qrcode_ascii(qrcode: &[u8]) -> anyhow::Result<String> {
+    let qrcode = decode_qrcode(qrcode)?;
+    let qrcode = qrcode
+        .render::<char>()
+        .dark_color('#')
+        .light_color(' ')
+        .module_dimensions(2, 1)
+        .build();
+    Ok(qrcode)
+}
+
+/// 将二维码图片转换为 SVG 字符串，可直接嵌入网页。
+#[cfg(feature = "show-qrcode")]
+pub fn qrcode_svg(qrcode: &[u8]) -> anyhow::Result<String> {
+    let qrcode = decode_qrcode(qrcode)?;
+    let qrcode = qrcode
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+    Ok(qrcode)
+}
+
+/// 创建将二维码保存为 PNG 文件的回调，可直接传给 [`login_with_qrcode`] 或 [`LoginOptions::show_qrcode`]。
+///
+/// 二维码过期重新获取时会覆盖同一个文件。路径不可写时，回调返回错误，登录随之失败。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{time::Duration, sync::Arc};
+/// use chocho_login::{login_with_qrcode, QSignClient};
+/// use chocho_login::qrcode::save_qrcode_to;
+/// use ricq::handler::DefaultHandler;
+///
+/// # async fn _f() -> anyhow::Result<()> {
+/// let qsign_client = Arc::new(QSignClient::new(
+///     "http://localhost:5000".to_string(),
+///     "114514".to_string(),
+///     Duration::from_secs(60),
+/// )?);
+/// let (client, alive) = login_with_qrcode(
+///     123456789,
+///     save_qrcode_to("./qrcode.png"),
+///     "./data",
+///     qsign_client,
+///     DefaultHandler,
+/// ).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn save_qrcode_to(path: impl AsRef<Path>) -> impl FnMut(Bytes) -> Result<()> + Send + 'static {
+    let path: PathBuf = path.as_ref().to_path_buf();
+    move |qrcode| {
+        std::fs::write(&path, &qrcode)
+            .with_context(|| format!("无法将二维码写入 {}", path.display()))?;
+        tracing::info!("二维码已保存到 {}，请扫码登录", path.display());
+        Ok(())
+    }
+}
+
+/// 将二维码图片重新编码为 PNG 格式。
+#[cfg(feature = "show-qrcode")]
+pub fn qrcode_to_png_bytes(qrcode: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(qrcode)?;
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageOutputFormat::Png)?;
+    Ok(png.into_inner())
+}
@@ -0,0 +1,410 @@
+//! qsign 服务器故障转移。
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::{Method, StatusCode, Url};
+use ricq::qsign::QSignClient;
+use serde_json::Value;
+
+#[derive(Default)]
+struct Health {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+struct Server {
+    url: String,
+    key: String,
+    health: Mutex<Health>,
+}
+
+/// 转发给 qsign 服务器的请求。
+struct QSignRequest {
+    method: Method,
+    path: String,
+    query: Vec<(String, String)>,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+/// qsign 服务器的响应：状态码、`Content-Type` 与响应体。
+type QSignResponse = (StatusCode, String, Vec<u8>);
+
+/// 多 qsign 服务器的故障转移。
+///
+/// 每次调用 qsign 接口时，从上次使用的下一个服务器开始轮询，跳过已被剔除的服务器；
+/// 请求失败（连接失败、超时或服务器返回 5xx）时立即改用下一个服务器重试。
+/// 某个服务器连续失败达到阈值后，会在冷却时间内被剔除，不再参与分发。
+///
+/// ricq 的客户端只接受单个 [`QSignClient`]，因此通过 [`serve`](MultiQSignClient::serve)
+/// 在本机启动一个转发代理，返回指向代理的 `QSignClient`。登录和运行期间 ricq 发出的所有 qsign 请求
+/// 都经过代理，按上述策略分发到各个服务器。请求中的 `key` 参数会替换为目标服务器的密钥。
+/// `serve` 需要启用 `qsign-failover` 特性；未启用时，可以用 [`select`](MultiQSignClient::select)
+/// 在登录前选择一个可用的服务器。
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+/// use chocho_login::{login_with_credential, Credential, LoginOptions, MultiQSignClient};
+/// use ricq::handler::DefaultHandler;
+///
+/// # #[cfg(feature = "qsign-failover")]
+/// # async fn _f() -> anyhow::Result<()> {
+/// let qsign = MultiQSignClient::new(
+///     [
+///         ("http://localhost:5000", "114514"),
+///         ("http://localhost:5001", "114514"),
+///     ],
+///     Duration::from_secs(60),
+/// );
+/// let (client, alive) = login_with_credential(
+///     Credential::QrCode { uin: 123456789 },
+///     "./data",
+///     DefaultHandler,
+///     Arc::new(qsign).serve().await?,
+///     LoginOptions::new(),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultiQSignClient {
+    servers: Vec<Server>,
+    timeout: Duration,
+    failure_threshold: u32,
+    cooldown: Duration,
+    next: AtomicUsize,
+    http: reqwest::Client,
+}
+
+impl MultiQSignClient {
+    /// 创建故障转移客户端。
+    ///
+    /// # Arguments
+    ///
+    /// * `servers` - qsign 服务器的地址与密钥。
+    /// * `timeout` - 请求超时时间。
+    pub fn new(
+        servers: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            servers: servers
+                .into_iter()
+                .map(|(url, key)| Server {
+                    url: url.into(),
+                    key: key.into(),
+                    health: Default::default(),
+                })
+                .collect(),
+            timeout,
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(300),
+            next: AtomicUsize::new(0),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// 连续失败多少次后剔除服务器，默认为 3 次。
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    /// 服务器被剔除的时长，默认为 5 分钟。
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// 启动本机的转发代理，返回指向代理的 [`QSignClient`]。
+    ///
+    /// 代理监听 `127.0.0.1` 上的随机端口，每个请求都按故障转移策略分发到各个服务器，
+    /// 所有服务器都失败时返回 502。代理在 tokio 运行时结束前一直运行。
+    /// 返回的 `QSignClient` 的超时时间为单个服务器超时时间乘以服务器数量，以便尝试所有服务器。
+    ///
+    /// 需要启用 `qsign-failover` 特性。
+    #[cfg(feature = "qsign-failover")]
+    pub async fn serve(self: Arc<Self>) -> Result<Arc<QSignClient>> {
+        let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let timeout = self.timeout * self.servers.len().max(1) as u32;
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("qsign 代理接受连接失败: {}", e);
+                        continue;
+                    }
+                };
+                let this = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = this.handle(stream).await {
+                        tracing::warn!("qsign 代理处理请求失败: {}", e);
+                    }
+                });
+            }
+        });
+        tracing::info!("qsign 故障转移代理已启动: {}", addr);
+        Ok(Arc::new(QSignClient::new(
+            format!("http://{}", addr),
+            String::new(),
+            timeout,
+        )?))
+    }
+
+    /// 以 GET 请求调用 qsign 接口，例如 `sign`、`custom_energy`，返回解析后的 JSON。
+    ///
+    /// 按故障转移策略选择服务器，`key` 参数会自动添加。
+    pub async fn call(&self, path: &str, params: &[(&str, &str)]) -> Result<Value> {
+        let request = QSignRequest {
+            method: Method::GET,
+            path: path.trim_start_matches('/').to_string(),
+            query: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            content_type: None,
+            body: vec![],
+        };
+        let (_, _, body) = self.forward(&request).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// 选择一个可用的 qsign 服务器。
+    ///
+    /// 从上次选择的下一个服务器开始轮询，跳过已被剔除的服务器，返回第一个健康检查通过的服务器。
+    /// 返回的 `QSignClient` 只连接选中的服务器，之后不会再故障转移；需要运行期间的故障转移时，请使用
+    /// [`serve`](MultiQSignClient::serve)。
+    pub async fn select(&self) -> Result<Arc<QSignClient>> {
+        let count = self.servers.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for server in (0..count).map(|i| &self.servers[(start + i) % count]) {
+            if self.is_open(server) {
+                continue;
+            }
+            if self.probe(server).await {
+                self.record_success(server);
+                tracing::info!("使用 qsign 服务器: {}", server.url);
+                return Ok(Arc::new(QSignClient::new(
+                    server.url.clone(),
+                    server.key.clone(),
+                    self.timeout,
+                )?));
+            }
+            tracing::warn!("qsign 服务器不可用: {}", server.url);
+            self.record_failure(server);
+        }
+        bail!("所有 qsign 服务器均不可用")
+    }
+
+    /// 将请求依次发送到各个未被剔除的服务器，直到成功。
+    async fn forward(&self, request: &QSignRequest) -> Result<QSignResponse> {
+        let count = self.servers.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut last_error = None;
+        for server in (0..count).map(|i| &self.servers[(start + i) % count]) {
+            if self.is_open(server) {
+                continue;
+            }
+            match self.send(server, request).await {
+                Ok(response) => {
+                    self.record_success(server);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "qsign 服务器 {} 请求失败: {}，尝试下一个服务器",
+                        server.url,
+                        e
+                    );
+                    self.record_failure(server);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("所有 qsign 服务器均已暂停使用")))
+    }
+
+    async fn send(&self, server: &Server, request: &QSignRequest) -> Result<QSignResponse> {
+        let mut url = Url::parse(&format!(
+            "{}/{}",
+            server.url.trim_end_matches('/'),
+            request.path
+        ))?;
+        url.set_query(Some(&with_key(&request.query, &server.key)));
+        let mut builder = self
+            .http
+            .request(request.method.clone(), url)
+            .timeout(self.timeout);
+        let mut body = request.body.clone();
+        if let Some(content_type) = &request.content_type {
+            if content_type.starts_with("application/x-www-form-urlencoded") {
+                let form = parse_query(&String::from_utf8_lossy(&body));
+                body = with_key(&form, &server.key).into_bytes();
+            }
+            builder = builder.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+        let resp = builder.body(body).send().await?;
+        let status = resp.status();
+        if status.is_server_error() {
+            bail!("服务器返回 {}", status);
+        }
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        Ok((status, content_type, resp.bytes().await?.to_vec()))
+    }
+
+    /// 处理代理收到的一个 HTTP 请求，响应后关闭连接。
+    #[cfg(feature = "qsign-failover")]
+    async fn handle(&self, stream: tokio::net::TcpStream) -> Result<()> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        /// 代理只转发 qsign 请求，请求体不会很大。
+        const MAX_BODY: usize = 16 * 1024 * 1024;
+
+        let mut stream = BufReader::new(stream);
+        let mut line = String::new();
+        stream.read_line(&mut line).await?;
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            bail!("请求格式错误: {:?}", line);
+        };
+        let method = Method::from_bytes(method.as_bytes())?;
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let mut request = QSignRequest {
+            method,
+            path: path.trim_start_matches('/').to_string(),
+            query: parse_query(query),
+            content_type: None,
+            body: vec![],
+        };
+
+        let mut content_length = 0;
+        loop {
+            line.clear();
+            if stream.read_line(&mut line).await? == 0 {
+                break;
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse()?;
+                } else if name.eq_ignore_ascii_case("content-type") {
+                    request.content_type = Some(value.trim().to_string());
+                }
+            }
+        }
+        if content_length > MAX_BODY {
+            bail!("请求体过大: {} 字节", content_length);
+        }
+        request.body = vec![0; content_length];
+        stream.read_exact(&mut request.body).await?;
+
+        let (status, content_type, body) = match self.forward(&request).await {
+            Ok(response) => response,
+            Err(e) => (
+                StatusCode::BAD_GATEWAY,
+                "text/plain; charset=utf-8".to_string(),
+                e.to_string().into_bytes(),
+            ),
+        };
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default(),
+            content_type,
+            body.len()
+        );
+        let stream = stream.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&body).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    /// 手动报告某个服务器调用失败。
+    pub fn report_failure(&self, url: &str) {
+        if let Some(server) = self.servers.iter().find(|server| server.url == url) {
+            self.record_failure(server);
+        }
+    }
+
+    async fn probe(&self, server: &Server) -> bool {
+        match self
+            .http
+            .get(&server.url)
+            .timeout(self.timeout)
+            .send()
+            .await
+        {
+            Ok(resp) => resp.status().is_success(),
+            Err(_) => false,
+        }
+    }
+
+    fn is_open(&self, server: &Server) -> bool {
+        let mut health = server.health.lock().expect("Failed locking health");
+        match health.open_until {
+            Some(until) if until > Instant::now() => true,
+            Some(_) => {
+                // 冷却结束，重新参与选择
+                *health = Health::default();
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn record_success(&self, server: &Server) {
+        *server.health.lock().expect("Failed locking health") = Health::default();
+    }
+
+    fn record_failure(&self, server: &Server) {
+        let mut health = server.health.lock().expect("Failed locking health");
+        health.failures += 1;
+        if health.failures >= self.failure_threshold {
+            tracing::warn!(
+                "qsign 服务器 {} 连续失败 {} 次，暂停使用 {:?}",
+                server.url,
+                health.failures,
+                self.cooldown
+            );
+            health.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+/// 解析 `application/x-www-form-urlencoded` 格式的参数。
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    let mut url = Url::parse("http://localhost/").expect("valid url");
+    url.set_query(Some(query));
+    url.query_pairs()
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect()
+}
+
+/// 将参数中的 `key` 替换为目标服务器的密钥，并编码为 `application/x-www-form-urlencoded` 格式。
+fn with_key(params: &[(String, String)], key: &str) -> String {
+    let mut url = Url::parse("http://localhost/").expect("valid url");
+    url.query_pairs_mut()
+        .extend_pairs(params.iter().filter(|(name, _)| name != "key"))
+        .append_pair("key", key);
+    url.query().unwrap_or_default().to_string()
+}
@@ -0,0 +1,88 @@
+//! token 文件的加密与压缩，需要 `secure-store` 特性。
+//!
+//! 加密或压缩后的文件格式为：魔数 [`MAGIC`]、一字节的标志位、（加密时）12 字节的 nonce，以及数据本身。
+//! 数据先经过 gzip 压缩，再使用 AES-256-GCM 加密。
+
+use std::io::{Read, Write};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rand::RngCore;
+
+use crate::token::MAGIC;
+
+const FLAG_GZIP: u8 = 0b01;
+const FLAG_AES: u8 = 0b10;
+const NONCE_LEN: usize = 12;
+
+/// 编码数据。`key` 为 `None` 且不压缩时，返回原始数据。
+pub(crate) fn encode(data: &[u8], key: Option<&[u8; 32]>, compress: bool) -> Result<Vec<u8>> {
+    if key.is_none() && !compress {
+        return Ok(data.to_vec());
+    }
+
+    let mut flags = 0;
+    let mut payload = data.to_vec();
+    if compress {
+        flags |= FLAG_GZIP;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&payload)?;
+        payload = encoder.finish()?;
+    }
+
+    let mut out = MAGIC.to_vec();
+    match key {
+        Some(key) => {
+            flags |= FLAG_AES;
+            let mut nonce = [0; NONCE_LEN];
+            rand::thread_rng().fill_bytes(&mut nonce);
+            let cipher = Aes256Gcm::new(key.into());
+            let encrypted = cipher
+                .encrypt(Nonce::from_slice(&nonce), payload.as_slice())
+                .map_err(|_| anyhow!("token 加密失败"))?;
+            out.push(flags);
+            out.extend_from_slice(&nonce);
+            out.extend(encrypted);
+        }
+        None => {
+            out.push(flags);
+            out.extend(payload);
+        }
+    }
+    Ok(out)
+}
+
+/// 解码以 [`MAGIC`] 开头的数据。
+pub(crate) fn decode(data: &[u8], key: Option<&[u8; 32]>) -> Result<Vec<u8>> {
+    let Some((&flags, rest)) = data[MAGIC.len()..].split_first() else {
+        bail!("token 文件已损坏");
+    };
+
+    let mut payload = if flags & FLAG_AES != 0 {
+        let Some(key) = key else {
+            bail!("token 文件已加密，但未指定密钥，请通过 `FileTokenStore::encryption_key` 设置");
+        };
+        if rest.len() < NONCE_LEN {
+            bail!("token 文件已损坏");
+        }
+        let (nonce, encrypted) = rest.split_at(NONCE_LEN);
+        Aes256Gcm::new(key.into())
+            .decrypt(Nonce::from_slice(nonce), encrypted)
+            .map_err(|_| anyhow!("token 解密失败，密钥错误或文件已损坏"))?
+    } else {
+        rest.to_vec()
+    };
+
+    if flags & FLAG_GZIP != 0 {
+        let mut decompressed = vec![];
+        GzDecoder::new(payload.as_slice())
+            .read_to_end(&mut decompressed)
+            .map_err(|e| anyhow!("token 解压失败：{}", e))?;
+        payload = decompressed;
+    }
+    Ok(payload)
+}
@@ -0,0 +1,345 @@
+//! 会话快照。
+//!
+//! 将账号的数据文件（包括 `device.json`、`token.json` 等）打包为一个快照，用于将账号迁移到另一台机器上。
+//! 快照带有格式版本号和校验和，导入时会校验完整性。
+//!
+//! 文件的位置由[数据目录结构](crate::layout)决定。目录结构提供了账号目录（例如默认的 `{data_folder}/{uin}/`）时，
+//! 打包整个账号目录；否则只打包 `device.json`、token 文件和消息处理开关文件。
+//!
+//! 如果 token 文件经过加密（参见 [`FileTokenStore`](crate::token::FileTokenStore)），导入后仍需使用相同的密钥。
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chocho_login::session::{self, ConflictStrategy};
+//!
+//! # async fn _f() -> anyhow::Result<()> {
+//! // 旧机器
+//! let snapshot = session::export(123456789, "./bots").await?;
+//! tokio::fs::write("123456789.session", &snapshot).await?;
+//!
+//! // 新机器
+//! let snapshot = tokio::fs::read("123456789.session").await?;
+//! let uin = session::import_with(&snapshot, "./bots", ConflictStrategy::Backup).await?;
+//! println!("已导入账号 {}", uin);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::fs::atomic_write;
+use crate::layout::{DataLayout, DefaultLayout};
+
+/// 快照文件的魔数。
+const MAGIC: &[u8] = b"CHOCHO\x00SESSION";
+
+/// 快照格式的版本号。
+const VERSION: u8 = 1;
+
+/// 导入时目标账号的文件已存在的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// 返回错误，不做任何修改。
+    #[default]
+    Error,
+    /// 删除已有的文件后导入。
+    Overwrite,
+    /// 将已有的账号目录（或文件）重命名为 `{原名}.bak.{时间戳}` 后导入。
+    Backup,
+}
+
+/// 没有账号目录时，快照中各个文件的名称与路径。
+fn known_files(layout: &dyn DataLayout, uin: i64) -> [(&'static str, PathBuf); 3] {
+    [
+        ("device.json", layout.device_path(uin)),
+        ("token.json", layout.token_path(uin)),
+        ("scopes.json", layout.scopes_path(uin)),
+    ]
+}
+
+/// 在 `path` 之后加上备份后缀。
+fn backup_path(path: &Path) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut backup = OsString::from(path.as_os_str());
+    backup.push(format!(".bak.{}", timestamp));
+    backup.into()
+}
+
+/// 导出默认目录结构 `{data_folder}/{uin}/` 中账号的会话快照。
+///
+/// 参见 [`export_with_layout`]。
+pub async fn export(uin: i64, data_folder: impl AsRef<Path>) -> Result<Vec<u8>> {
+    export_with_layout(uin, &DefaultLayout::new(data_folder.as_ref())).await
+}
+
+/// 按目录结构 `layout` 导出账号的会话快照。
+///
+/// 账号的 `device.json` 必须存在；没有 token 文件时只打印警告，导入后需要重新登录。
+pub async fn export_with_layout(uin: i64, layout: &dyn DataLayout) -> Result<Vec<u8>> {
+    let device_path = layout.device_path(uin);
+    ensure!(
+        device_path.exists(),
+        "账号 {} 的 device.json（{}）不存在",
+        uin,
+        device_path.display()
+    );
+    if !layout.token_path(uin).exists() {
+        tracing::warn!("账号 {} 没有保存的 token，导入后需要重新登录", uin);
+    }
+
+    let mut files = match layout.account_dir(uin) {
+        Some(account_dir) => read_account_dir(&account_dir).await?,
+        None => {
+            let mut files = vec![];
+            for (name, path) in known_files(layout, uin) {
+                if path.exists() {
+                    files.push((name.to_string(), tokio::fs::read(&path).await?));
+                }
+            }
+            files
+        }
+    };
+    files.sort();
+
+    let mut body = vec![];
+    body.extend_from_slice(&uin.to_be_bytes());
+    body.extend_from_slice(&(files.len() as u32).to_be_bytes());
+    for (name, data) in &files {
+        body.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        body.extend_from_slice(name.as_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        body.extend_from_slice(data);
+    }
+
+    let mut snapshot = MAGIC.to_vec();
+    snapshot.push(VERSION);
+    snapshot.extend_from_slice(&md5::compute(&body).0);
+    snapshot.extend_from_slice(&body);
+    Ok(snapshot)
+}
+
+/// 读取账号目录中的所有文件，文件名为以 `/` 分隔的相对路径。
+async fn read_account_dir(account_dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = vec![];
+    let mut dirs = vec![account_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let name = path
+                .strip_prefix(account_dir)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if name.ends_with(".tmp") {
+                continue;
+            }
+            files.push((name, tokio::fs::read(&path).await?));
+        }
+    }
+    Ok(files)
+}
+
+/// 将会话快照导入默认目录结构 `{data_folder}/{uin}/`，返回快照中的账号。
+///
+/// 目标账号目录已存在时返回错误，参见 [`import_with`]。
+pub async fn import(snapshot: &[u8], data_folder: impl AsRef<Path>) -> Result<i64> {
+    import_with(snapshot, data_folder, ConflictStrategy::Error).await
+}
+
+/// 将会话快照导入默认目录结构 `{data_folder}/{uin}/`，按 `strategy` 处理目标账号目录已存在的情况，
+/// 返回快照中的账号。
+///
+/// 参见 [`import_with_layout`]。
+pub async fn import_with(
+    snapshot: &[u8],
+    data_folder: impl AsRef<Path>,
+    strategy: ConflictStrategy,
+) -> Result<i64> {
+    import_with_layout(
+        snapshot,
+        &DefaultLayout::new(data_folder.as_ref()),
+        strategy,
+    )
+    .await
+}
+
+/// 按目录结构 `layout` 导入会话快照，按 `strategy` 处理目标文件已存在的情况，返回快照中的账号。
+///
+/// 有账号目录时，快照会先完整地写入临时目录，校验通过后再替换账号目录，导入失败时不会破坏已有的数据。
+/// 没有账号目录时，快照中的 `device.json`、token 文件和消息处理开关文件分别写入对应的路径，
+/// 其它文件会被忽略。
+pub async fn import_with_layout(
+    snapshot: &[u8],
+    layout: &dyn DataLayout,
+    strategy: ConflictStrategy,
+) -> Result<i64> {
+    let (uin, files) = parse(snapshot)?;
+    match layout.account_dir(uin) {
+        Some(account_dir) => import_account_dir(uin, files, &account_dir, strategy).await?,
+        None => import_known_files(uin, files, layout, strategy).await?,
+    }
+    Ok(uin)
+}
+
+async fn import_account_dir(
+    uin: i64,
+    files: Vec<(PathBuf, Vec<u8>)>,
+    account_dir: &Path,
+    strategy: ConflictStrategy,
+) -> Result<()> {
+    if account_dir.exists() && strategy == ConflictStrategy::Error {
+        bail!("账号 {} 的目录 {} 已存在", uin, account_dir.display());
+    }
+
+    let staging = account_dir.with_file_name(format!(".{}.importing", uin));
+    if staging.exists() {
+        tokio::fs::remove_dir_all(&staging).await?;
+    }
+    tokio::fs::create_dir_all(&staging).await?;
+    for (name, data) in files {
+        let path = staging.join(&name);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        atomic_write(&path, data).await?;
+    }
+
+    if account_dir.exists() {
+        match strategy {
+            ConflictStrategy::Error => unreachable!(),
+            ConflictStrategy::Overwrite => {
+                tokio::fs::remove_dir_all(account_dir).await?;
+            }
+            ConflictStrategy::Backup => {
+                let backup = backup_path(account_dir);
+                tokio::fs::rename(account_dir, &backup).await?;
+                tracing::info!("已将账号 {} 原有的目录备份到 {}", uin, backup.display());
+            }
+        }
+    }
+    tokio::fs::rename(&staging, account_dir)
+        .await
+        .with_context(|| format!("无法写入账号目录 {}", account_dir.display()))?;
+    Ok(())
+}
+
+async fn import_known_files(
+    uin: i64,
+    files: Vec<(PathBuf, Vec<u8>)>,
+    layout: &dyn DataLayout,
+    strategy: ConflictStrategy,
+) -> Result<()> {
+    let known = known_files(layout, uin);
+    let mut targets = vec![];
+    for (name, data) in files {
+        match known.iter().find(|(known, _)| Path::new(known) == name) {
+            Some((_, path)) => targets.push((path.clone(), data)),
+            None => tracing::warn!("目录结构中没有 {} 对应的文件，已忽略", name.display()),
+        }
+    }
+
+    let existing: Vec<_> = known
+        .iter()
+        .map(|(_, path)| path)
+        .filter(|path| path.exists())
+        .collect();
+    if let Some(path) = existing.first() {
+        match strategy {
+            ConflictStrategy::Error => bail!("账号 {} 的文件 {} 已存在", uin, path.display()),
+            ConflictStrategy::Overwrite => {
+                // 快照中有的文件由写入时替换，只删除快照中没有的文件
+                for path in existing {
+                    if !targets.iter().any(|(target, _)| target == path) {
+                        tokio::fs::remove_file(path).await?;
+                    }
+                }
+            }
+            ConflictStrategy::Backup => {
+                for path in existing {
+                    let backup = backup_path(path);
+                    tokio::fs::rename(path, &backup).await?;
+                    tracing::info!("已将账号 {} 原有的文件备份到 {}", uin, backup.display());
+                }
+            }
+        }
+    }
+
+    for (path, data) in targets {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        atomic_write(&path, data)
+            .await
+            .with_context(|| format!("无法写入文件 {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// 解析并校验快照。
+fn parse(snapshot: &[u8]) -> Result<(i64, Vec<(PathBuf, Vec<u8>)>)> {
+    let rest = snapshot.strip_prefix(MAGIC).context("不是有效的会话快照")?;
+    let (&version, rest) = rest.split_first().context("会话快照已损坏")?;
+    ensure!(
+        version == VERSION,
+        "不支持的会话快照版本 {}，当前支持的版本为 {}",
+        version,
+        VERSION
+    );
+    ensure!(rest.len() >= 16, "会话快照已损坏");
+    let (checksum, body) = rest.split_at(16);
+    ensure!(
+        md5::compute(body).0 == checksum,
+        "会话快照校验失败，文件可能已损坏"
+    );
+
+    let mut reader = Reader(body);
+    let uin = i64::from_be_bytes(reader.take_array()?);
+    let count = u32::from_be_bytes(reader.take_array()?);
+    let mut files = vec![];
+    for _ in 0..count {
+        let len = u16::from_be_bytes(reader.take_array()?) as usize;
+        let name = std::str::from_utf8(reader.take(len)?).context("会话快照已损坏")?;
+        ensure!(
+            !name.is_empty()
+                && name
+                    .split('/')
+                    .all(|part| !part.is_empty() && part != ".." && !part.contains([':', '\\'])),
+            "会话快照中包含非法的文件名 {}",
+            name
+        );
+        let len = u32::from_be_bytes(reader.take_array()?) as usize;
+        let data = reader.take(len)?.to_vec();
+        files.push((name.split('/').collect(), data));
+    }
+    ensure!(reader.0.is_empty(), "会话快照已损坏");
+    Ok((uin, files))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        ensure!(self.0.len() >= len, "会话快照已损坏");
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
@@ -0,0 +1,154 @@
+//! 连接状态与统计信息。
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+use ricq::{client::NetworkStatus, Client};
+
+use crate::OfflineReason;
+
+/// 连接状态。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionStatus {
+    /// 在线。
+    Online,
+    /// 正在重连。
+    Connecting,
+    /// 已下线，包含下线原因。
+    Offline(OfflineReason),
+}
+
+/// 连接统计信息。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// 成功重连的次数。
+    pub reconnect_count: usize,
+    /// 最近一次登录或重连成功的时间。
+    pub connected_at: SystemTime,
+    /// 最近一次心跳成功的时间。
+    ///
+    /// 只记录通过 [`LoginOptions::heartbeat`](crate::LoginOptions::heartbeat) 开启的心跳检测，
+    /// 未开启或尚未成功时为 `None`。
+    pub last_heartbeat: Option<SystemTime>,
+}
+
+pub(crate) struct ConnectionState {
+    reconnecting: AtomicBool,
+    reconnect_count: AtomicUsize,
+    connected_at: Mutex<SystemTime>,
+    server: Mutex<Option<SocketAddr>>,
+    last_heartbeat: Mutex<Option<SystemTime>>,
+}
+
+impl ConnectionState {
+    pub(crate) fn new(server: Option<SocketAddr>) -> Self {
+        Self {
+            reconnecting: AtomicBool::new(false),
+            reconnect_count: AtomicUsize::new(0),
+            connected_at: Mutex::new(SystemTime::now()),
+            server: Mutex::new(server),
+            last_heartbeat: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn set_reconnecting(&self, reconnecting: bool) {
+        self.reconnecting.store(reconnecting, Ordering::SeqCst);
+    }
+
+    pub(crate) fn reconnected(&self, server: Option<SocketAddr>) {
+        self.reconnect_count.fetch_add(1, Ordering::SeqCst);
+        *self
+            .connected_at
+            .lock()
+            .expect("Failed locking connected_at") = SystemTime::now();
+        *self.server.lock().expect("Failed locking server") = server;
+    }
+
+    pub(crate) fn heartbeat_succeeded(&self) {
+        *self
+            .last_heartbeat
+            .lock()
+            .expect("Failed locking last_heartbeat") = Some(SystemTime::now());
+    }
+}
+
+/// 连接状态的监视器。
+///
+/// 通过 [`AliveHandle::monitor`](crate::AliveHandle::monitor) 获取。
+/// 监视器可以克隆，并且在 [`AliveHandle::auto_reconnect`](crate::AliveHandle::auto_reconnect)
+/// 消耗句柄后仍然可用，适合在健康检查接口中使用。
+///
+/// # Examples
+///
+/// ```no_run
+/// use chocho_login::ConnectionStatus;
+///
+/// # async fn _f(alive: chocho_login::AliveHandle) -> anyhow::Result<()> {
+/// let monitor = alive.monitor();
+/// tokio::spawn(async move {
+///     loop {
+///         tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+///         let stats = monitor.stats();
+///         match monitor.status() {
+///             ConnectionStatus::Online => println!("在线，已重连 {} 次", stats.reconnect_count),
+///             status => println!("连接异常：{:?}", status),
+///         }
+///     }
+/// });
+/// alive.auto_reconnect().await?;
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ConnectionMonitor {
+    client: Arc<Client>,
+    state: Arc<ConnectionState>,
+}
+
+impl ConnectionMonitor {
+    pub(crate) fn new(client: Arc<Client>, state: Arc<ConnectionState>) -> Self {
+        Self { client, state }
+    }
+
+    /// 当前的连接状态。
+    pub fn status(&self) -> ConnectionStatus {
+        if self.state.reconnecting.load(Ordering::SeqCst) {
+            return ConnectionStatus::Connecting;
+        }
+        match self.client.get_status() {
+            s if s == NetworkStatus::Running as u8 => ConnectionStatus::Online,
+            s => ConnectionStatus::Offline(OfflineReason::from_status(s)),
+        }
+    }
+
+    /// 当前连接的服务器地址。
+    ///
+    /// 使用默认连接器或通过 [`LoginOptions::servers`](crate::LoginOptions::servers) 指定服务器列表时才能获取，
+    /// 使用 [`LoginOptions::connector`](crate::LoginOptions::connector) 指定的自定义连接器时返回 `None`。
+    pub fn server(&self) -> Option<SocketAddr> {
+        *self.state.server.lock().expect("Failed locking server")
+    }
+
+    /// 连接统计信息。
+    pub fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            reconnect_count: self.state.reconnect_count.load(Ordering::SeqCst),
+            connected_at: *self
+                .state
+                .connected_at
+                .lock()
+                .expect("Failed locking connected_at"),
+            last_heartbeat: *self
+                .state
+                .last_heartbeat
+                .lock()
+                .expect("Failed locking last_heartbeat"),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! token 存储。
+//!
+//! 登录成功后，chocho 会保存 token，用于下次启动时免密登录和断线重连。
+//! 默认使用 [`FileTokenStore`] 将 token 保存在数据文件夹中，也可以实现 [`TokenStore`]
+//! 并通过 [`LoginOptions::token_store`](crate::LoginOptions::token_store) 使用其他存储后端。
+//!
+//! # Examples
+//!
+//! ```
+//! use std::{collections::HashMap, sync::Mutex};
+//! use anyhow::Result;
+//! use chocho_login::{token::TokenStore, LoginOptions};
+//! use ricq::client::Token;
+//!
+//! /// 仅保存在内存中的 token。
+//! #[derive(Default)]
+//! struct MemoryTokenStore(Mutex<HashMap<i64, Token>>);
+//!
+//! #[async_trait::async_trait]
+//! impl TokenStore for MemoryTokenStore {
+//!     async fn load(&self, uin: i64) -> Result<Option<Token>> {
+//!         Ok(self.0.lock().unwrap().get(&uin).cloned())
+//!     }
+//!
+//!     async fn save(&self, uin: i64, token: &Token) -> Result<()> {
+//!         self.0.lock().unwrap().insert(uin, token.clone());
+//!         Ok(())
+//!     }
+//!
+//!     async fn remove(&self, uin: i64) -> Result<()> {
+//!         self.0.lock().unwrap().remove(&uin);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let options = LoginOptions::new().token_store(MemoryTokenStore::default());
+//! ```
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use ricq::client::Token;
+
+use crate::fs::atomic_write;
+use crate::layout::{DataLayout, DefaultLayout};
+
+/// token 存储后端。
+#[async_trait::async_trait]
+pub trait TokenStore: Send + Sync {
+    /// 读取账号的 token，不存在时返回 `None`。
+    async fn load(&self, uin: i64) -> Result<Option<Token>>;
+
+    /// 保存账号的 token。
+    async fn save(&self, uin: i64, token: &Token) -> Result<()>;
+
+    /// 删除账号的 token。token 失效时会调用此方法。
+    ///
+    /// 默认不做任何操作。
+    async fn remove(&self, uin: i64) -> Result<()> {
+        let _ = uin;
+        Ok(())
+    }
+}
+
+/// 不保存 token 的存储，每次启动都需要重新登录。
+///
+/// 适用于无状态部署，参见 [`login_with_device`](crate::login_with_device)。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTokenStore;
+
+#[async_trait::async_trait]
+impl TokenStore for NoTokenStore {
+    async fn load(&self, _uin: i64) -> Result<Option<Token>> {
+        Ok(None)
+    }
+
+    async fn save(&self, _uin: i64, _token: &Token) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 加密或压缩后的 token 文件的魔数。
+pub(crate) const MAGIC: &[u8] = b"CHOCHO\x00TOKEN";
+
+/// 基于文件的 token 存储，token 默认保存在 `{data_folder}/{uin}/token.json`。
+///
+/// 开启 `secure-store` 特性后，可以对 token 文件进行加密和压缩。读取时会自动识别文件是否经过加密或压缩，因此开启加密后，仍然可以读取之前保存的明文 token。
+#[derive(Clone)]
+pub struct FileTokenStore {
+    layout: Arc<dyn DataLayout>,
+    #[cfg(feature = "secure-store")]
+    key: Option<[u8; 32]>,
+    #[cfg(feature = "secure-store")]
+    compress: bool,
+}
+
+impl FileTokenStore {
+    /// 创建文件存储。
+    pub fn new(data_folder: impl Into<PathBuf>) -> Self {
+        Self::with_layout(Arc::new(DefaultLayout::new(data_folder)))
+    }
+
+    /// 创建文件存储，按 `layout` 决定 token 文件的路径。
+    pub fn with_layout(layout: Arc<dyn DataLayout>) -> Self {
+        Self {
+            layout,
+            #[cfg(feature = "secure-store")]
+            key: None,
+            #[cfg(feature = "secure-store")]
+            compress: false,
+        }
+    }
+
+    /// 使用 AES-256-GCM 加密 token 文件。
+    #[cfg(feature = "secure-store")]
+    pub fn encryption_key(mut self, key: [u8; 32]) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// 从环境变量读取加密密钥，密钥为 64 位十六进制字符串。
+    ///
+    /// 环境变量不存在或格式错误时返回错误。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho_login::{token::FileTokenStore, LoginOptions};
+    ///
+    /// # fn _f() -> anyhow::Result<()> {
+    /// let store = FileTokenStore::new("./data")
+    ///     .encryption_key_from_env("CHOCHO_TOKEN_KEY")?
+    ///     .compress(true);
+    /// let options = LoginOptions::new().token_store(store);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "secure-store")]
+    pub fn encryption_key_from_env(self, var: &str) -> Result<Self> {
+        let value = std::env::var(var)
+            .map_err(|_| anyhow::anyhow!("未设置环境变量 {}，无法读取 token 加密密钥", var))?;
+        let key = hex::decode(value.trim())
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .ok_or_else(|| anyhow::anyhow!("环境变量 {} 应为 64 位十六进制字符串", var))?;
+        Ok(self.encryption_key(key))
+    }
+
+    /// 是否使用 gzip 压缩 token 文件，默认为 `false`。
+    #[cfg(feature = "secure-store")]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    fn token_path(&self, uin: i64) -> PathBuf {
+        self.layout.token_path(uin)
+    }
+
+    fn encode(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        #[cfg(feature = "secure-store")]
+        {
+            crate::secure::encode(&data, self.key.as_ref(), self.compress)
+        }
+        #[cfg(not(feature = "secure-store"))]
+        {
+            Ok(data)
+        }
+    }
+
+    fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        if !data.starts_with(MAGIC) {
+            return Ok(data);
+        }
+        #[cfg(feature = "secure-store")]
+        {
+            crate::secure::decode(&data, self.key.as_ref())
+        }
+        #[cfg(not(feature = "secure-store"))]
+        {
+            anyhow::bail!("token 文件经过加密或压缩，需要开启 `secure-store` 特性才能读取")
+        }
+    }
+}
+
+impl std::fmt::Debug for FileTokenStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileTokenStore").finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenStore for FileTokenStore {
+    async fn load(&self, uin: i64) -> Result<Option<Token>> {
+        let token_path = self.token_path(uin);
+        if !token_path.exists() {
+            return Ok(None);
+        }
+        let token = self.decode(tokio::fs::read(token_path).await?)?;
+        Ok(Some(serde_json::from_slice(&token)?))
+    }
+
+    async fn save(&self, uin: i64, token: &Token) -> Result<()> {
+        let token = self.encode(serde_json::to_vec(token)?)?;
+        let token_path = self.token_path(uin);
+        if let Some(parent) = token_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        atomic_write(token_path, token).await
+    }
+
+    async fn remove(&self, uin: i64) -> Result<()> {
+        let token_path = self.token_path(uin);
+        if token_path.exists() {
+            tokio::fs::remove_file(token_path).await?;
+        }
+        Ok(())
+    }
+}
@@ -5,7 +5,10 @@
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{meta::ParseNestedMeta, parse_macro_input, Expr, ItemFn};
+use syn::{
+    meta::ParseNestedMeta, parse_macro_input, Expr, ExprArray, ExprLit, ItemFn, Lit, LitBool,
+    LitStr,
+};
 
 /// 声明 `chocho` 的主函数。
 ///
@@ -25,7 +28,7 @@ use syn::{meta::ParseNestedMeta, parse_macro_input, Expr, ItemFn};
 ///
 /// `chocho` 的生命周期分为三个阶段：
 ///
-/// 1. 初始化 `tracing-subscriber` 的日志输出，登录账号；
+/// 1. 初始化 `tracing-subscriber` 的日志输出（可以通过 `init_tracing` 关闭），登录账号，并缓存账号的 QQ 号（参见 `ClientExt::self_uin`）；
 /// 2. 执行主函数。
 /// 3. 开始自动断线重连。
 ///
@@ -33,6 +36,23 @@ use syn::{meta::ParseNestedMeta, parse_macro_input, Expr, ItemFn};
 ///
 /// - `data_folder`：指定 `chocho` 的数据文件夹路径。默认为 `./bots`。
 /// - `handler`：指定 `chocho` 的事件处理器。默认为 `chocho::ricq::handler::DefaultHandler`。
+///   事件处理器会被 [`chocho::event::Dispatcher`] 包装，以支持 `wait_for_message` 等功能。
+/// - `middleware`：指定消息中间件的数组，例如 `middleware = [LogMw, RateLimitMw::default()]`，
+///   按顺序组成中间件链，参见 [`chocho::event::MessageMiddleware`]。默认不使用中间件。
+/// - `flavor`：指定 tokio 运行时的类型，可选 `"multi_thread"` 和 `"current_thread"`。默认为 `"multi_thread"`。
+/// - `worker_threads`：指定多线程运行时的工作线程数。默认为 CPU 核心数。不能与 `flavor = "current_thread"` 同时使用。
+/// - `uin`：指定登录的账号。默认在启动时交互式询问。
+/// - `login_method`：指定登录方式，为 [`chocho::LoginMethod`] 类型的表达式，例如
+///   `chocho::LoginMethod::Token { token: std::env::var("CHOCHO_TOKEN")? }`。默认在启动时交互式询问。
+/// - `setup`：指定在登录前执行的初始化函数，其返回值作为主函数的第二个参数，参见下文。
+/// - `shutdown_signals`：指定触发退出的信号，收到信号后执行 finalizer 并退出。默认为 `["SIGINT"]`，即只监听 Ctrl-C。
+///   可选 `"SIGINT"`、`"SIGTERM"`、`"SIGHUP"`、`"SIGQUIT"`、`"SIGUSR1"`、`"SIGUSR2"`，除 `"SIGINT"` 外均为 Unix 专有信号，在其它平台上会被忽略。
+///   在执行 finalizer 期间再次收到信号时，立即以状态码 1 强制退出，参见 `chocho::lifespan::shutdown`。
+/// - `shutdown_timeout`：指定收到退出信号后执行 finalizer 的总时限，为 `Duration` 或 `Option<Duration>` 类型的表达式。
+///   超时后以状态码 1 强制退出，`None` 表示不限制。默认为 30 秒。
+/// - `init_tracing`：是否初始化内置的 `tracing-subscriber` 日志输出。默认为 `true`。
+///   设为 `false` 时不做任何初始化，可以在 `setup` 中自行配置订阅器，使登录过程的日志也能输出。
+///   已经设置过全局订阅器时，内置的初始化会被跳过，不会 panic。
 ///
 /// 可以用以下语法指定属性：
 /// ```,no_run
@@ -71,6 +91,94 @@ use syn::{meta::ParseNestedMeta, parse_macro_input, Expr, ItemFn};
 ///     // ...
 /// }
 /// ```
+///
+/// 在资源受限的环境中，可以使用单线程运行时：
+///
+/// ```,no_run
+/// # use chocho::prelude::*;
+/// #[chocho::main(flavor = "current_thread")]
+/// async fn main(client: RQClient) {
+///     // ...
+/// }
+/// ```
+///
+/// 在容器中运行时，可以同时处理 `SIGTERM`：
+///
+/// ```,no_run
+/// # use chocho::prelude::*;
+/// #[chocho::main(shutdown_signals = ["SIGINT", "SIGTERM"])]
+/// async fn main(client: RQClient) {
+///     // ...
+/// }
+/// ```
+///
+/// 如果需要在登录前解析命令行参数或读取配置，可以用 `setup` 指定一个初始化函数，
+/// 主函数的签名变为 `async fn main(client: RQClient, state: T)`，其中 `T` 是初始化函数的返回值类型。
+/// 初始化函数不接受参数，可以返回 `T` 或 `Result<T, E>`，返回错误时程序直接退出，不会登录：
+///
+/// ```,no_run
+/// # use chocho::prelude::*;
+/// struct AppState {
+///     admin: i64,
+/// }
+///
+/// fn setup() -> Result<AppState, std::num::ParseIntError> {
+///     let admin = std::env::args().nth(1).unwrap_or_default().parse()?;
+///     Ok(AppState { admin })
+/// }
+///
+/// #[chocho::main(setup = setup)]
+/// async fn main(client: RQClient, state: AppState) {
+///     // ...
+/// }
+/// ```
+///
+/// 主函数有第二个参数时必须指定 `setup`，反之亦然：
+///
+/// ```,compile_fail
+/// # use chocho::prelude::*;
+/// #[chocho::main]
+/// async fn main(client: RQClient, state: String) {
+///     // ...
+/// }
+/// ```
+///
+/// 限制退出清理的时间，保证进程最终会退出：
+///
+/// ```,no_run
+/// # use chocho::prelude::*;
+/// #[chocho::main(shutdown_timeout = std::time::Duration::from_secs(10))]
+/// async fn main(client: RQClient) {
+///     // ...
+/// }
+/// ```
+///
+/// 使用自定义的日志订阅器：
+///
+/// ```,no_run
+/// # use chocho::prelude::*;
+/// fn setup() {
+///     chocho::tracing_subscriber::fmt()
+///         .with_target(false)
+///         .with_thread_ids(true)
+///         .init();
+/// }
+///
+/// #[chocho::main(init_tracing = false, setup = setup)]
+/// async fn main(client: RQClient, _: ()) {
+///     // ...
+/// }
+/// ```
+///
+/// 单线程运行时不能指定工作线程数：
+///
+/// ```,compile_fail
+/// # use chocho::prelude::*;
+/// #[chocho::main(flavor = "current_thread", worker_threads = 4)]
+/// async fn main(client: RQClient) {
+///     // ...
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
     let ItemFn {
@@ -89,8 +197,15 @@ pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
 
     let mut data_folder = quote! { "./bots".to_string() };
     let mut handler = quote! { ::chocho::ricq::handler::DefaultHandler };
+    let mut middlewares = vec![];
     let mut uin = quote! { ::std::option::Option::None };
     let mut login_method = quote! { ::std::option::Option::None };
+    let mut flavor = None;
+    let mut worker_threads = None;
+    let mut shutdown_signals = vec![LitStr::new("SIGINT", proc_macro::Span::call_site().into())];
+    let mut setup = None;
+    let mut shutdown_timeout = quote! { ::std::time::Duration::from_secs(30) };
+    let mut init_tracing = true;
     let mut qsign = quote! { || -> ::std::result::Result<::std::sync::Arc<::chocho::QSignClient>, Box<dyn ::std::error::Error>> {
         Ok(::std::sync::Arc::new(::chocho::QSignClient::new(
             "http://localhost:11451".to_string(),
@@ -106,12 +221,51 @@ pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
         } else if meta.path.is_ident("handler") {
             let value: Expr = meta.value()?.parse()?;
             handler = quote! { #value };
+        } else if meta.path.is_ident("middleware") {
+            let value: ExprArray = meta.value()?.parse()?;
+            middlewares = value.elems.into_iter().collect();
         } else if meta.path.is_ident("uin") {
             let value: Expr = meta.value()?.parse()?;
             uin = quote! { ::std::option::Option::Some(#value) };
         } else if meta.path.is_ident("login_method") {
             let value: Expr = meta.value()?.parse()?;
             login_method = quote! { ::std::option::Option::Some(#value) };
+        } else if meta.path.is_ident("flavor") {
+            let value: LitStr = meta.value()?.parse()?;
+            match value.value().as_str() {
+                "multi_thread" | "current_thread" => flavor = Some(value.value()),
+                other => {
+                    return Err(meta.error(format!(
+                        "unknown flavor `{}`, expected `multi_thread` or `current_thread`",
+                        other
+                    )))
+                }
+            }
+        } else if meta.path.is_ident("worker_threads") {
+            let value: Expr = meta.value()?.parse()?;
+            worker_threads = Some(value);
+        } else if meta.path.is_ident("shutdown_signals") {
+            let value: ExprArray = meta.value()?.parse()?;
+            shutdown_signals = value
+                .elems
+                .into_iter()
+                .map(|elem| match elem {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(signal),
+                        ..
+                    }) => Ok(signal),
+                    _ => Err(meta.error("expected a string literal such as `\"SIGTERM\"`")),
+                })
+                .collect::<syn::Result<_>>()?;
+        } else if meta.path.is_ident("setup") {
+            let value: Expr = meta.value()?.parse()?;
+            setup = Some(value);
+        } else if meta.path.is_ident("shutdown_timeout") {
+            let value: Expr = meta.value()?.parse()?;
+            shutdown_timeout = quote! { #value };
+        } else if meta.path.is_ident("init_tracing") {
+            let value: LitBool = meta.value()?.parse()?;
+            init_tracing = value.value;
         } else if meta.path.is_ident("qsign") {
             let value: Expr = meta.value()?.parse()?;
             qsign = quote! { #value };
@@ -134,6 +288,110 @@ pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     }
 
+    let runtime = match (flavor.as_deref(), worker_threads) {
+        (Some("current_thread"), Some(_)) => {
+            panic!("`worker_threads` cannot be used with `flavor = \"current_thread\"`")
+        }
+        (Some("current_thread"), None) => {
+            quote! { ::chocho::tokio::runtime::Builder::new_current_thread() }
+        }
+        (_, Some(worker_threads)) => quote! {
+            ::chocho::tokio::runtime::Builder::new_multi_thread().worker_threads(#worker_threads)
+        },
+        (_, None) => quote! { ::chocho::tokio::runtime::Builder::new_multi_thread() },
+    };
+
+    let shutdown = shutdown_signals.iter().map(|signal| {
+        let (cfg, listen) = match signal.value().as_str() {
+            "SIGINT" => (
+                quote! {},
+                quote! {
+                    loop {
+                        ::chocho::tokio::signal::ctrl_c().await.unwrap();
+                        ::chocho::tokio::spawn(::chocho::lifespan::shutdown(shutdown_timeout));
+                    }
+                },
+            ),
+            name => {
+                let kind = match name {
+                    "SIGTERM" => quote! { terminate },
+                    "SIGHUP" => quote! { hangup },
+                    "SIGQUIT" => quote! { quit },
+                    "SIGUSR1" => quote! { user_defined1 },
+                    "SIGUSR2" => quote! { user_defined2 },
+                    other => {
+                        return syn::Error::new(
+                            signal.span(),
+                            format!("unsupported shutdown signal `{}`", other),
+                        )
+                        .to_compile_error()
+                    }
+                };
+                (
+                    quote! { #[cfg(unix)] },
+                    quote! {
+                        let mut signal = ::chocho::tokio::signal::unix::signal(
+                            ::chocho::tokio::signal::unix::SignalKind::#kind(),
+                        )
+                        .unwrap();
+                        while signal.recv().await.is_some() {
+                            ::chocho::tokio::spawn(::chocho::lifespan::shutdown(shutdown_timeout));
+                        }
+                    },
+                )
+            }
+        };
+        quote! {
+            #cfg
+            ::chocho::tokio::spawn(async move {
+                let shutdown_timeout: ::std::option::Option<::std::time::Duration> = (#shutdown_timeout).into();
+                #listen
+            });
+        }
+    });
+
+    let (setup, setup_arg) = match (sig.inputs.len(), setup) {
+        (1, None) => (quote! {}, quote! {}),
+        (2, Some(setup)) => (
+            quote! {
+                let state = __chocho_private::Wrap::wrap((#setup)())?;
+            },
+            quote! { , state },
+        ),
+        (2, None) => {
+            return syn::Error::new_spanned(
+                &sig.inputs,
+                "the second argument of `#[chocho::main]` requires `setup = ...`",
+            )
+            .to_compile_error()
+            .into()
+        }
+        (1, Some(setup)) => {
+            return syn::Error::new_spanned(
+                setup,
+                "`setup` requires the main function to take a second argument, e.g. `async fn main(client: RQClient, state: T)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+        _ => {
+            return syn::Error::new_spanned(
+                &sig.inputs,
+                "`#[chocho::main]` expects `async fn main(client: RQClient)` or `async fn main(client: RQClient, state: T)`",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let init_tracing = if init_tracing {
+        quote! {
+            let _ = ::chocho::tracing_subscriber::fmt::try_init();
+        }
+    } else {
+        quote! {}
+    };
+
     let ident = sig.ident;
     let args = sig.inputs;
     let output = sig.output;
@@ -145,7 +403,7 @@ pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
                     Output = ::std::result::Result<T, ::std::boxed::Box<dyn ::std::error::Error>>
                 >
             ) -> ::std::result::Result<T, Box<dyn ::std::error::Error>> {
-                ::chocho::tokio::runtime::Builder::new_multi_thread()
+                #runtime
                     .enable_all()
                     .build()
                     .expect("Failed building the Runtime")
@@ -178,18 +436,24 @@ pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
                 async fn #ident(#args) #output {
                     #block
                 }
-                ::chocho::tracing_subscriber::fmt::init();
-                ::chocho::tokio::spawn(async {
-                    ::chocho::tokio::signal::ctrl_c().await.unwrap();
-                    ::chocho::lifespan::do_finalize().await;
-                    ::std::process::exit(0);
-                });
+                #init_tracing
+                #setup
+                #(#shutdown)*
                 let qsign_client = {
                     let builder = #qsign;
                     builder()?
                 };
-                let (client, alive) = ::chocho::login(#data_folder, #handler, #uin, #login_method, qsign_client).await?;
-                let result = __chocho_private::Wrap::wrap(#ident(client).await)?;
+                let data_folder = #data_folder;
+                let (client, alive) = ::chocho::login(data_folder.clone(), ::chocho::event::Dispatcher::new(#handler)#(.middleware(#middlewares))*, #uin, #login_method, qsign_client).await?;
+                ::chocho::client::account::cache_self_uin(&client).await;
+                ::chocho::event::ScopeExt::persist_scopes(
+                    &client,
+                    ::chocho::layout::DataLayout::scopes_path(
+                        &::chocho::layout::DefaultLayout::new(&data_folder),
+                        client.uin().await,
+                    ),
+                )?;
+                let result = __chocho_private::Wrap::wrap(#ident(client #setup_arg).await)?;
                 alive.auto_reconnect().await?;
                 ::chocho::lifespan::do_finalize().await;
                 Ok(result)
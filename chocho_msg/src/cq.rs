@@ -0,0 +1,126 @@
+//! CQ 码的解析与生成。
+//!
+//! 支持 `text`（纯文本）、`at`、`face`、`image` 和 `reply` 段，消息段的含义与 [OneBot 消息段](Message::to_onebot_segments)
+//! 相同。文本中的 `&`、`[`、`]`
+//! 以及参数中的 `,` 会按 OneBot 的规则转义。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::{msg, Message};
+//! use chocho_msg::elem::*;
+//!
+//! let msg = Message::from_cq_code("[CQ:at,qq=12345678] 你好[CQ:face,id=178]").unwrap();
+//! assert_eq!(msg.ats(), [12345678]);
+//! assert_eq!(msg.text(), " 你好");
+//! assert_eq!(msg.to_cq_code(), "[CQ:at,qq=12345678] 你好[CQ:face,id=178]");
+//!
+//! // 转义
+//! let msg = msg!["a&b[c]"];
+//! assert_eq!(msg.to_cq_code(), "a&amp;b&#91;c&#93;");
+//! assert_eq!(Message::from_cq_code("a&amp;b&#91;c&#93;").unwrap().text(), "a&b[c]");
+//!
+//! // 未知的 CQ 段
+//! let err = Message::from_cq_code("[CQ:record,file=1.amr]").unwrap_err();
+//! assert_eq!(err.to_string(), "不支持的消息段：record");
+//! ```
+
+use crate::{
+    segment::{Segment, SegmentError},
+    Message,
+};
+
+impl Message {
+    /// 从 CQ 码解析消息。
+    ///
+    /// 图片段优先从 `file`（`{md5}.image` 的形式）中读取 md5，`file` 不符合格式时，尝试从群图片的 `url`
+    /// 中读取。解析结果为引用服务器上已有图片的群图片。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::Message;
+    ///
+    /// let msg = Message::from_cq_code("[CQ:reply,id=42][CQ:at,qq=all] 开会").unwrap();
+    /// assert_eq!(msg.first_reply().unwrap().reply_seq, 42);
+    /// assert_eq!(msg.to_cq_code(), "[CQ:reply,id=42][CQ:at,qq=all] 开会");
+    ///
+    /// // 与 OneBot 消息段的解析结果一致
+    /// let url = "https://gchat.qpic.cn/gchatpic_new/0/0-0-0123456789ABCDEF0123456789ABCDEF/0?term=2";
+    /// let msg = Message::from_cq_code(&format!("[CQ:image,file=image.png,url={}]", url)).unwrap();
+    /// assert_eq!(msg.to_onebot_segments()[0]["data"]["file"], "0123456789abcdef0123456789abcdef.image");
+    ///
+    /// assert!(Message::from_cq_code("[CQ:at,qq=abc]").is_err());
+    /// assert!(Message::from_cq_code("[CQ:at,qq=123").is_err());
+    /// ```
+    pub fn from_cq_code(s: &str) -> Result<Message, SegmentError> {
+        let mut msg = Message::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("[CQ:") {
+            msg.push_segment(Segment::Text(unescape(&rest[..start])));
+            let end = rest[start..].find(']').ok_or(SegmentError::Unclosed)? + start;
+            msg.push_segment(parse_segment(&rest[start + 4..end])?);
+            rest = &rest[end + 1..];
+        }
+        msg.push_segment(Segment::Text(unescape(rest)));
+        Ok(msg)
+    }
+
+    /// 将消息转换为 CQ 码。
+    ///
+    /// 不支持的元素会以其文本形式输出。
+    pub fn to_cq_code(&self) -> String {
+        let mut out = String::new();
+        for segment in self.to_segments() {
+            match segment {
+                Segment::Text(text) => out.push_str(&escape(&text, false)),
+                segment => {
+                    out.push_str("[CQ:");
+                    out.push_str(segment.kind());
+                    for (key, value) in segment.params() {
+                        out.push(',');
+                        out.push_str(key);
+                        out.push('=');
+                        out.push_str(&escape(&value, true));
+                    }
+                    out.push(']');
+                }
+            }
+        }
+        out
+    }
+}
+
+fn parse_segment(segment: &str) -> Result<Segment, SegmentError> {
+    let mut parts = segment.split(',');
+    let kind = parts.next().unwrap_or_default();
+    let params: Vec<(&str, String)> = parts
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key, unescape(value)))
+        .collect();
+    Segment::parse(kind, |name| {
+        params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+fn escape(s: &str, param: bool) -> String {
+    let s = s
+        .replace('&', "&amp;")
+        .replace('[', "&#91;")
+        .replace(']', "&#93;");
+    if param {
+        s.replace(',', "&#44;")
+    } else {
+        s
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&#44;", ",")
+        .replace("&#91;", "[")
+        .replace("&#93;", "]")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,341 @@
+//! 结构化实体提取。
+//!
+//! 从消息的文本元素中提取 URL、邮箱、手机号等实体，并收集消息中 At 的对象。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::msg;
+//! use chocho_msg::elem::At;
+//! use chocho_msg::entity::EntityKind;
+//!
+//! let msg = msg!["看看 https://github.com/Wybxc/chocho 吧", At::new(12345678)];
+//! let entities = msg.extract_entities();
+//! assert_eq!(entities.urls().collect::<Vec<_>>(), ["https://github.com/Wybxc/chocho"]);
+//! assert_eq!(entities.ats().collect::<Vec<_>>(), [12345678]);
+//!
+//! let url = &entities.iter().next().unwrap();
+//! assert_eq!(url.kind, EntityKind::Url);
+//! assert_eq!(&msg.to_string()[url.range.clone()], url.text);
+//! ```
+
+use std::ops::Range;
+
+use crate::{Message, RQElem};
+
+/// 实体类型。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityKind {
+    /// 网址。
+    Url,
+    /// 邮箱。
+    Email,
+    /// 疑似手机号。
+    Phone,
+    /// At 的对象。
+    At(i64),
+    /// 自定义实体。
+    Custom(String),
+}
+
+/// 从消息中提取出的实体。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entity {
+    /// 实体类型。
+    pub kind: EntityKind,
+    /// 实体的原始文本。
+    pub text: String,
+    /// 实体在消息文本（即 [`Message`] 的 `to_string()` 结果）中的字节范围。
+    pub range: Range<usize>,
+}
+
+/// 实体提取结果。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entities(pub Vec<Entity>);
+
+impl Entities {
+    /// 遍历所有实体，按出现位置排序。
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.0.iter()
+    }
+
+    /// 是否没有提取到任何实体。
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 遍历指定类型的实体。
+    pub fn of_kind<'a>(&'a self, kind: &'a EntityKind) -> impl Iterator<Item = &'a Entity> {
+        self.0.iter().filter(move |e| &e.kind == kind)
+    }
+
+    /// 所有网址。
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.of_kind(&EntityKind::Url).map(|e| e.text.as_str())
+    }
+
+    /// 所有邮箱。
+    pub fn emails(&self) -> impl Iterator<Item = &str> {
+        self.of_kind(&EntityKind::Email).map(|e| e.text.as_str())
+    }
+
+    /// 所有疑似手机号。
+    pub fn phones(&self) -> impl Iterator<Item = &str> {
+        self.of_kind(&EntityKind::Phone).map(|e| e.text.as_str())
+    }
+
+    /// 所有被 At 的 QQ 号。
+    pub fn ats(&self) -> impl Iterator<Item = i64> + '_ {
+        self.0.iter().filter_map(|e| match e.kind {
+            EntityKind::At(uin) => Some(uin),
+            _ => None,
+        })
+    }
+}
+
+impl IntoIterator for Entities {
+    type Item = Entity;
+    type IntoIter = std::vec::IntoIter<Entity>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// 实体提取规则。
+///
+/// 实现此 trait 并通过 [`EntityExtractor::with`] 注册，即可提取自定义实体。
+///
+/// # Examples
+///
+/// ```
+/// use std::ops::Range;
+/// use chocho_msg::msg;
+/// use chocho_msg::entity::{EntityExtractor, EntityKind, Extractor};
+///
+/// /// 提取 `#话题` 形式的标签。
+/// struct Hashtag;
+///
+/// impl Extractor for Hashtag {
+///     fn extract(&self, text: &str) -> Vec<(Range<usize>, EntityKind)> {
+///         let mut result = vec![];
+///         for (start, _) in text.match_indices('#') {
+///             let len = text[start + 1..]
+///                 .find(char::is_whitespace)
+///                 .unwrap_or(text.len() - start - 1);
+///             if len > 0 {
+///                 result.push((start..start + 1 + len, EntityKind::Custom("hashtag".into())));
+///             }
+///         }
+///         result
+///     }
+/// }
+///
+/// let extractor = EntityExtractor::new().with(Hashtag);
+/// let entities = extractor.extract(&msg!["今天的 #chocho 更新"]);
+/// assert_eq!(entities.iter().next().unwrap().text, "#chocho");
+/// ```
+pub trait Extractor: Send + Sync {
+    /// 从一段文本中提取实体，返回实体在文本中的字节范围和类型。
+    fn extract(&self, text: &str) -> Vec<(Range<usize>, EntityKind)>;
+}
+
+/// 实体提取器，由若干 [`Extractor`] 组成。
+///
+/// 同一段文本中，先注册的规则优先；与已提取实体重叠的结果会被丢弃。
+pub struct EntityExtractor {
+    extractors: Vec<Box<dyn Extractor>>,
+}
+
+impl Default for EntityExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntityExtractor {
+    /// 创建包含内置规则（网址、邮箱、手机号）的提取器。
+    pub fn new() -> Self {
+        Self {
+            extractors: vec![
+                Box::new(UrlExtractor),
+                Box::new(EmailExtractor),
+                Box::new(PhoneExtractor),
+            ],
+        }
+    }
+
+    /// 创建不含任何规则的提取器。
+    pub fn empty() -> Self {
+        Self { extractors: vec![] }
+    }
+
+    /// 注册一个提取规则。
+    pub fn with(mut self, extractor: impl Extractor + 'static) -> Self {
+        self.extractors.push(Box::new(extractor));
+        self
+    }
+
+    /// 从消息中提取实体。
+    pub fn extract(&self, message: &Message) -> Entities {
+        let mut entities = vec![];
+        let mut offset = 0;
+        for elem in message.elems() {
+            let display = elem.to_string();
+            match elem {
+                RQElem::Text(text) => {
+                    let mut found: Vec<(Range<usize>, EntityKind)> = vec![];
+                    for extractor in &self.extractors {
+                        for (range, kind) in extractor.extract(&text.content) {
+                            if !found
+                                .iter()
+                                .any(|(r, _)| r.start < range.end && range.start < r.end)
+                            {
+                                found.push((range, kind));
+                            }
+                        }
+                    }
+                    found.sort_by_key(|(r, _)| r.start);
+                    entities.extend(found.into_iter().map(|(range, kind)| Entity {
+                        kind,
+                        text: text.content[range.clone()].to_string(),
+                        range: range.start + offset..range.end + offset,
+                    }));
+                }
+                RQElem::At(at) => entities.push(Entity {
+                    kind: EntityKind::At(at.target),
+                    text: display.clone(),
+                    range: offset..offset + display.len(),
+                }),
+                _ => {}
+            }
+            offset += display.len();
+        }
+        Entities(entities)
+    }
+}
+
+impl Message {
+    /// 使用内置规则提取消息中的实体。
+    ///
+    /// 内置规则包括网址、邮箱、疑似手机号，以及消息中 At 的对象。
+    /// 如需自定义规则，请使用 [`EntityExtractor`]。
+    pub fn extract_entities(&self) -> Entities {
+        EntityExtractor::new().extract(self)
+    }
+}
+
+/// 网址提取规则，识别 `http://` 和 `https://` 开头的链接。
+pub struct UrlExtractor;
+
+impl Extractor for UrlExtractor {
+    fn extract(&self, text: &str) -> Vec<(Range<usize>, EntityKind)> {
+        let mut result = vec![];
+        let mut pos = 0;
+        while let Some(found) = text[pos..].find("http") {
+            let start = pos + found;
+            let rest = &text[start..];
+            let scheme = if rest.starts_with("https://") {
+                "https://".len()
+            } else if rest.starts_with("http://") {
+                "http://".len()
+            } else {
+                pos = start + "http".len();
+                continue;
+            };
+            let len = rest[scheme..]
+                .find(|c: char| !is_url_char(c))
+                .unwrap_or(rest.len() - scheme);
+            let mut end = start + scheme + len;
+            // 去掉末尾的标点，它们通常不属于链接
+            while end > start + scheme && text[..end].ends_with(['.', ',', '!', '?', ')', ';', ':'])
+            {
+                end -= 1;
+            }
+            if end > start + scheme {
+                result.push((start..end, EntityKind::Url));
+            }
+            pos = end.max(start + scheme);
+        }
+        result
+    }
+}
+
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c)
+}
+
+/// 邮箱提取规则。
+pub struct EmailExtractor;
+
+impl Extractor for EmailExtractor {
+    fn extract(&self, text: &str) -> Vec<(Range<usize>, EntityKind)> {
+        let bytes = text.as_bytes();
+        let is_local = |b: u8| b.is_ascii_alphanumeric() || b"._%+-".contains(&b);
+        let is_domain = |b: u8| b.is_ascii_alphanumeric() || b".-".contains(&b);
+
+        let mut result = vec![];
+        let mut last_end = 0;
+        for (at, _) in text.match_indices('@') {
+            if at < last_end {
+                continue;
+            }
+            let mut start = at;
+            while start > last_end && is_local(bytes[start - 1]) {
+                start -= 1;
+            }
+            let mut end = at + 1;
+            while end < bytes.len() && is_domain(bytes[end]) {
+                end += 1;
+            }
+            while end > at + 1 && bytes[end - 1] == b'.' {
+                end -= 1;
+            }
+            let domain = &text[at + 1..end];
+            let valid_tld = domain
+                .rsplit_once('.')
+                .map(|(host, tld)| {
+                    !host.is_empty()
+                        && tld.len() >= 2
+                        && tld.bytes().all(|b| b.is_ascii_alphabetic())
+                })
+                .unwrap_or(false);
+            if start < at && valid_tld {
+                result.push((start..end, EntityKind::Email));
+                last_end = end;
+            }
+        }
+        result
+    }
+}
+
+/// 疑似手机号提取规则，识别中国大陆的 11 位手机号（可带 `+86` 前缀）。
+pub struct PhoneExtractor;
+
+impl Extractor for PhoneExtractor {
+    fn extract(&self, text: &str) -> Vec<(Range<usize>, EntityKind)> {
+        let bytes = text.as_bytes();
+        let mut result = vec![];
+        let mut i = 0;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            let (start, digits) = match &bytes[start..i] {
+                [b'8', b'6', rest @ ..] if start > 0 && bytes[start - 1] == b'+' => {
+                    (start - 1, rest)
+                }
+                digits => (start, digits),
+            };
+            if digits.len() == 11 && digits[0] == b'1' && (b'3'..=b'9').contains(&digits[1]) {
+                result.push((start..i, EntityKind::Phone));
+            }
+        }
+        result
+    }
+}
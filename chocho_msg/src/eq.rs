@@ -0,0 +1,128 @@
+//! 消息的语义相等性。
+
+use ricq::msg::elem::{FlashImage, Text};
+
+use crate::{
+    image::{Content, Image},
+    Message, RQElem,
+};
+
+/// 语义化的相等性比较。
+///
+/// 只比较以下关键字段，忽略 protobuf 中的其它内部字段：
+///
+/// - 回复：原消息的序号和发送者；
+/// - 文本：文本内容，相邻的文本会先合并；
+/// - At：被 At 的 QQ 号（不比较显示名称）；
+/// - 表情：表情 id；
+/// - 商城表情：表情 id 和表情包 id；
+/// - 骰子、猜拳：结果；
+/// - 小程序、富文本卡片：卡片内容及服务 id；
+/// - 图片、闪照：图片的 md5 及图片类型；
+/// - 视频：视频的 md5；
+/// - 待上传的图片：图片数据；
+/// - 其它元素：完整的原始内容。
+///
+/// 匿名信息和消息元信息不参与比较。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_msg::{msg, Message};
+/// use chocho_msg::elem::*;
+///
+/// assert_eq!(msg!["你好", At::new(12345678)], msg!["你", "好", At::new(12345678)]);
+/// assert_ne!(msg!["你好"], msg!["你好", Face::new(178)]);
+///
+/// // At 的显示名称不参与比较
+/// let at = At { target: 12345678, display: "@小明".to_string() };
+/// assert_eq!(msg![at], msg![At::new(12345678)]);
+///
+/// // 只有内部字段不同的文本视为相等
+/// let a = msg!["你好"];
+/// let mut b = msg!["你好"];
+/// if let ricq::msg::MessageElem::Text(text) = &mut b.orig_elems[0] {
+///     text.pb_reserve = Some(vec![1, 2, 3]);
+/// }
+/// assert_eq!(a, b);
+///
+/// // 消息去重
+/// let mut unique: Vec<Message> = vec![];
+/// for message in [msg!["a"], msg!["b"], msg!["a"]] {
+///     if !unique.contains(&message) {
+///         unique.push(message);
+///     }
+/// }
+/// assert_eq!(unique.len(), 2);
+/// ```
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        let reply_eq = match (&self.reply, &other.reply) {
+            (Some(a), Some(b)) => a.reply_seq == b.reply_seq && a.sender == b.sender,
+            (None, None) => true,
+            _ => false,
+        };
+        if !reply_eq {
+            return false;
+        }
+        let a = normalized_elems(self);
+        let b = normalized_elems(other);
+        a.len() == b.len()
+            && a.iter().zip(&b).all(|(a, b)| match (a, b) {
+                (Ok(a), Ok(b)) => elem_eq(a, b),
+                (Err(a), Err(b)) => a == b,
+                _ => false,
+            })
+    }
+}
+
+impl Eq for Message {}
+
+/// 合并相邻文本后的消息元素，待上传的图片以 `Err` 表示。
+fn normalized_elems(msg: &Message) -> Vec<Result<RQElem, &Image>> {
+    let mut elems: Vec<Result<RQElem, &Image>> = vec![];
+    for content in msg.contents() {
+        let elem = match content {
+            Content::Elem(elem) => RQElem::from(elem.clone()),
+            Content::Image(image) => {
+                elems.push(Err(image));
+                continue;
+            }
+        };
+        match (elems.last_mut(), elem) {
+            (Some(Ok(RQElem::Text(last))), RQElem::Text(text)) => {
+                last.content.push_str(&text.content)
+            }
+            (_, RQElem::Text(Text { content, .. })) if content.is_empty() => {}
+            (_, elem) => elems.push(Ok(elem)),
+        }
+    }
+    elems
+}
+
+fn elem_eq(a: &RQElem, b: &RQElem) -> bool {
+    match (a, b) {
+        (RQElem::Text(a), RQElem::Text(b)) => a.content == b.content,
+        (RQElem::At(a), RQElem::At(b)) => a.target == b.target,
+        (RQElem::Face(a), RQElem::Face(b)) => a.index == b.index,
+        (RQElem::MarketFace(a), RQElem::MarketFace(b)) => {
+            a.face_id == b.face_id && a.tab_id == b.tab_id
+        }
+        (RQElem::Dice(a), RQElem::Dice(b)) => a.value == b.value,
+        (RQElem::FingerGuessing(a), RQElem::FingerGuessing(b)) => a == b,
+        (RQElem::LightApp(a), RQElem::LightApp(b)) => a.content == b.content,
+        (RQElem::RichMsg(a), RQElem::RichMsg(b)) => {
+            a.service_id == b.service_id && a.template1 == b.template1
+        }
+        (RQElem::FriendImage(a), RQElem::FriendImage(b)) => a.md5 == b.md5,
+        (RQElem::GroupImage(a), RQElem::GroupImage(b)) => a.md5 == b.md5,
+        (RQElem::FlashImage(a), RQElem::FlashImage(b)) => match (a, b) {
+            (FlashImage::FriendImage(a), FlashImage::FriendImage(b)) => a.md5 == b.md5,
+            (FlashImage::GroupImage(a), FlashImage::GroupImage(b)) => a.md5 == b.md5,
+            _ => false,
+        },
+        (RQElem::VideoFile(a), RQElem::VideoFile(b)) => a.md5 == b.md5,
+        (RQElem::Other(a), RQElem::Other(b)) => a == b,
+        _ => false,
+    }
+}
@@ -0,0 +1,100 @@
+//! 提取特定元素的辅助方法。
+
+use ricq::msg::elem::{FlashImage, FriendImage, GroupImage, Reply};
+
+use crate::{Message, RQElem};
+
+/// 消息中的图片。
+#[derive(Debug, Clone)]
+pub enum ImageRef {
+    /// 好友图片。
+    Friend(FriendImage),
+    /// 群图片。
+    Group(GroupImage),
+    /// 闪照。
+    Flash(FlashImage),
+}
+
+impl ImageRef {
+    /// 图片下载链接。
+    pub fn url(&self) -> String {
+        match self {
+            ImageRef::Friend(image) | ImageRef::Flash(FlashImage::FriendImage(image)) => {
+                image.url()
+            }
+            ImageRef::Group(image) | ImageRef::Flash(FlashImage::GroupImage(image)) => image.url(),
+        }
+    }
+
+    /// 图片的 md5。
+    pub fn md5(&self) -> &[u8] {
+        match self {
+            ImageRef::Friend(image) | ImageRef::Flash(FlashImage::FriendImage(image)) => &image.md5,
+            ImageRef::Group(image) | ImageRef::Flash(FlashImage::GroupImage(image)) => &image.md5,
+        }
+    }
+
+    /// 是否是闪照。
+    pub fn is_flash(&self) -> bool {
+        matches!(self, ImageRef::Flash(_))
+    }
+}
+
+impl Message {
+    /// 消息中的纯文本，即所有文本元素的拼接，不含 At、表情等元素。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let msg = msg![At::new(12345678), " 你好", Face::new(178), "世界"];
+    /// assert_eq!(msg.text(), " 你好世界");
+    /// ```
+    pub fn text(&self) -> String {
+        self.elems()
+            .filter_map(|elem| match elem {
+                RQElem::Text(text) => Some(text.content),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 消息中被 At 的 QQ 号，按出现顺序排列。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let msg = msg![At::new(12345678), " 和 ", At::new(87654321)];
+    /// assert_eq!(msg.ats(), [12345678, 87654321]);
+    /// ```
+    pub fn ats(&self) -> Vec<i64> {
+        self.elems()
+            .filter_map(|elem| match elem {
+                RQElem::At(at) => Some(at.target),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 消息中的所有图片，包括闪照。
+    pub fn images(&self) -> Vec<ImageRef> {
+        self.elems()
+            .filter_map(|elem| match elem {
+                RQElem::FriendImage(image) => Some(ImageRef::Friend(image)),
+                RQElem::GroupImage(image) => Some(ImageRef::Group(image)),
+                RQElem::FlashImage(image) => Some(ImageRef::Flash(image)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 消息回复的原消息。
+    pub fn first_reply(&self) -> Option<&Reply> {
+        self.reply.as_deref()
+    }
+}
@@ -0,0 +1,143 @@
+//! 合并转发消息。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::msg;
+//! use chocho_msg::forward::{ForwardMessage, ForwardNode};
+//!
+//! let inner = ForwardMessage::new()
+//!     .message(12345678, "小明", 1680000000, msg!["内层消息"]);
+//! let forward = ForwardMessage::new()
+//!     .message(12345678, "小明", 1680000000, msg!["你好"])
+//!     .message(87654321, "小红", 1680000060, "你好呀".to_string())
+//!     .forward(12345678, "小明", 1680000120, inner);
+//!
+//! let nodes: Vec<ForwardNode> = forward.into();
+//! assert_eq!(nodes.len(), 3);
+//! assert!(matches!(&nodes[2], ForwardNode::Forward { nodes, .. } if nodes.len() == 1));
+//! ```
+
+use ricq::structs::{
+    ForwardMessage as RQForwardMessage, ForwardNode as RQForwardNode, MessageNode,
+};
+
+use crate::Message;
+
+/// 合并转发中的一个节点。
+#[derive(Debug, Clone)]
+pub enum ForwardNode {
+    /// 一条消息。
+    Message {
+        /// 发送者 QQ 号。
+        sender_uin: i64,
+        /// 发送者名称。
+        sender_name: String,
+        /// 发送时间。
+        time: i32,
+        /// 消息内容。
+        message: Message,
+    },
+    /// 嵌套的合并转发。
+    Forward {
+        /// 发送者 QQ 号。
+        sender_uin: i64,
+        /// 发送者名称。
+        sender_name: String,
+        /// 发送时间。
+        time: i32,
+        /// 被转发的节点。
+        nodes: Vec<ForwardNode>,
+    },
+}
+
+impl From<ForwardNode> for RQForwardMessage {
+    fn from(node: ForwardNode) -> Self {
+        match node {
+            ForwardNode::Message {
+                sender_uin,
+                sender_name,
+                time,
+                message,
+            } => RQForwardMessage::Message(MessageNode {
+                sender_id: sender_uin,
+                time,
+                sender_name,
+                elements: message.into(),
+            }),
+            ForwardNode::Forward {
+                sender_uin,
+                sender_name,
+                time,
+                nodes,
+            } => RQForwardMessage::Forward(RQForwardNode {
+                sender_id: sender_uin,
+                time,
+                sender_name,
+                nodes: nodes.into_iter().map(Into::into).collect(),
+            }),
+        }
+    }
+}
+
+/// 合并转发消息，由若干 [`ForwardNode`] 组成。
+#[derive(Debug, Clone, Default)]
+pub struct ForwardMessage {
+    /// 转发的节点。
+    pub nodes: Vec<ForwardNode>,
+}
+
+impl ForwardMessage {
+    /// 创建空的合并转发消息。
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 添加一条消息。
+    pub fn message(
+        mut self,
+        sender_uin: i64,
+        sender_name: impl Into<String>,
+        time: i32,
+        message: impl Into<Message>,
+    ) -> Self {
+        self.nodes.push(ForwardNode::Message {
+            sender_uin,
+            sender_name: sender_name.into(),
+            time,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// 添加一条嵌套的合并转发。
+    pub fn forward(
+        mut self,
+        sender_uin: i64,
+        sender_name: impl Into<String>,
+        time: i32,
+        forward: impl Into<Vec<ForwardNode>>,
+    ) -> Self {
+        self.nodes.push(ForwardNode::Forward {
+            sender_uin,
+            sender_name: sender_name.into(),
+            time,
+            nodes: forward.into(),
+        });
+        self
+    }
+}
+
+impl From<ForwardMessage> for Vec<ForwardNode> {
+    fn from(forward: ForwardMessage) -> Self {
+        forward.nodes
+    }
+}
+
+impl FromIterator<ForwardNode> for ForwardMessage {
+    fn from_iter<T: IntoIterator<Item = ForwardNode>>(iter: T) -> Self {
+        Self {
+            nodes: iter.into_iter().collect(),
+        }
+    }
+}
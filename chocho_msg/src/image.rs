@@ -0,0 +1,174 @@
+//! 待上传的图片。
+//!
+//! [`Image`] 是图片的占位元素，可以像其它元素一样放进 [`msg!`](crate::msg) 中，
+//! 发送时（例如 `Group::send`、`Friend::send`）会自动上传并替换为实际的图片元素。
+//! 这样构造消息与发送消息可以分离，构造时不需要客户端。
+//!
+//! 待上传的图片不放在 [`orig_elems`](crate::Message::orig_elems) 中，而是和所在的位置一起单独保存，
+//! 因此不会出现在 [`elems`](crate::Message::elems) 中。未上传前，它在 [`Display`](std::fmt::Display)
+//! 中显示为 `[图片(待上传)]`，不计入 [`text`](crate::Message::text)。
+//! 转换为 [`MessageChain`](ricq::msg::MessageChain) 时，待上传的图片会被丢弃，发送前需要先调用
+//! [`Message::upload_images`]。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::msg;
+//! use chocho_msg::elem::*;
+//!
+//! let png = b"\x89PNG\r\n\x1a\n".to_vec();
+//! let msg = msg!["看图：", Image::from_bytes(png.clone())];
+//! assert_eq!(msg.to_string(), "看图：[图片(待上传)]");
+//! assert!(msg.has_pending_images());
+//! assert_eq!(msg.pending_images(), [Image::from_bytes(png)]);
+//! assert_eq!(msg.elems().count(), 1);
+//! ```
+
+use std::future::Future;
+
+use ricq::msg::MessageElem as OriginMessageElement;
+use ricq_core::pb::msg::CommonElem;
+
+use crate::{Message, RQElem};
+
+/// 占位元素的 `service_type`，QQ 不使用负数，不会与真实的元素冲突。
+const PENDING_IMAGE_SERVICE_TYPE: i32 = -1;
+
+/// 待上传的图片。
+#[derive(Clone, PartialEq, Eq)]
+pub struct Image {
+    /// 图片数据。
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// 从图片数据创建占位元素。
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Self {
+        Self { data: data.into() }
+    }
+
+    /// 原始消息元素是否是占位元素。
+    pub(crate) fn is_pending(elem: &OriginMessageElement) -> bool {
+        matches!(
+            elem,
+            OriginMessageElement::CommonElem(elem)
+                if elem.service_type() == PENDING_IMAGE_SERVICE_TYPE
+        )
+    }
+
+    /// 从占位元素中取出图片，调用前需要用 [`is_pending`](Image::is_pending) 检查。
+    pub(crate) fn from_orig(elem: OriginMessageElement) -> Self {
+        match elem {
+            OriginMessageElement::CommonElem(common) => {
+                Self::from_bytes(common.pb_elem.unwrap_or_default())
+            }
+            _ => unreachable!("not a pending image"),
+        }
+    }
+}
+
+impl std::fmt::Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+impl std::fmt::Display for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[图片(待上传)]")
+    }
+}
+
+/// 转换为占位元素。
+///
+/// 占位元素只用于把图片传给 [`Message::push`] 等方法，放入 [`Message`] 时会立即取出，
+/// 保存为待上传的图片。
+impl From<Image> for RQElem {
+    fn from(image: Image) -> Self {
+        RQElem::Other(Box::new(OriginMessageElement::CommonElem(CommonElem {
+            service_type: Some(PENDING_IMAGE_SERVICE_TYPE),
+            pb_elem: Some(image.data),
+            business_type: None,
+        })))
+    }
+}
+
+/// 消息的内容：消息元素或待上传的图片。
+pub(crate) enum Content<'a> {
+    /// 消息元素。
+    Elem(&'a OriginMessageElement),
+    /// 待上传的图片。
+    Image(&'a Image),
+}
+
+impl Message {
+    /// 消息中是否有待上传的图片。
+    pub fn has_pending_images(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// 消息中所有待上传的图片。
+    pub fn pending_images(&self) -> Vec<Image> {
+        self.pending
+            .iter()
+            .map(|(_, image)| image.clone())
+            .collect()
+    }
+
+    /// 按顺序遍历消息元素和待上传的图片。
+    pub(crate) fn contents(&self) -> Vec<Content<'_>> {
+        let mut pending = self.pending.iter().peekable();
+        let mut contents = vec![];
+        for (index, elem) in self.orig_elems.iter().enumerate() {
+            while let Some((_, image)) = pending.next_if(|(i, _)| *i == index) {
+                contents.push(Content::Image(image));
+            }
+            contents.push(Content::Elem(elem));
+        }
+        contents.extend(pending.map(|(_, image)| Content::Image(image)));
+        contents
+    }
+
+    /// 上传消息中所有待上传的图片。
+    ///
+    /// 按顺序对每张图片调用 `upload`，并将返回的元素插入到图片所在的位置。某张图片上传失败时立即返回错误，
+    /// 已上传的图片保留替换后的结果，其余图片保持不变。
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chocho_msg::{msg, RQElem};
+    /// use chocho_msg::elem::*;
+    ///
+    /// # async fn _f(client: &ricq::Client) -> ricq::RQResult<()> {
+    /// let mut msg = msg![Image::from_bytes(std::fs::read("cat.png")?)];
+    /// msg.upload_images(|data| async move {
+    ///     client.upload_group_image(87654321, &data).await.map(RQElem::from)
+    /// })
+    /// .await?;
+    /// assert!(!msg.has_pending_images());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn upload_images<F, Fut, E>(&mut self, mut upload: F) -> Result<(), E>
+    where
+        F: FnMut(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<RQElem, E>>,
+    {
+        while let Some((_, image)) = self.pending.first() {
+            // 上传失败时图片保持不变，因此先复制一份数据
+            let elem = upload(image.data.clone()).await?;
+            let (index, _) = self.pending.remove(0);
+            let mut uploaded = Message::new();
+            uploaded.push(elem);
+            let len = uploaded.orig_elems.len();
+            self.orig_elems.splice(index..index, uploaded.orig_elems);
+            for (i, _) in &mut self.pending {
+                *i += len;
+            }
+        }
+        Ok(())
+    }
+}
@@ -27,14 +27,34 @@ use ricq::msg::{
     MessageElem as OriginMessageElement, PushElem,
 };
 
+mod cq;
+pub mod entity;
+mod eq;
+mod extract;
+pub mod forward;
+mod image;
 mod macros;
+mod markdown;
+mod onebot;
+mod pretty;
+mod reply;
+mod segment;
+mod serialize;
+pub mod similarity;
+mod split;
 
+pub use extract::ImageRef;
+pub use reply::ReplySource;
+pub use segment::SegmentError;
 pub use ricq::msg::elem::RQElem;
 
 /// 消息元素。
 ///
-/// [`ricq::msg::elem`] 的重新导出。
+/// [`ricq::msg::elem`] 的重新导出，以及 chocho 新增的 [`Markdown`](elem::Markdown)
+/// 和待上传图片的占位元素 [`Image`](elem::Image)。
 pub mod elem {
+    pub use crate::image::Image;
+    pub use crate::markdown::Markdown;
     pub use ricq::msg::elem::*;
 }
 
@@ -51,7 +71,12 @@ pub struct Message {
     /// 匿名消息。
     pub anonymous: Option<Box<Anonymous>>,
     /// 消息元信息。
+    ///
+    /// 元信息不参与显示，转换为 [`MessageChain`](ricq::msg::MessageChain) 时，
+    /// 按添加的顺序排在所有消息元素之后。
     pub meta: Vec<OriginMessageElement>,
+    /// 待上传的图片，以及图片之后第一个消息元素在 `orig_elems` 中的位置，按位置排序。
+    pending: Vec<(usize, image::Image)>,
 }
 
 impl Message {
@@ -69,7 +94,7 @@ impl Message {
 
     /// 遍历消息元素。
     ///
-    /// 此方法会返回消息元素副本上的迭代器。
+    /// 此方法会返回消息元素副本上的迭代器，不包括[待上传的图片](elem::Image)。
     ///
     /// # Examples
     ///
@@ -89,6 +114,7 @@ impl Message {
     /// 遍历消息元素。
     ///
     /// 此方法会清空内部的消息元素，并以迭代器的形式返回所有消息元素。
+    /// [待上传的图片](elem::Image)会被一同清空。
     ///
     /// # Examples
     ///
@@ -102,12 +128,13 @@ impl Message {
     /// assert!(msg.orig_elems.is_empty());
     /// ```
     pub fn drain_elems(&mut self) -> impl Iterator<Item = RQElem> + '_ {
+        self.pending.clear();
         self.orig_elems.drain(..).map(RQElem::from)
     }
 
     /// 遍历消息元素。
     ///
-    /// 此方法消耗消息，并以迭代器的形式返回所有消息元素。
+    /// 此方法消耗消息，并以迭代器的形式返回所有消息元素，不包括[待上传的图片](elem::Image)。
     ///
     /// # Examples
     ///
@@ -127,6 +154,13 @@ impl Message {
     ///
     /// 如果添加的元素与末尾的消息元素都是文本，则会将两个文本合并为一个文本。
     ///
+    /// 骰子和猜拳是特殊的魔法表情，QQ 无法正确显示连续的同类魔法表情，
+    /// 因此连续添加同类的魔法表情时，后添加的会替换先添加的。需要发送多个时，请分为多条消息发送。
+    ///
+    /// [`RQElem::Other`] 中的元素如果是可显示的元素（例如未知类型的 `CommonElem`），
+    /// 会按顺序加入 [`orig_elems`](Message::orig_elems)；否则作为元信息加入 [`meta`](Message::meta)。
+    /// [待上传的图片](elem::Image)单独保存，不加入 `orig_elems`，也不会与前后的文本合并。
+    ///
     /// # Examples
     ///
     /// ```
@@ -136,10 +170,43 @@ impl Message {
     /// msg.push("世界");
     /// assert_eq!(msg.to_string(), "你好世界");
     /// ```
+    ///
+    /// ```
+    /// use chocho_msg::{msg, RQElem};
+    /// use chocho_msg::elem::*;
+    /// use ricq::msg::MessageElem;
+    /// use ricq_core::pb::msg::{CommonElem, GeneralFlags};
+    ///
+    /// // 连续的骰子只保留最后一个
+    /// let mut msg = msg![];
+    /// msg.push(Dice::new(1)).push(Dice::new(3));
+    /// assert_eq!(msg.elems().count(), 1);
+    /// msg.push("再来").push(Dice::new(5));
+    /// assert_eq!(msg.elems().count(), 3);
+    ///
+    /// // 可显示的未知元素保持顺序，元信息放入 meta
+    /// let mut msg = msg!["前"];
+    /// msg.push(RQElem::Other(Box::new(MessageElem::CommonElem(CommonElem {
+    ///     service_type: Some(2),
+    ///     ..Default::default()
+    /// }))));
+    /// msg.push(RQElem::Other(Box::new(MessageElem::GeneralFlags(GeneralFlags::default()))));
+    /// msg.push("后");
+    /// assert_eq!(msg.orig_elems.len(), 3);
+    /// assert!(matches!(msg.orig_elems[1], MessageElem::CommonElem(_)));
+    /// assert_eq!(msg.meta.len(), 1);
+    ///
+    /// // 待上传的图片两侧的文本不合并
+    /// let msg = msg!["前", Image::from_bytes(vec![0; 16]), "后"];
+    /// assert_eq!(msg.orig_elems.len(), 2);
+    /// assert_eq!(msg.to_string(), "前[图片(待上传)]后");
+    /// ```
     pub fn push(&mut self, elem: impl Into<RQElem>) -> &mut Self {
+        let after_image = self.ends_with_image();
         match elem.into() {
             RQElem::Text(text) => {
-                if let Some(OriginMessageElement::Text(last_text)) = self.orig_elems.last_mut()
+                if !after_image
+                    && let Some(OriginMessageElement::Text(last_text)) = self.orig_elems.last_mut()
                     && last_text.attr6_buf().is_empty()
                 {
                     if let Some(last_str) = &mut last_text.str {
@@ -154,19 +221,157 @@ impl Message {
             RQElem::At(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::Face(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::MarketFace(e) => PushElem::push_to(e, &mut self.orig_elems),
-            RQElem::Dice(e) => PushElem::push_to(e, &mut self.orig_elems),
-            RQElem::FingerGuessing(e) => PushElem::push_to(e, &mut self.orig_elems),
+            RQElem::Dice(e) => {
+                if !after_image && matches!(self.last_elem(), Some(RQElem::Dice(_))) {
+                    self.orig_elems.pop();
+                }
+                PushElem::push_to(e, &mut self.orig_elems)
+            }
+            RQElem::FingerGuessing(e) => {
+                if !after_image && matches!(self.last_elem(), Some(RQElem::FingerGuessing(_))) {
+                    self.orig_elems.pop();
+                }
+                PushElem::push_to(e, &mut self.orig_elems)
+            }
             RQElem::LightApp(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::RichMsg(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::FriendImage(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::GroupImage(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::FlashImage(e) => PushElem::push_to(e, &mut self.orig_elems),
             RQElem::VideoFile(e) => PushElem::push_to(e, &mut self.orig_elems),
+            RQElem::Other(e) if image::Image::is_pending(&e) => {
+                let index = self.orig_elems.len();
+                self.pending.push((index, image::Image::from_orig(*e)));
+            }
+            RQElem::Other(e) if is_content_elem(&e) => self.orig_elems.push(*e),
             RQElem::Other(e) => self.meta.push(*e),
         }
         self
     }
 
+    fn last_elem(&self) -> Option<RQElem> {
+        self.orig_elems.last().cloned().map(RQElem::from)
+    }
+
+    /// 消息是否以待上传的图片结尾。
+    fn ends_with_image(&self) -> bool {
+        matches!(self.pending.last(), Some((index, _)) if *index == self.orig_elems.len())
+    }
+
+    /// 在消息开头添加一个消息元素。
+    ///
+    /// 如果添加的元素与开头的消息元素都是文本，则会将两个文本合并为一个文本。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let mut msg = msg!["你好"];
+    /// msg.push_front(" ").push_front(At::new(12345678));
+    /// assert_eq!(msg.to_string(), "[@12345678] 你好");
+    /// assert_eq!(msg.elems().count(), 2);
+    /// ```
+    pub fn push_front(&mut self, elem: impl Into<RQElem>) -> &mut Self {
+        self.insert(0, elem)
+    }
+
+    /// 在消息的指定位置插入一个消息元素。
+    ///
+    /// `index` 是 [`elems`](Message::elems) 中的位置。插入的文本会与前后相邻的文本合并。
+    ///
+    /// # Panics
+    ///
+    /// `index` 大于消息元素的数量时 panic。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let mut msg = msg![At::new(12345678), At::new(87654321)];
+    /// msg.insert(1, " 和 ");
+    /// assert_eq!(msg.to_string(), "[@12345678] 和 [@87654321]");
+    ///
+    /// let mut msg = msg!["你好", Face::new(178), "世界"];
+    /// msg.insert(1, "，");
+    /// assert_eq!(msg.elems().count(), 3);
+    /// ```
+    ///
+    /// ```should_panic
+    /// use chocho_msg::msg;
+    ///
+    /// let mut msg = msg!["你好"];
+    /// msg.insert(2, "世界");
+    /// ```
+    pub fn insert(&mut self, index: usize, elem: impl Into<RQElem>) -> &mut Self {
+        let len = self.orig_elems.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        let tail = self.orig_elems.split_off(index);
+        // 插入位置上的待上传图片排在插入的元素之后
+        let split = self.pending.partition_point(|(i, _)| *i < index);
+        let tail_pending = self.pending.split_off(split);
+        self.push(elem);
+        let added = self.orig_elems.len() - index;
+        self.orig_elems.extend(tail);
+        self.pending
+            .extend(tail_pending.into_iter().map(|(i, image)| (i + added, image)));
+        if added > 0 {
+            // 与后面的文本合并
+            self.merge_text(index);
+        }
+        self
+    }
+
+    /// 如果 `index` 与 `index + 1` 处都是纯文本，并且中间没有待上传的图片，将它们合并。
+    fn merge_text(&mut self, index: usize) {
+        let is_plain_text = |elem: Option<&OriginMessageElement>| match elem {
+            Some(OriginMessageElement::Text(text)) => text.attr6_buf().is_empty(),
+            _ => false,
+        };
+        if !is_plain_text(self.orig_elems.get(index))
+            || !is_plain_text(self.orig_elems.get(index + 1))
+            || self.pending.iter().any(|(i, _)| *i == index + 1)
+        {
+            return;
+        }
+        for (i, _) in &mut self.pending {
+            if *i > index + 1 {
+                *i -= 1;
+            }
+        }
+        let OriginMessageElement::Text(next) = self.orig_elems.remove(index + 1) else {
+            unreachable!()
+        };
+        if let OriginMessageElement::Text(text) = &mut self.orig_elems[index] {
+            text.str
+                .get_or_insert_with(String::new)
+                .push_str(next.str.as_deref().unwrap_or_default());
+        }
+    }
+
+    /// 消息中的纯文本，等同于 [`text`](Message::text)。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let msg = msg![At::new(12345678), " 你好", Face::new(178), "世界"];
+    /// assert_eq!(msg.plain_text(), " 你好世界");
+    /// ```
+    pub fn plain_text(&self) -> String {
+        self.text()
+    }
+
     /// 是否是长消息（含有多张图片的群聊消息）。
     pub fn is_long(&self) -> bool {
         let mut count = 0;
@@ -182,6 +387,22 @@ impl Message {
     }
 }
 
+/// 转换为 [`MessageChain`](ricq::msg::MessageChain)。
+///
+/// [待上传的图片](elem::Image)不会被转换，发送前需要先调用 [`upload_images`](Message::upload_images)。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_msg::msg;
+/// use chocho_msg::elem::*;
+/// use ricq::msg::{MessageChain, MessageElem};
+///
+/// let msg = msg!["看图：", Image::from_bytes(vec![0; 16])];
+/// let chain = MessageChain::from(msg);
+/// assert_eq!(chain.0.len(), 1);
+/// assert!(matches!(chain.0[0], MessageElem::Text(_)));
+/// ```
 impl From<Message> for ricq::msg::MessageChain {
     fn from(msg: Message) -> Self {
         let mut elems = vec![];
@@ -209,15 +430,11 @@ impl From<ricq::msg::MessageChain> for Message {
                 E::SrcMsg(src) => {
                     result.reply = Some(Box::new(Reply::from(src)));
                 }
-                E::Text(_)
-                | E::Face(_)
-                | E::CommonElem(_)
-                | E::MarketFace(_)
-                | E::LightApp(_)
-                | E::RichMsg(_)
-                | E::VideoFile(_)
-                | E::NotOnlineImage(_)
-                | E::CustomFace(_) => {
+                elem if image::Image::is_pending(&elem) => {
+                    let index = result.orig_elems.len();
+                    result.pending.push((index, image::Image::from_orig(elem)));
+                }
+                elem if is_content_elem(&elem) => {
                     result.orig_elems.push(elem);
                 }
                 _ => {
@@ -229,6 +446,23 @@ impl From<ricq::msg::MessageChain> for Message {
     }
 }
 
+/// 是否是参与显示的消息元素，其余元素作为元信息。
+fn is_content_elem(elem: &OriginMessageElement) -> bool {
+    use ricq::msg::MessageElem as E;
+    matches!(
+        elem,
+        E::Text(_)
+            | E::Face(_)
+            | E::CommonElem(_)
+            | E::MarketFace(_)
+            | E::LightApp(_)
+            | E::RichMsg(_)
+            | E::VideoFile(_)
+            | E::NotOnlineImage(_)
+            | E::CustomFace(_)
+    )
+}
+
 impl<E> FromIterator<E> for Message
 where
     RQElem: From<E>,
@@ -264,8 +498,11 @@ impl From<String> for Message {
 
 impl Display for Message {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for elem in self.elems() {
-            elem.fmt(f)?;
+        for content in self.contents() {
+            match content {
+                image::Content::Elem(elem) => RQElem::from(elem.clone()).fmt(f)?,
+                image::Content::Image(image) => image.fmt(f)?,
+            }
         }
         Ok(())
     }
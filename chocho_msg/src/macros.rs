@@ -1,5 +1,11 @@
 /// 快速创建消息。
 ///
+/// 除了逗号分隔的消息元素，还支持以下语法：
+///
+/// - `..iter`：将迭代器中的元素依次展开到消息中；
+/// - `if cond => elem`：仅当 `cond` 为真时插入元素；
+/// - `reply = source`：回复 `source`，参见 [`Message::reply_to`](crate::Message::reply_to)。
+///
 /// # Examples
 ///
 /// ```
@@ -13,13 +19,53 @@
 /// ];
 /// assert_eq!(message.to_string(), "[@12345678]Hello, world![笑哭]");
 /// ```
+///
+/// 展开迭代器与条件插入：
+///
+/// ```
+/// use chocho_msg::msg;
+/// use chocho_msg::elem::*;
+///
+/// let ats = vec![At::new(12345678), At::new(87654321)];
+/// let urgent = true;
+/// let message = msg![
+///     ..ats,
+///     " 开会了",
+///     if urgent => "！",
+///     if !urgent => "。",
+/// ];
+/// assert_eq!(message.to_string(), "[@12345678][@87654321] 开会了！");
+/// ```
 #[macro_export]
 macro_rules! msg {
-    ($($elem: expr),* $(,)?) => {
-        $crate::Message::from_iter([
-            $(
-                $crate::RQElem::from($elem),
-            )*
-        ])
+    ($($tt: tt)*) => {{
+        #[allow(unused_mut)]
+        let mut message = $crate::Message::new();
+        $crate::__msg_push!(message; $($tt)*);
+        message
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msg_push {
+    ($msg: ident;) => {};
+    ($msg: ident; .. $iter: expr $(, $($rest: tt)*)?) => {
+        $msg.extend($iter);
+        $crate::__msg_push!($msg; $($($rest)*)?);
+    };
+    ($msg: ident; reply = $source: expr $(, $($rest: tt)*)?) => {
+        $msg.reply_to(&$source);
+        $crate::__msg_push!($msg; $($($rest)*)?);
+    };
+    ($msg: ident; if $cond: expr => $elem: expr $(, $($rest: tt)*)?) => {
+        if $cond {
+            $msg.push($crate::RQElem::from($elem));
+        }
+        $crate::__msg_push!($msg; $($($rest)*)?);
+    };
+    ($msg: ident; $elem: expr $(, $($rest: tt)*)?) => {
+        $msg.push($crate::RQElem::from($elem));
+        $crate::__msg_push!($msg; $($($rest)*)?);
     };
 }
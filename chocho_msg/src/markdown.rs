@@ -0,0 +1,154 @@
+//! markdown 消息元素。
+//!
+//! markdown 元素以 `service_type` 为 45 的 `CommonElem` 发送，只有部分客户端和接口能够正常显示。
+//! 在 [`Message`] 中，markdown 元素与文本等其它元素一样按添加的顺序排列，添加文本时不会与 markdown 合并。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::msg;
+//! use chocho_msg::elem::*;
+//!
+//! let md = Markdown::new()
+//!     .heading(1, "日报")
+//!     .paragraph("今日**新增**用户 42 人。")
+//!     .list(["活跃群 12 个", "消息 3000 条"])
+//!     .code_block("json", r#"{"ok": true}"#);
+//! let msg = msg!["摘要：", md.clone(), "（完）"];
+//! assert_eq!(msg.elems().count(), 3);
+//! assert_eq!(msg.markdowns(), [md]);
+//! ```
+
+use prost::Message as _;
+use ricq::msg::MessageElem as OriginMessageElement;
+use ricq_core::pb::msg::CommonElem;
+
+use crate::{Message, RQElem};
+
+/// markdown 元素的 `service_type`。
+const MARKDOWN_SERVICE_TYPE: i32 = 45;
+
+/// markdown 消息元素。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Markdown {
+    /// markdown 文本。
+    pub content: String,
+}
+
+impl Markdown {
+    /// 创建空的 markdown 元素。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一个块，块之间以空行分隔。
+    fn block(mut self, block: &str) -> Self {
+        if !self.content.is_empty() {
+            self.content.push_str("\n\n");
+        }
+        self.content.push_str(block);
+        self
+    }
+
+    /// 追加原始的 markdown 文本，不做任何处理。
+    pub fn raw(mut self, content: impl AsRef<str>) -> Self {
+        self.content.push_str(content.as_ref());
+        self
+    }
+
+    /// 追加标题，`level` 取 1 到 6，超出范围时按边界处理。
+    pub fn heading(self, level: usize, text: impl AsRef<str>) -> Self {
+        let level = level.clamp(1, 6);
+        self.block(&format!("{} {}", "#".repeat(level), text.as_ref()))
+    }
+
+    /// 追加段落。
+    pub fn paragraph(self, text: impl AsRef<str>) -> Self {
+        self.block(text.as_ref())
+    }
+
+    /// 追加无序列表。
+    pub fn list<T: AsRef<str>>(self, items: impl IntoIterator<Item = T>) -> Self {
+        let list = items
+            .into_iter()
+            .map(|item| format!("- {}", item.as_ref()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.block(&list)
+    }
+
+    /// 追加代码块。
+    pub fn code_block(self, lang: &str, code: impl AsRef<str>) -> Self {
+        self.block(&format!("```{}\n{}\n```", lang, code.as_ref()))
+    }
+
+    /// 追加引用。
+    pub fn quote(self, text: impl AsRef<str>) -> Self {
+        let quote = text
+            .as_ref()
+            .lines()
+            .map(|line| format!("> {}", line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.block(&quote)
+    }
+
+    /// 从原始消息元素中解析 markdown 元素。
+    fn from_orig(elem: &OriginMessageElement) -> Option<Self> {
+        let OriginMessageElement::CommonElem(elem) = elem else {
+            return None;
+        };
+        if elem.service_type() != MARKDOWN_SERVICE_TYPE {
+            return None;
+        }
+        let pb = pb::MarkdownElem::decode(elem.pb_elem()).ok()?;
+        Some(Self {
+            content: String::from_utf8_lossy(pb.content()).into_owned(),
+        })
+    }
+}
+
+impl From<&str> for Markdown {
+    fn from(content: &str) -> Self {
+        Self {
+            content: content.to_string(),
+        }
+    }
+}
+
+impl From<String> for Markdown {
+    fn from(content: String) -> Self {
+        Self { content }
+    }
+}
+
+impl From<Markdown> for RQElem {
+    fn from(markdown: Markdown) -> Self {
+        let pb = pb::MarkdownElem {
+            content: Some(markdown.content.into_bytes()),
+        };
+        RQElem::Other(Box::new(OriginMessageElement::CommonElem(CommonElem {
+            service_type: Some(MARKDOWN_SERVICE_TYPE),
+            pb_elem: Some(pb.encode_to_vec()),
+            business_type: Some(1),
+        })))
+    }
+}
+
+impl Message {
+    /// 消息中的所有 markdown 元素。
+    pub fn markdowns(&self) -> Vec<Markdown> {
+        self.orig_elems
+            .iter()
+            .filter_map(Markdown::from_orig)
+            .collect()
+    }
+}
+
+mod pb {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MarkdownElem {
+        #[prost(bytes = "vec", optional, tag = "1")]
+        pub content: Option<Vec<u8>>,
+    }
+}
@@ -0,0 +1,113 @@
+//! OneBot v11 消息段的转换。
+//!
+//! 支持 `text`、`at`、`face`、`image` 和 `reply` 段，消息段的含义与 [CQ 码](Message::to_cq_code) 相同。
+//! 输出时参数值均为字符串；解析时参数值可以是字符串或数字。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::Message;
+//! use serde_json::json;
+//!
+//! let segments = json!([
+//!     {"type": "reply", "data": {"id": "42"}},
+//!     {"type": "at", "data": {"qq": 12345678}},
+//!     {"type": "text", "data": {"text": " 你好"}},
+//!     {"type": "face", "data": {"id": "178"}},
+//! ]);
+//! let msg = Message::from_onebot_segments(&segments).unwrap();
+//! assert_eq!(msg.first_reply().unwrap().reply_seq, 42);
+//! assert_eq!(msg.ats(), [12345678]);
+//! assert_eq!(msg.text(), " 你好");
+//! assert_eq!(msg.to_cq_code(), "[CQ:reply,id=42][CQ:at,qq=12345678] 你好[CQ:face,id=178]");
+//!
+//! // 未知的消息段
+//! let err = Message::from_onebot_segments(&json!([{"type": "record", "data": {}}])).unwrap_err();
+//! assert_eq!(err.to_string(), "不支持的消息段：record");
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    segment::{Segment, SegmentError},
+    Message,
+};
+
+impl Message {
+    /// 将消息转换为 OneBot v11 消息段数组。
+    ///
+    /// 图片段的 `file` 为 `{md5}.image`，`url` 为图片的下载地址，闪照额外带有 `"type": "flash"`。
+    /// 不支持的元素会以其文本形式输出为 `text` 段。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::Message;
+    /// use serde_json::json;
+    ///
+    /// let msg = Message::from_cq_code("[CQ:at,qq=all] 开会").unwrap();
+    /// assert_eq!(
+    ///     msg.to_onebot_segments(),
+    ///     json!([
+    ///         {"type": "at", "data": {"qq": "all"}},
+    ///         {"type": "text", "data": {"text": " 开会"}},
+    ///     ])
+    /// );
+    /// ```
+    pub fn to_onebot_segments(&self) -> Value {
+        self.to_segments()
+            .into_iter()
+            .map(|segment| {
+                let data: Map<String, Value> = segment
+                    .params()
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), Value::String(value)))
+                    .collect();
+                json!({ "type": segment.kind(), "data": data })
+            })
+            .collect()
+    }
+
+    /// 从 OneBot v11 消息段数组解析消息。
+    ///
+    /// 图片段优先从 `file`（`{md5}.image` 的形式）中读取 md5，`file` 不符合格式时，尝试从群图片的 `url`
+    /// 中读取。解析结果为引用服务器上已有图片的群图片。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::Message;
+    /// use serde_json::json;
+    ///
+    /// let url = "https://gchat.qpic.cn/gchatpic_new/0/0-0-0123456789ABCDEF0123456789ABCDEF/0?term=2";
+    /// let msg = Message::from_onebot_segments(&json!([
+    ///     {"type": "image", "data": {"file": "image.png", "url": url}},
+    /// ]))
+    /// .unwrap();
+    /// let segments = msg.to_onebot_segments();
+    /// assert_eq!(segments[0]["data"]["file"], "0123456789abcdef0123456789abcdef.image");
+    ///
+    /// assert!(Message::from_onebot_segments(&json!({"type": "text"})).is_err());
+    /// assert!(Message::from_onebot_segments(&json!([{"type": "at", "data": {"qq": "abc"}}])).is_err());
+    /// ```
+    pub fn from_onebot_segments(v: &Value) -> Result<Message, SegmentError> {
+        let mut msg = Message::new();
+        for segment in v.as_array().ok_or(SegmentError::InvalidFormat)? {
+            let kind = segment["type"]
+                .as_str()
+                .ok_or(SegmentError::InvalidFormat)?;
+            let empty = Map::new();
+            let data = match &segment["data"] {
+                Value::Object(data) => data,
+                Value::Null => &empty,
+                _ => return Err(SegmentError::InvalidFormat),
+            };
+            msg.push_segment(Segment::parse(kind, |name| match data.get(name) {
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(Value::Number(n)) => Some(n.to_string()),
+                _ => None,
+            })?);
+        }
+        Ok(msg)
+    }
+}
@@ -0,0 +1,109 @@
+//! 人类可读的调试格式。
+
+use std::fmt::Write;
+
+use ricq::msg::elem::FlashImage;
+
+use crate::{image::Content, Message, RQElem};
+
+/// 调试输出中长文本的最大字符数。
+const MAX_WIDTH: usize = 48;
+
+impl Message {
+    /// 以简洁的多行格式展示消息内容，用于日志和调试。
+    ///
+    /// 与 `{:?}` 相比，此方法只展示每个元素的类型和关键字段：文本完整显示，
+    /// 图片只显示尺寸和链接前缀，不会打印全部字节。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let msg = msg!["你好", At::new(12345678)];
+    /// let pretty = msg.debug_pretty();
+    /// assert!(pretty.starts_with("Message {\n  [0] Text \"你好\"\n  [1] At 12345678"));
+    /// assert!(pretty.ends_with("\n}"));
+    /// ```
+    pub fn debug_pretty(&self) -> String {
+        let mut out = String::from("Message {\n");
+        if let Some(reply) = &self.reply {
+            writeln!(
+                out,
+                "  reply: seq={} sender={} time={}",
+                reply.reply_seq, reply.sender, reply.time
+            )
+            .unwrap();
+        }
+        for (i, content) in self.contents().into_iter().enumerate() {
+            let pretty = match content {
+                Content::Elem(elem) => pretty_elem(&RQElem::from(elem.clone())),
+                Content::Image(image) => format!("Image(待上传) {} 字节", image.data.len()),
+            };
+            writeln!(out, "  [{}] {}", i, pretty).unwrap();
+        }
+        if let Some(anonymous) = &self.anonymous {
+            writeln!(out, "  anonymous: {:?}", anonymous.nick).unwrap();
+        }
+        if !self.meta.is_empty() {
+            writeln!(out, "  meta: {} 个元素", self.meta.len()).unwrap();
+        }
+        out.push('}');
+        out
+    }
+}
+
+fn pretty_elem(elem: &RQElem) -> String {
+    match elem {
+        RQElem::Text(text) => format!("Text {:?}", text.content),
+        RQElem::At(at) => format!("At {} {:?}", at.target, at.display),
+        RQElem::Face(face) => format!("Face {} [{}]", face.index, face.name),
+        RQElem::MarketFace(face) => format!("MarketFace {:?}", face.name),
+        RQElem::Dice(dice) => format!("Dice {}", dice.value),
+        RQElem::FingerGuessing(finger) => format!("FingerGuessing {:?}", finger),
+        RQElem::LightApp(app) => format!("LightApp {}", truncate(&app.content)),
+        RQElem::RichMsg(rich) => format!(
+            "RichMsg service_id={} {}",
+            rich.service_id,
+            truncate(&rich.template1)
+        ),
+        RQElem::FriendImage(image) => format!(
+            "FriendImage {}x{} {}",
+            image.width,
+            image.height,
+            truncate(&image.url())
+        ),
+        RQElem::GroupImage(image) => format!(
+            "GroupImage {}x{} {}",
+            image.width,
+            image.height,
+            truncate(&image.url())
+        ),
+        RQElem::FlashImage(FlashImage::FriendImage(image)) => format!(
+            "FlashImage {}x{} {}",
+            image.width,
+            image.height,
+            truncate(&image.url())
+        ),
+        RQElem::FlashImage(FlashImage::GroupImage(image)) => format!(
+            "FlashImage {}x{} {}",
+            image.width,
+            image.height,
+            truncate(&image.url())
+        ),
+        RQElem::VideoFile(video) => format!("VideoFile {:?} {} 字节", video.name, video.size),
+        RQElem::Other(_) => "Other".to_string(),
+    }
+}
+
+/// 截断过长的文本，超出部分以 `...` 代替。
+fn truncate(s: &str) -> String {
+    if s.chars().count() <= MAX_WIDTH {
+        s.to_string()
+    } else {
+        let mut result: String = s.chars().take(MAX_WIDTH).collect();
+        result.push_str("...");
+        result
+    }
+}
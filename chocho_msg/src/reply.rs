@@ -0,0 +1,119 @@
+//! 回复消息的构造。
+
+use ricq::{
+    client::event::{FriendMessageEvent, GroupMessageEvent, GroupTempMessageEvent},
+    msg::{elem::Reply, MessageChain},
+    structs::{FriendMessage, GroupMessage, GroupTempMessage, MessageReceipt},
+};
+
+use crate::Message;
+
+/// 可以被回复的消息。
+///
+/// 用于 [`Message::reply_to`] 和 [`msg!`](crate::msg) 的 `reply = ...` 语法。
+pub trait ReplySource {
+    /// 构造引用此消息的 [`Reply`]。
+    fn to_reply(&self) -> Reply;
+}
+
+impl ReplySource for Reply {
+    fn to_reply(&self) -> Reply {
+        self.clone()
+    }
+}
+
+impl<T: ReplySource + ?Sized> ReplySource for &T {
+    fn to_reply(&self) -> Reply {
+        (**self).to_reply()
+    }
+}
+
+fn reply(seqs: &[i32], sender: i64, time: i32, elements: &MessageChain) -> Reply {
+    Reply {
+        reply_seq: seqs.first().copied().unwrap_or_default(),
+        sender,
+        time,
+        elements: elements.clone(),
+    }
+}
+
+impl ReplySource for GroupMessage {
+    fn to_reply(&self) -> Reply {
+        reply(&self.seqs, self.from_uin, self.time, &self.elements)
+    }
+}
+
+impl ReplySource for FriendMessage {
+    fn to_reply(&self) -> Reply {
+        reply(&self.seqs, self.from_uin, self.time, &self.elements)
+    }
+}
+
+impl ReplySource for GroupTempMessage {
+    fn to_reply(&self) -> Reply {
+        reply(&self.seqs, self.from_uin, self.time, &self.elements)
+    }
+}
+
+impl ReplySource for GroupMessageEvent {
+    fn to_reply(&self) -> Reply {
+        self.inner.to_reply()
+    }
+}
+
+impl ReplySource for FriendMessageEvent {
+    fn to_reply(&self) -> Reply {
+        self.inner.to_reply()
+    }
+}
+
+impl ReplySource for GroupTempMessageEvent {
+    fn to_reply(&self) -> Reply {
+        self.inner.to_reply()
+    }
+}
+
+impl Message {
+    /// 设置回复的原消息。
+    ///
+    /// # Examples
+    ///
+    /// 在群消息事件中引用原消息回复：
+    ///
+    /// ```no_run
+    /// use chocho_msg::{msg, Message};
+    /// use ricq::client::event::GroupMessageEvent;
+    ///
+    /// # async fn _f(event: GroupMessageEvent) -> ricq::RQResult<()> {
+    /// let mut reply = msg!["收到"];
+    /// reply.reply_to(&event);
+    /// event
+    ///     .client
+    ///     .send_group_message(event.inner.group_code, reply.into())
+    ///     .await?;
+    ///
+    /// // 也可以使用 `msg!` 的 `reply = ...` 语法
+    /// let reply = msg![reply = event, "收到"];
+    /// assert_eq!(reply.first_reply().unwrap().sender, event.inner.from_uin);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reply_to(&mut self, source: impl ReplySource) -> &mut Self {
+        self.reply = Some(Box::new(source.to_reply()));
+        self
+    }
+
+    /// 回复自己发送的消息。
+    ///
+    /// `receipt` 为发送消息时得到的回执，`sender` 为发送者，即机器人自己的 QQ 号。
+    /// 回执中不包含原消息的内容，因此引用中不会显示原消息。
+    pub fn reply_to_receipt(&mut self, receipt: &MessageReceipt, sender: i64) -> &mut Self {
+        self.reply = Some(Box::new(Reply {
+            reply_seq: receipt.seqs.first().copied().unwrap_or_default(),
+            sender,
+            time: receipt.time as i32,
+            elements: MessageChain::new(vec![]),
+        }));
+        self
+    }
+}
@@ -0,0 +1,239 @@
+//! CQ 码与 OneBot 消息段共用的消息段模型。
+//!
+//! [CQ 码](Message::from_cq_code) 和 [OneBot 消息段](Message::from_onebot_segments) 只是同一组消息段的两种写法：
+//! 两者都先将消息转换为 [`Segment`]，再各自处理格式上的差异（转义、参数值的类型等）。
+
+use std::fmt::Display;
+
+use ricq::msg::{
+    elem::{At, Face, FlashImage, Reply, Text},
+    MessageChain, MessageElem as OriginMessageElement,
+};
+
+use crate::{Message, RQElem};
+
+/// 群图片 URL 的域名。
+const GCHAT_HOST: &str = "gchat.qpic.cn";
+
+/// CQ 码或 OneBot 消息段的解析错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SegmentError {
+    /// CQ 段缺少结尾的 `]`。
+    Unclosed,
+    /// 消息段数组或消息段的结构不正确。
+    InvalidFormat,
+    /// 不支持的消息段类型。
+    UnknownSegment(String),
+    /// 缺少参数或参数格式错误。
+    InvalidParam {
+        /// 消息段类型。
+        segment: String,
+        /// 参数名。
+        param: String,
+    },
+}
+
+impl Display for SegmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SegmentError::Unclosed => write!(f, "CQ 段缺少结尾的 `]`"),
+            SegmentError::InvalidFormat => write!(f, "消息段格式错误"),
+            SegmentError::UnknownSegment(kind) => write!(f, "不支持的消息段：{}", kind),
+            SegmentError::InvalidParam { segment, param } => {
+                write!(f, "消息段 {} 的参数 {} 缺失或格式错误", segment, param)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SegmentError {}
+
+/// 消息段。
+pub(crate) enum Segment {
+    /// 纯文本。
+    Text(String),
+    /// @ 某人，`0` 表示 @全体成员。
+    At(i64),
+    /// 表情。
+    Face(i32),
+    /// 图片。
+    Image {
+        /// 图片的 md5。
+        md5: Vec<u8>,
+        /// 图片的下载地址。
+        url: Option<String>,
+        /// 是否是闪照，解析时总是 `false`。
+        flash: bool,
+    },
+    /// 回复。
+    Reply {
+        /// 被回复消息的序号。
+        seq: i32,
+        /// 被回复消息的发送者，未知时为 `0`。
+        sender: i64,
+    },
+}
+
+impl Segment {
+    /// 从消息段类型和参数解析消息段。
+    ///
+    /// `param` 返回参数的值，不同格式的转义、类型转换由调用方处理。
+    pub(crate) fn parse(
+        kind: &str,
+        param: impl Fn(&str) -> Option<String>,
+    ) -> Result<Segment, SegmentError> {
+        let invalid = |name: &str| SegmentError::InvalidParam {
+            segment: kind.to_string(),
+            param: name.to_string(),
+        };
+        let required = |name: &str| param(name).ok_or_else(|| invalid(name));
+
+        Ok(match kind {
+            "text" => Segment::Text(required("text")?),
+            "at" => match required("qq")?.as_str() {
+                "all" => Segment::At(0),
+                qq => Segment::At(qq.parse().map_err(|_| invalid("qq"))?),
+            },
+            "face" => Segment::Face(required("id")?.parse().map_err(|_| invalid("id"))?),
+            "image" => {
+                let url = param("url");
+                // 优先从 `file` 中读取 md5，`file` 不符合格式时，尝试从群图片的 `url` 中读取
+                let md5 = param("file")
+                    .as_deref()
+                    .and_then(md5_from_file)
+                    .or_else(|| url.as_deref().and_then(md5_from_url))
+                    .ok_or_else(|| invalid("file"))?;
+                Segment::Image {
+                    md5,
+                    url,
+                    flash: false,
+                }
+            }
+            "reply" => Segment::Reply {
+                seq: required("id")?.parse().map_err(|_| invalid("id"))?,
+                sender: match param("qq") {
+                    Some(qq) => qq.parse().map_err(|_| invalid("qq"))?,
+                    None => 0,
+                },
+            },
+            other => return Err(SegmentError::UnknownSegment(other.to_string())),
+        })
+    }
+
+    /// 消息段类型。
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Segment::Text(_) => "text",
+            Segment::At(_) => "at",
+            Segment::Face(_) => "face",
+            Segment::Image { .. } => "image",
+            Segment::Reply { .. } => "reply",
+        }
+    }
+
+    /// 消息段的参数，参数值未转义。
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Segment::Text(text) => vec![("text", text.clone())],
+            Segment::At(0) => vec![("qq", "all".to_string())],
+            Segment::At(target) => vec![("qq", target.to_string())],
+            Segment::Face(id) => vec![("id", id.to_string())],
+            Segment::Image { md5, url, flash } => {
+                let mut params = vec![("file", format!("{}.image", hex::encode(md5)))];
+                if let Some(url) = url {
+                    params.push(("url", url.clone()));
+                }
+                if *flash {
+                    params.push(("type", "flash".to_string()));
+                }
+                params
+            }
+            Segment::Reply { seq, .. } => vec![("id", seq.to_string())],
+        }
+    }
+}
+
+impl Message {
+    /// 将消息转换为消息段，不支持的元素以其文本形式转换为纯文本段。
+    pub(crate) fn to_segments(&self) -> Vec<Segment> {
+        let mut segments = vec![];
+        if let Some(reply) = &self.reply {
+            segments.push(Segment::Reply {
+                seq: reply.reply_seq,
+                sender: reply.sender,
+            });
+        }
+        let image = |md5: &[u8], url: String, flash: bool| Segment::Image {
+            md5: md5.to_vec(),
+            url: Some(url),
+            flash,
+        };
+        for elem in self.elems() {
+            segments.push(match elem {
+                RQElem::Text(text) => Segment::Text(text.content),
+                RQElem::At(at) => Segment::At(at.target),
+                RQElem::Face(face) => Segment::Face(face.index),
+                RQElem::FriendImage(img) => image(&img.md5, img.url(), false),
+                RQElem::GroupImage(img) => image(&img.md5, img.url(), false),
+                RQElem::FlashImage(FlashImage::FriendImage(img)) => {
+                    image(&img.md5, img.url(), true)
+                }
+                RQElem::FlashImage(FlashImage::GroupImage(img)) => image(&img.md5, img.url(), true),
+                other => Segment::Text(other.to_string()),
+            });
+        }
+        segments
+    }
+
+    /// 在消息末尾添加消息段。
+    ///
+    /// 图片段解析为引用服务器上已有图片的群图片。
+    pub(crate) fn push_segment(&mut self, segment: Segment) {
+        match segment {
+            Segment::Text(text) if text.is_empty() => {}
+            Segment::Text(text) => self.push(Text::new(text)),
+            Segment::At(0) => self.push(At {
+                target: 0,
+                display: "@全体成员".to_string(),
+            }),
+            Segment::At(target) => self.push(At::new(target)),
+            Segment::Face(id) => self.push(Face::new(id)),
+            Segment::Image { md5, url, .. } => {
+                let orig_url = url
+                    .as_deref()
+                    .and_then(|url| url.split_once(GCHAT_HOST))
+                    .map(|(_, path)| path.to_string());
+                self.orig_elems.push(OriginMessageElement::CustomFace(
+                    ricq_core::pb::msg::CustomFace {
+                        file_path: Some(format!("{}.image", hex::encode(&md5))),
+                        md5: Some(md5),
+                        orig_url,
+                        ..Default::default()
+                    },
+                ));
+            }
+            Segment::Reply { seq, sender } => {
+                self.reply = Some(Box::new(Reply {
+                    reply_seq: seq,
+                    sender,
+                    time: 0,
+                    elements: MessageChain::new(vec![]),
+                }));
+            }
+        }
+    }
+}
+
+/// 从 `{md5}.image` 形式的文件名中读取 md5。
+fn md5_from_file(file: &str) -> Option<Vec<u8>> {
+    let md5 = file.get(..32)?;
+    hex::decode(md5).ok()
+}
+
+/// 从群图片 URL（`.../gchatpic_new/0/0-0-{MD5}/0`）中读取 md5。
+fn md5_from_url(url: &str) -> Option<Vec<u8>> {
+    let (_, path) = url.split_once(GCHAT_HOST)?;
+    path.split(['/', '-'])
+        .find(|part| part.len() == 32)
+        .and_then(|md5| hex::decode(md5).ok())
+}
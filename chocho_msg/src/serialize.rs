@@ -0,0 +1,244 @@
+//! 消息的序列化与反序列化。
+//!
+//! 文本、At、表情以可读的字段序列化，待上传的图片以图片数据的十六进制字符串序列化，
+//! 其他元素以 protobuf 编码的十六进制字符串序列化，保证反序列化后的消息与原消息一致。
+//! 文本、At、表情带有可读字段无法表示的内容时（例如收到的文本中的 `pb_reserve`），
+//! 同样以 protobuf 编码序列化。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::{msg, Message, RQElem};
+//! use chocho_msg::elem::*;
+//!
+//! let image = ricq::msg::MessageElem::CustomFace(ricq_core::pb::msg::CustomFace {
+//!     file_path: Some("{F7D3C8B1-0000-0000-0000-000000000000}.png".to_string()),
+//!     md5: Some(vec![0x12; 16]),
+//!     width: Some(640),
+//!     height: Some(480),
+//!     ..Default::default()
+//! });
+//! let mut msg = msg!["你好", At::new(12345678), Face::new(178)];
+//! msg.push(RQElem::from(image));
+//! msg.push(Image::from_bytes(vec![0x89, 0x50]));
+//!
+//! let json = serde_json::to_string(&msg).unwrap();
+//! assert!(json.contains(r#"{"type":"text","content":"你好"}"#));
+//! assert!(json.contains(r#""type":"at","target":12345678"#));
+//! assert!(json.contains(r#"{"type":"pending_image","data":"8950"}"#));
+//!
+//! let de: Message = serde_json::from_str(&json).unwrap();
+//! assert_eq!(de.to_string(), msg.to_string());
+//! assert_eq!(de.orig_elems, msg.orig_elems);
+//! assert_eq!(de.pending_images(), msg.pending_images());
+//! ```
+//!
+//! 带有额外字段的文本：
+//!
+//! ```
+//! use chocho_msg::{msg, Message};
+//! use ricq::msg::MessageElem;
+//!
+//! let mut msg = msg!["你好"];
+//! if let MessageElem::Text(text) = &mut msg.orig_elems[0] {
+//!     text.pb_reserve = Some(vec![1, 2, 3]);
+//! }
+//!
+//! let json = serde_json::to_string(&msg).unwrap();
+//! assert!(json.contains(r#""type":"raw","kind":"text""#));
+//!
+//! let de: Message = serde_json::from_str(&json).unwrap();
+//! assert_eq!(de.orig_elems, msg.orig_elems);
+//! ```
+
+use prost::Message as _;
+use ricq::msg::{
+    elem::{Anonymous, At, Face, Reply, Text},
+    MessageElem as OriginMessageElement, PushElem,
+};
+use ricq_core::pb::msg::Elem;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    image::{Content, Image},
+    Message, RQElem,
+};
+
+#[derive(Serialize, Deserialize)]
+struct MessageRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reply: Option<RawElem>,
+    elems: Vec<ElemRepr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anonymous: Option<RawElem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    meta: Vec<RawElem>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ElemRepr {
+    Text { content: String },
+    At { target: i64, display: String },
+    Face { index: i32, name: String },
+    PendingImage { data: String },
+    Raw(RawElem),
+}
+
+/// protobuf 编码的元素。`kind` 只用于提高可读性，反序列化时以 `pb` 为准。
+#[derive(Serialize, Deserialize)]
+struct RawElem {
+    kind: String,
+    pb: String,
+}
+
+impl RawElem {
+    fn encode(kind: &str, elem: OriginMessageElement) -> Self {
+        Self {
+            kind: kind.to_string(),
+            pb: hex::encode(Elem { elem: Some(elem) }.encode_to_vec()),
+        }
+    }
+
+    fn decode<E: serde::de::Error>(&self) -> Result<OriginMessageElement, E> {
+        let bytes = hex::decode(&self.pb).map_err(E::custom)?;
+        Elem::decode(bytes.as_slice())
+            .map_err(E::custom)?
+            .elem
+            .ok_or_else(|| E::custom(format!("空的 {} 元素", self.kind)))
+    }
+}
+
+fn elem_kind(elem: &RQElem) -> &'static str {
+    match elem {
+        RQElem::Text(_) => "text",
+        RQElem::At(_) => "at",
+        RQElem::Face(_) => "face",
+        RQElem::MarketFace(_) => "market_face",
+        RQElem::Dice(_) => "dice",
+        RQElem::FingerGuessing(_) => "finger_guessing",
+        RQElem::LightApp(_) => "light_app",
+        RQElem::RichMsg(_) => "rich_msg",
+        RQElem::FriendImage(_) => "friend_image",
+        RQElem::GroupImage(_) => "group_image",
+        RQElem::FlashImage(_) => "flash_image",
+        RQElem::VideoFile(_) => "video_file",
+        RQElem::Other(_) => "other",
+    }
+}
+
+fn elem_repr(orig: &OriginMessageElement) -> ElemRepr {
+    let elem = RQElem::from(orig.clone());
+    let kind = elem_kind(&elem);
+    // 按反序列化的方式重新构造元素，用于检查可读的字段能否还原原始元素
+    let mut rebuilt = vec![];
+    let repr = match elem {
+        RQElem::Text(text) => {
+            Text::new(text.content.clone()).push_to(&mut rebuilt);
+            ElemRepr::Text {
+                content: text.content,
+            }
+        }
+        RQElem::At(at) => {
+            At {
+                target: at.target,
+                display: at.display.clone(),
+            }
+            .push_to(&mut rebuilt);
+            ElemRepr::At {
+                target: at.target,
+                display: at.display,
+            }
+        }
+        RQElem::Face(face) => {
+            Face {
+                index: face.index,
+                name: face.name.clone(),
+            }
+            .push_to(&mut rebuilt);
+            ElemRepr::Face {
+                index: face.index,
+                name: face.name,
+            }
+        }
+        _ => return ElemRepr::Raw(RawElem::encode(kind, orig.clone())),
+    };
+    if rebuilt.len() == 1 && rebuilt[0] == *orig {
+        repr
+    } else {
+        ElemRepr::Raw(RawElem::encode(kind, orig.clone()))
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let elems = self
+            .contents()
+            .into_iter()
+            .map(|content| match content {
+                Content::Elem(orig) => elem_repr(orig),
+                Content::Image(image) => ElemRepr::PendingImage {
+                    data: hex::encode(&image.data),
+                },
+            })
+            .collect();
+        MessageRepr {
+            reply: self
+                .reply
+                .as_ref()
+                .map(|reply| RawElem::encode("reply", (**reply).clone().into())),
+            elems,
+            anonymous: self
+                .anonymous
+                .as_ref()
+                .map(|anonymous| RawElem::encode("anonymous", (**anonymous).clone().into())),
+            meta: self
+                .meta
+                .iter()
+                .map(|elem| RawElem::encode("meta", elem.clone()))
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = MessageRepr::deserialize(deserializer)?;
+        let mut msg = Message::new();
+        if let Some(reply) = repr.reply {
+            match reply.decode()? {
+                OriginMessageElement::SrcMsg(src) => msg.reply = Some(Box::new(Reply::from(src))),
+                _ => return Err(D::Error::custom("reply 不是回复元素")),
+            }
+        }
+        for elem in repr.elems {
+            // 直接写入元素，不合并相邻文本，保持元素结构不变
+            match elem {
+                ElemRepr::Text { content } => Text::new(content).push_to(&mut msg.orig_elems),
+                ElemRepr::At { target, display } => {
+                    At { target, display }.push_to(&mut msg.orig_elems)
+                }
+                ElemRepr::Face { index, name } => Face { index, name }.push_to(&mut msg.orig_elems),
+                ElemRepr::PendingImage { data } => {
+                    msg.push(Image::from_bytes(
+                        hex::decode(data).map_err(D::Error::custom)?,
+                    ));
+                }
+                ElemRepr::Raw(raw) => msg.orig_elems.push(raw.decode()?),
+            }
+        }
+        if let Some(anonymous) = repr.anonymous {
+            match anonymous.decode()? {
+                OriginMessageElement::AnonGroupMsg(anon) => {
+                    msg.anonymous = Some(Box::new(Anonymous::from(anon)))
+                }
+                _ => return Err(D::Error::custom("anonymous 不是匿名元素")),
+            }
+        }
+        for meta in repr.meta {
+            msg.meta.push(meta.decode()?);
+        }
+        Ok(msg)
+    }
+}
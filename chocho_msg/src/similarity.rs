@@ -0,0 +1,130 @@
+//! 文本相似度与模糊匹配。
+//!
+//! 相似度基于编辑距离计算：比较前会转为小写，并忽略空白和标点，
+//! 结果为 `1 - 编辑距离 / 较长文本的字符数`，取值范围 `[0, 1]`。
+//!
+//! # Examples
+//!
+//! ```
+//! use chocho_msg::similarity::text_similarity;
+//!
+//! assert_eq!(text_similarity("你好", "你好！"), 1.0);
+//! assert_eq!(text_similarity("你好", "您好"), 0.5);
+//! assert_eq!(text_similarity("Hello", "hello"), 1.0);
+//! assert_eq!(text_similarity("", ""), 1.0);
+//! assert_eq!(text_similarity("abc", "xyz"), 0.0);
+//! ```
+
+use crate::Message;
+
+fn normalize(text: &str) -> Vec<char> {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// 计算两段文本的相似度，取值范围 `[0, 1]`。
+pub fn text_similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    let len = a.len().max(b.len());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f32 / len as f32
+}
+
+impl Message {
+    /// 计算消息的纯文本与模式的相似度，取值范围 `[0, 1]`。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::At;
+    ///
+    /// let msg = msg![At::new(12345678), " 你好呀"];
+    /// assert!(msg.text_similarity("你好") > 0.6);
+    /// ```
+    pub fn text_similarity(&self, pattern: &str) -> f32 {
+        text_similarity(&self.plain_text(), pattern)
+    }
+}
+
+/// 基于相似度的规则匹配器。
+///
+/// 每条规则包含若干模式，消息与任一模式的相似度达到阈值即视为命中；
+/// 多条规则命中时，返回相似度最高的规则。
+///
+/// # Examples
+///
+/// ```
+/// use chocho_msg::msg;
+/// use chocho_msg::similarity::FuzzyMatcher;
+///
+/// let matcher = FuzzyMatcher::new(0.5)
+///     .rule(["你好", "早上好"], "greet")
+///     .rule(["今天天气怎么样"], "weather");
+///
+/// assert_eq!(matcher.find(&msg!["您好！"]), Some(&"greet"));
+/// assert_eq!(matcher.find(&msg!["今天天气如何"]), Some(&"weather"));
+/// assert_eq!(matcher.find(&msg!["晚安"]), None);
+/// ```
+pub struct FuzzyMatcher<T> {
+    threshold: f32,
+    rules: Vec<(Vec<String>, T)>,
+}
+
+impl<T> FuzzyMatcher<T> {
+    /// 创建匹配器，`threshold` 为命中所需的最低相似度。
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            rules: vec![],
+        }
+    }
+
+    /// 添加一条规则。
+    pub fn rule(mut self, patterns: impl IntoIterator<Item = impl Into<String>>, value: T) -> Self {
+        self.rules
+            .push((patterns.into_iter().map(Into::into).collect(), value));
+        self
+    }
+
+    /// 查找与文本最相似的规则，返回规则的值与相似度。
+    pub fn find_text(&self, text: &str) -> Option<(&T, f32)> {
+        self.rules
+            .iter()
+            .filter_map(|(patterns, value)| {
+                let score = patterns
+                    .iter()
+                    .map(|pattern| text_similarity(text, pattern))
+                    .fold(0.0, f32::max);
+                (score >= self.threshold).then_some((value, score))
+            })
+            .fold(None, |best: Option<(&T, f32)>, (value, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((value, score)),
+            })
+    }
+
+    /// 查找与消息纯文本最相似的规则。
+    pub fn find(&self, message: &Message) -> Option<&T> {
+        self.find_text(&message.plain_text())
+            .map(|(value, _)| value)
+    }
+}
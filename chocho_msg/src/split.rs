@@ -0,0 +1,189 @@
+//! 长消息的长度估算与切分。
+//!
+//! QQ 服务端对消息长度的限制没有公开，并且与消息类型、客户端协议有关，超出限制时发送会失败或被截断。
+//! 这里的估算只适合作为发送前的参考：[`display_len`](Message::display_len) 对应用户看到的长度，
+//! [`byte_len`](Message::byte_len) 对应消息体的大小，判断是否需要切分或使用长消息通道时应预留一定余量。
+
+use prost::Message as _;
+use ricq::msg::MessageElem as OriginMessageElement;
+use ricq_core::pb::msg::Elem;
+
+use crate::{image::Content, Message, RQElem};
+
+impl Message {
+    /// 按显示字符估算消息长度。
+    ///
+    /// 文本按字符计数，其它元素（At、表情、图片等，包括待上传的图片）各计为 1，与 [`split_by_len`](Message::split_by_len) 的计数方式相同。
+    /// 回复、匿名和元信息不计入。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let msg = msg!["你好，", At::new(12345678), Face::new(178)];
+    /// assert_eq!(msg.display_len(), 5);
+    /// ```
+    pub fn display_len(&self) -> usize {
+        self.contents()
+            .into_iter()
+            .map(|content| match content {
+                Content::Elem(elem) => match RQElem::from(elem.clone()) {
+                    RQElem::Text(text) => text.content.chars().count(),
+                    _ => 1,
+                },
+                Content::Image(_) => 1,
+            })
+            .sum()
+    }
+
+    /// 估算消息序列化后的字节数。
+    ///
+    /// 按转换为 [`MessageChain`](ricq::msg::MessageChain) 后，所有元素的 protobuf 编码长度计算，包括回复、匿名和元信息，
+    /// 不包括发送时另外附加的消息头等字段。待上传的图片按图片数据的大小计算，上传后实际的长度会小得多。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    ///
+    /// let short = msg!["你好"];
+    /// let long = msg!["你好".repeat(100)];
+    /// assert!(short.byte_len() > "你好".len());
+    /// assert!(long.byte_len() > short.byte_len());
+    /// ```
+    pub fn byte_len(&self) -> usize {
+        let reply = self
+            .reply
+            .iter()
+            .map(|reply| OriginMessageElement::from((**reply).clone()));
+        let anonymous = self
+            .anonymous
+            .iter()
+            .map(|anonymous| OriginMessageElement::from((**anonymous).clone()));
+        let elems: usize = reply
+            .chain(self.orig_elems.iter().cloned())
+            .chain(anonymous)
+            .chain(self.meta.iter().cloned())
+            .map(|elem| {
+                let len = Elem { elem: Some(elem) }.encoded_len();
+                // repeated 字段中每个元素的 tag 与长度前缀
+                1 + prost::length_delimiter_len(len) + len
+            })
+            .sum();
+        let images: usize = self.pending.iter().map(|(_, image)| image.data.len()).sum();
+        elems + images
+    }
+
+    /// 按文本长度将消息切分为多条消息。
+    ///
+    /// 每条消息的 [`display_len`](Message::display_len) 不超过 `max_len`。
+    /// 文本会优先在换行处切分，放不下时移到下一条消息，单独一条也放不下时才按长度截断。
+    /// At、图片等元素不会被拆开。
+    /// 回复和元信息只保留在第一条消息中，匿名信息保留在每一条消息中。
+    ///
+    /// # Panics
+    ///
+    /// `max_len` 为 0 时 panic。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chocho_msg::msg;
+    /// use chocho_msg::elem::*;
+    ///
+    /// let msg = msg!["第一行\n第二行\n", At::new(12345678), "第三行"];
+    /// let parts = msg.split_by_len(6);
+    /// let parts: Vec<_> = parts.iter().map(|part| part.to_string()).collect();
+    /// assert_eq!(parts, ["第一行\n", "第二行\n[@12345678]", "第三行"]);
+    ///
+    /// // 没有换行时直接按长度切分
+    /// let parts = msg!["一二三四五"].split_by_len(2);
+    /// assert_eq!(parts.len(), 3);
+    /// assert_eq!(parts[2].to_string(), "五");
+    /// ```
+    pub fn split_by_len(&self, max_len: usize) -> Vec<Message> {
+        assert!(max_len > 0, "max_len should be greater than 0");
+
+        let mut splitter = Splitter {
+            template: self,
+            parts: vec![],
+            current: self.new_part(true),
+            current_len: 0,
+            max_len,
+        };
+        for content in self.contents() {
+            let elem = match content {
+                Content::Elem(elem) => RQElem::from(elem.clone()),
+                Content::Image(image) => RQElem::from(image.clone()),
+            };
+            match elem {
+                RQElem::Text(text) => splitter.push_text(&text.content),
+                elem => {
+                    if splitter.current_len + 1 > max_len {
+                        splitter.flush();
+                    }
+                    splitter.current.push(elem);
+                    splitter.current_len += 1;
+                }
+            }
+        }
+        if splitter.current_len > 0 {
+            splitter.flush();
+        }
+        splitter.parts
+    }
+
+    /// 创建切分后的一条空消息，保留匿名信息。
+    fn new_part(&self, first: bool) -> Message {
+        let mut part = Message::new();
+        part.anonymous = self.anonymous.clone();
+        if first {
+            part.reply = self.reply.clone();
+            part.meta = self.meta.clone();
+        }
+        part
+    }
+}
+
+struct Splitter<'a> {
+    template: &'a Message,
+    parts: Vec<Message>,
+    current: Message,
+    current_len: usize,
+    max_len: usize,
+}
+
+impl Splitter<'_> {
+    fn push_text(&mut self, mut text: &str) {
+        while !text.is_empty() {
+            let budget = self.max_len - self.current_len;
+            let Some((end, _)) = text.char_indices().nth(budget) else {
+                // 剩余的文本可以全部放入当前消息
+                self.current.push(text);
+                self.current_len += text.chars().count();
+                return;
+            };
+            // 优先在换行处切分
+            let cut = match text[..end].rfind('\n') {
+                Some(newline) => newline + 1,
+                // 当前消息放不下时，先把文本移到下一条消息
+                None if self.current_len > 0 => 0,
+                None => end,
+            };
+            if cut > 0 {
+                self.current.push(&text[..cut]);
+                self.current_len += text[..cut].chars().count();
+            }
+            text = &text[cut..];
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        let next = self.template.new_part(false);
+        self.parts.push(std::mem::replace(&mut self.current, next));
+        self.current_len = 0;
+    }
+}